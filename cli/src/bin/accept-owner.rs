@@ -0,0 +1,52 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use taker::NFTPool;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Takes over a pool proposed by its owner")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    pending_owner_keypair: String,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let pending_owner_keypair =
+        keypair_of(&Opt::clap().get_matches(), "pending-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pending_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsAcceptOwner {
+            pending_owner: pending_owner_keypair.pubkey(),
+            pool,
+        })
+        .args(taker::instruction::AcceptOwner {})
+        .signer(&pending_owner_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
@@ -5,13 +5,16 @@ use solana_clap_utils::input_parsers::keypair_of;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signer;
 use structopt::StructOpt;
-use taker::NFTBid;
+use taker::{NFTBid, NFTPool};
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
 struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
 
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
     #[structopt(long, env)]
     nft_mint_address: Pubkey,
 
@@ -31,8 +34,11 @@ fn main() -> Result<()> {
         .taker_program_address
         .unwrap_or_else(cli::load_program_from_idl);
 
+    let pool = NFTPool::get_address(&program_id, &opt.pool_id);
+
     let bid_account = NFTBid::get_address(
         &program_id,
+        &pool,
         &opt.nft_mint_address,
         &lender_wallet_keypair.pubkey(),
     );
@@ -47,5 +53,15 @@ fn main() -> Result<()> {
         bid_account, content
     );
 
+    match content.expires_at {
+        0 => println!("The bid never expires"),
+        expires_at => println!("The bid expires at {}", expires_at),
+    }
+    println!(
+        "Loans take between {} and {} DAI from the bid",
+        content.min_amount as f64 / 10f64.powf(9.),
+        content.price as f64 / 10f64.powf(9.)
+    );
+
     Ok(())
 }
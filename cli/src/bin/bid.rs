@@ -1,6 +1,6 @@
 use anchor_client::Client;
 use anyhow::Result;
-use cli::{get_cluster, load_program_from_idl, Keypair};
+use cli::{get_cluster, load_program_from_idl, BidLimitsOpt, Keypair, LoanTermsOpt};
 use solana_clap_utils::input_parsers::keypair_of;
 use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
 use spl_associated_token_account::get_associated_token_address;
@@ -13,6 +13,9 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
 
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
     #[structopt(long, env)]
     lender_wallet_keypair: String,
 
@@ -27,6 +30,16 @@ struct Opt {
 
     #[structopt(long)]
     qty: u64,
+
+    #[structopt(flatten)]
+    terms: LoanTermsOpt,
+
+    #[structopt(flatten)]
+    limits: BidLimitsOpt,
+
+    // moves the DAI into the bid escrow instead of approving the pool
+    #[structopt(long)]
+    escrow: bool,
 }
 
 fn main() -> Result<()> {
@@ -43,7 +56,13 @@ fn main() -> Result<()> {
     let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
     let program = client.program(program_id);
 
-    let pool = NFTPool::get_address(&program.id());
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let bid = NFTBid::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &lender_wallet_keypair.pubkey(),
+    );
 
     let tx = program
         .request()
@@ -52,16 +71,14 @@ fn main() -> Result<()> {
             lender_wallet_account: lender_wallet_keypair.pubkey(),
 
             nft_mint: opt.nft_mint_address,
+            dai_mint: opt.dai_mint_address,
             lender_dai_account: get_associated_token_address(
                 &lender_wallet_keypair.pubkey(),
                 &opt.dai_mint_address,
             ),
 
-            bid_account: NFTBid::get_address(
-                &program_id,
-                &opt.nft_mint_address,
-                &lender_wallet_keypair.pubkey(),
-            ),
+            bid_account: bid,
+            bid_escrow_account: NFTBid::get_escrow_address(&program_id, &bid),
 
             spl_program: spl_token::id(),
             system_program: system_program::id(),
@@ -70,6 +87,10 @@ fn main() -> Result<()> {
         .args(taker::instruction::PlaceBid {
             price: (opt.price * 10f64.powf(9.)) as u64,
             qty: opt.qty,
+            terms: opt.terms.terms()?,
+            escrow: opt.escrow,
+            expires_at: opt.limits.expires_at(),
+            min_amount: opt.limits.min_amount(),
         })
         .signer(&lender_wallet_keypair)
         .send()?;
@@ -1,7 +1,7 @@
 use anchor_client::Client;
 use anchor_client::ClientError as ClientError0;
 use anyhow::Result;
-use cli::{get_cluster, load_program_from_idl, Keypair};
+use cli::{get_cluster, load_allowlist, load_program_from_idl, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
 use solana_clap_utils::input_parsers::pubkey_of;
 use solana_client::{
@@ -10,11 +10,12 @@ use solana_client::{
     rpc_response::RpcSimulateTransactionResult,
 };
 use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
-use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
 use spl_associated_token_account::get_associated_token_address;
+use std::path::PathBuf;
 use structopt::StructOpt;
-use taker::TakerError;
-use taker::{NFTBid, NFTDeposit, NFTPool};
+use taker::CustomError;
+use taker::{allowlist, NFTBid, NFTDeposit, NFTPool};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
@@ -22,6 +23,9 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
 
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
     #[structopt(long, env)]
     borrower_wallet_keypair: String,
 
@@ -42,6 +46,10 @@ struct Opt {
 
     #[structopt(long, env)]
     amount: f64,
+
+    // borrow against the lender's collection bid for this allowlist instead of the NFT bid
+    #[structopt(long, env)]
+    allowlist: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
@@ -61,7 +69,41 @@ fn main() -> Result<()> {
     let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
     let program = client.program(program_id);
 
-    let pool = NFTPool::get_address(&program.id());
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let (bid_account, proof) = match &opt.allowlist {
+        Some(path) => {
+            let nft_mints = load_allowlist(path)?;
+            let proof = allowlist::proof(&nft_mints, &opt.nft_mint_address)
+                .ok_or_else(|| anyhow::anyhow!("The NFT is not in the allowlist"))?;
+            let bid_account = NFTBid::get_collection_address(
+                &program_id,
+                &pool,
+                &allowlist::root(&nft_mints),
+                &lender_wallet_address,
+            );
+            (bid_account, proof)
+        }
+        None => {
+            let bid_account = NFTBid::get_address(
+                &program_id,
+                &pool,
+                &opt.nft_mint_address,
+                &lender_wallet_address,
+            );
+            (bid_account, vec![])
+        }
+    };
+
+    let deposit = NFTDeposit::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &borrower_wallet_keypair.pubkey(),
+        &opt.deposit_id,
+    );
+    let loan_note_mint =
+        NFTDeposit::get_loan_note_address(&program_id, &deposit, &lender_wallet_address);
 
     let resp = program
         .request()
@@ -87,23 +129,25 @@ fn main() -> Result<()> {
             ),
             pool_tai_account: get_associated_token_address(&pool, &opt.tai_mint_address),
 
-            deposit_account: NFTDeposit::get_address(
-                &program_id,
-                &opt.nft_mint_address,
-                &borrower_wallet_keypair.pubkey(),
-                &opt.deposit_id,
-            ),
-            bid_account: NFTBid::get_address(
-                &program_id,
-                &opt.nft_mint_address,
+            deposit_account: deposit,
+            bid_account,
+            bid_escrow_account: NFTBid::get_escrow_address(&program_id, &bid_account),
+
+            loan_note_mint,
+            lender_note_account: get_associated_token_address(
                 &lender_wallet_address,
+                &loan_note_mint,
             ),
 
+            ata_program: spl_associated_token_account::id(),
             spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
             clock: sysvar::clock::id(),
         })
         .args(taker::instruction::Borrow {
             amount: (opt.amount * 10f64.powf(9.)) as u64,
+            proof,
         })
         .signer(&borrower_wallet_keypair)
         .send();
@@ -128,7 +172,7 @@ fn main() -> Result<()> {
                 }),
             ..
         })) => {
-            println!("Error: {}", TakerError::from_code(code));
+            println!("Error: {}", CustomError::from(code));
         }
         Err(e) => println!("{:?}", e),
     };
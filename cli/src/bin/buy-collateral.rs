@@ -0,0 +1,97 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use taker::{NFTDeposit, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Buys the NFT of an auctioned loan")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    buyer_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: Pubkey,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: Pubkey,
+
+    // the most DAI the buyer accepts to pay, the price falls while the auction runs
+    #[structopt(long)]
+    max_price: f64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let buyer_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "buyer-wallet-keypair").unwrap();
+    let buyer = buyer_wallet_keypair.pubkey();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&buyer_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsBuyCollateral {
+            pool,
+            buyer_wallet_account: buyer,
+
+            pool_fee_vault: NFTPool::get_fee_vault_address(&program_id, &pool),
+            buyer_dai_account: dbg!(get_associated_token_address(&buyer, &opt.dai_mint_address)),
+            borrower_dai_account: dbg!(get_associated_token_address(
+                &opt.borrower_wallet_address,
+                &opt.dai_mint_address
+            )),
+            pool_dai_account: dbg!(get_associated_token_address(&pool, &opt.dai_mint_address)),
+
+            nft_mint: opt.nft_mint_address,
+            buyer_nft_account: dbg!(get_associated_token_address(&buyer, &opt.nft_mint_address)),
+            pool_nft_account: dbg!(get_associated_token_address(&pool, &opt.nft_mint_address)),
+
+            deposit_account: dbg!(NFTDeposit::get_address(
+                &program_id,
+                &pool,
+                &opt.nft_mint_address,
+                &opt.borrower_wallet_address,
+                &opt.deposit_id,
+            )),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(taker::instruction::BuyCollateral {
+            max_price: (opt.max_price * 10f64.powf(9.)) as u64,
+        })
+        .signer(&buyer_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -5,7 +5,7 @@ use solana_clap_utils::input_parsers::keypair_of;
 use solana_sdk::{pubkey::Pubkey, signature::Signer};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
-use taker::NFTBid;
+use taker::{NFTBid, NFTPool};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
@@ -13,6 +13,9 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
 
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
     #[structopt(long, env)]
     lender_wallet_keypair: String,
 
@@ -37,9 +40,18 @@ fn main() -> Result<()> {
     let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
     let program = client.program(program_id);
 
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let bid = NFTBid::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &lender_wallet_keypair.pubkey(),
+    );
+
     let tx = program
         .request()
         .accounts(taker::accounts::AccountsCancelBid {
+            pool,
             lender_wallet_account: lender_wallet_keypair.pubkey(),
 
             nft_mint: opt.nft_mint_address,
@@ -48,11 +60,8 @@ fn main() -> Result<()> {
                 &opt.dai_mint_address
             )),
 
-            bid_account: dbg!(NFTBid::get_address(
-                &program_id,
-                &opt.nft_mint_address,
-                &lender_wallet_keypair.pubkey(),
-            )),
+            bid_account: dbg!(bid),
+            bid_escrow_account: NFTBid::get_escrow_address(&program_id, &bid),
 
             spl_program: spl_token::id(),
         })
@@ -0,0 +1,80 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_allowlist, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use spl_associated_token_account::get_associated_token_address;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use taker::{allowlist, NFTBid, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    lender_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    // file with the NFT mints of the collection, one per line
+    #[structopt(long, env)]
+    allowlist: PathBuf,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let lender_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let allowlist_root = allowlist::root(&load_allowlist(&opt.allowlist)?);
+    let bid = NFTBid::get_collection_address(
+        &program_id,
+        &pool,
+        &allowlist_root,
+        &lender_wallet_keypair.pubkey(),
+    );
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsCancelCollectionBid {
+            pool,
+            lender_wallet_account: lender_wallet_keypair.pubkey(),
+
+            lender_dai_account: get_associated_token_address(
+                &lender_wallet_keypair.pubkey(),
+                &opt.dai_mint_address,
+            ),
+
+            bid_account: bid,
+            bid_escrow_account: NFTBid::get_escrow_address(&program_id, &bid),
+
+            spl_program: spl_token::id(),
+        })
+        .args(taker::instruction::CancelCollectionBid {
+            allowlist_root,
+            revoke: true,
+        })
+        .signer(&lender_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
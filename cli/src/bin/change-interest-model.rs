@@ -0,0 +1,109 @@
+use anchor_client::Client;
+use anyhow::{anyhow, Result};
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use taker::{InterestCurve, KinkedInterest, NFTPool, SettingsChange};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    settings_admin_keypair: String,
+
+    // queues the change, for pools with a settings delay, see change_loan_settings execute
+    #[structopt(long)]
+    propose: bool,
+
+    // simple, daily-compound or kinked
+    #[structopt(long, env)]
+    interest_model: String,
+
+    // utilization of the pool in bp where the rate of the loan applies, kinked only
+    #[structopt(long, env)]
+    kink: Option<u16>,
+
+    // in bp of the rate of the loan, added at 100% utilization, kinked only
+    #[structopt(long, env)]
+    jump_rate: Option<u16>,
+}
+
+impl Opt {
+    fn interest_model(&self) -> Result<InterestCurve> {
+        match (self.interest_model.as_str(), self.kink, self.jump_rate) {
+            ("simple", None, None) => Ok(InterestCurve::Simple),
+            ("daily-compound", None, None) => Ok(InterestCurve::DailyCompound),
+            ("kinked", Some(kink), Some(jump_rate)) => {
+                Ok(InterestCurve::Kinked(KinkedInterest { kink, jump_rate }))
+            }
+            ("kinked", _, _) => Err(anyhow!("the kinked model needs --kink and --jump-rate")),
+            (model, _, _) => Err(anyhow!("unexpected options for the {} model", model)),
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+    println!("program_id: {}", program_id);
+
+    let interest_model = opt.interest_model()?;
+    let settings_admin_keypair =
+        keypair_of(&Opt::clap().get_matches(), "settings-admin-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&settings_admin_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let request = if opt.propose {
+        program
+            .request()
+            .accounts(taker::accounts::AccountsProposeSettingsChange {
+                settings_admin: settings_admin_keypair.pubkey(),
+                pool,
+                settings_change: SettingsChange::get_address(&program.id(), &pool),
+                clock: sysvar::clock::id(),
+                rent: sysvar::rent::id(),
+                system_program: system_program::id(),
+            })
+            .args(taker::instruction::ProposeSettingsChange {
+                incentive: None,
+                interest_rate: None,
+                service_fee_rate: None,
+                max_loan_duration: None,
+                mortgage_rate: None,
+                grace_period: None,
+                grace_penalty_rate: None,
+                auction_duration: None,
+                auction_start_rate: None,
+                interest_model: Some(interest_model),
+            })
+    } else {
+        program
+            .request()
+            .accounts(taker::accounts::AccountsChangeLoanSetting {
+                settings_admin: settings_admin_keypair.pubkey(),
+                pool,
+                clock: sysvar::clock::id(),
+            })
+            .args(taker::instruction::ChangeInterestModel { interest_model })
+    };
+    let tx = request.signer(&settings_admin_keypair).send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
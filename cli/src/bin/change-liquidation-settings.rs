@@ -0,0 +1,101 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use taker::{NFTPool, SettingsChange};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    settings_admin_keypair: String,
+
+    // queues the change, for pools with a settings delay, see change_loan_settings execute
+    #[structopt(long)]
+    propose: bool,
+
+    // seconds after the expiry during which the borrower can still repay
+    #[structopt(long, env)]
+    grace_period: Option<i64>,
+
+    #[structopt(long, env)]
+    grace_penalty_rate: Option<u64>,
+
+    // 0 disables the auction, expired NFTs go straight to the lender
+    #[structopt(long, env)]
+    auction_duration: Option<i64>,
+
+    #[structopt(long, env)]
+    auction_start_rate: Option<u64>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+    println!("program_id: {}", program_id);
+
+    let settings_admin_keypair =
+        keypair_of(&Opt::clap().get_matches(), "settings-admin-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&settings_admin_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let request = if opt.propose {
+        program
+            .request()
+            .accounts(taker::accounts::AccountsProposeSettingsChange {
+                settings_admin: settings_admin_keypair.pubkey(),
+                pool,
+                settings_change: SettingsChange::get_address(&program.id(), &pool),
+                clock: sysvar::clock::id(),
+                rent: sysvar::rent::id(),
+                system_program: system_program::id(),
+            })
+            .args(taker::instruction::ProposeSettingsChange {
+                incentive: None,
+                interest_rate: None,
+                service_fee_rate: None,
+                max_loan_duration: None,
+                mortgage_rate: None,
+                grace_period: opt.grace_period,
+                grace_penalty_rate: opt.grace_penalty_rate,
+                auction_duration: opt.auction_duration,
+                auction_start_rate: opt.auction_start_rate,
+                interest_model: None,
+            })
+    } else {
+        program
+            .request()
+            .accounts(taker::accounts::AccountsChangeLoanSetting {
+                settings_admin: settings_admin_keypair.pubkey(),
+                pool,
+                clock: sysvar::clock::id(),
+            })
+            .args(taker::instruction::ChangeLiquidationSettings {
+                grace_period: opt.grace_period,
+                grace_penalty_rate: opt.grace_penalty_rate,
+                auction_duration: opt.auction_duration,
+                auction_start_rate: opt.auction_start_rate,
+            })
+    };
+    let tx = request.signer(&settings_admin_keypair).send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use taker::NFTPool;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the wallet allowed to sweep the service fees, see collect-fees
+    #[structopt(long)]
+    fee_recipient: Option<Pubkey>,
+
+    #[structopt(long)]
+    settings_admin: Option<Pubkey>,
+
+    #[structopt(long)]
+    pauser: Option<Pubkey>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsPoolOwner {
+            owner: pool_owner_keypair.pubkey(),
+            pool,
+        })
+        .args(taker::instruction::ChangeRoles {
+            fee_recipient: opt.fee_recipient,
+            settings_admin: opt.settings_admin,
+            pauser: opt.pauser,
+        })
+        .signer(&pool_owner_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use taker::{NFTPool, SettingsCaps, SettingsChange};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    // the caps left out are set to the bounds of the protocol
+    #[structopt(long)]
+    max_incentive: Option<u64>,
+
+    #[structopt(long)]
+    max_interest_rate: Option<u64>,
+
+    #[structopt(long)]
+    max_service_fee_rate: Option<u64>,
+
+    #[structopt(long)]
+    max_loan_duration: Option<i64>,
+
+    #[structopt(long)]
+    max_mortgage_rate: Option<u64>,
+
+    #[structopt(long)]
+    max_grace_penalty_rate: Option<u64>,
+
+    // of the kinked interest model
+    #[structopt(long)]
+    max_jump_rate: Option<u16>,
+
+    // secs between the announcement of new loan settings and when they can be applied
+    #[structopt(long, default_value = "0")]
+    settings_delay: i64,
+
+    // queue the change for the current settings delay, needed to raise a cap or lower the delay
+    #[structopt(long)]
+    propose: bool,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let unbounded = SettingsCaps::UNBOUNDED;
+    let settings_caps = SettingsCaps {
+        max_incentive: opt.max_incentive.unwrap_or(unbounded.max_incentive),
+        max_interest_rate: opt.max_interest_rate.unwrap_or(unbounded.max_interest_rate),
+        max_service_fee_rate: opt
+            .max_service_fee_rate
+            .unwrap_or(unbounded.max_service_fee_rate),
+        max_loan_duration: opt.max_loan_duration.unwrap_or(unbounded.max_loan_duration),
+        max_mortgage_rate: opt.max_mortgage_rate.unwrap_or(unbounded.max_mortgage_rate),
+        max_grace_penalty_rate: opt
+            .max_grace_penalty_rate
+            .unwrap_or(unbounded.max_grace_penalty_rate),
+        max_jump_rate: opt.max_jump_rate.unwrap_or(unbounded.max_jump_rate),
+    };
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let request = if opt.propose {
+        program
+            .request()
+            .accounts(taker::accounts::AccountsProposeCapsChange {
+                owner: pool_owner_keypair.pubkey(),
+                pool,
+                settings_change: SettingsChange::get_address(&program_id, &pool),
+                clock: sysvar::clock::id(),
+                rent: sysvar::rent::id(),
+                system_program: system_program::id(),
+            })
+            .args(taker::instruction::ProposeCapsChange {
+                settings_caps,
+                settings_delay: opt.settings_delay,
+            })
+    } else {
+        program
+            .request()
+            .accounts(taker::accounts::AccountsPoolOwner {
+                owner: pool_owner_keypair.pubkey(),
+                pool,
+            })
+            .args(taker::instruction::ChangeSettingsCaps {
+                settings_caps,
+                settings_delay: opt.settings_delay,
+            })
+    };
+    let tx = request.signer(&pool_owner_keypair).send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
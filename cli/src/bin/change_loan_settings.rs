@@ -2,9 +2,9 @@ use anchor_client::Client;
 use anyhow::Result;
 use cli::{get_cluster, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
-use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
 use structopt::StructOpt;
-use taker::NFTPool;
+use taker::{NFTPool, SettingsChange};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
@@ -13,8 +13,30 @@ struct Opt {
     taker_program_address: Option<Pubkey>,
 
     #[structopt(long, env)]
-    pool_owner_keypair: String,
+    pool_id: Pubkey,
 
+    // the settings admin to set or propose, the owner or the settings admin to cancel,
+    // any wallet paying the transaction to execute
+    #[structopt(long, env)]
+    authority_keypair: String,
+
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    // Applies the settings right away, for pools without a settings delay
+    Set(Settings),
+    // Queues the settings until the settings delay of the pool is over
+    Propose(Settings),
+    Cancel,
+    // Applies the queued settings once their delay is over
+    Execute,
+}
+
+#[derive(Debug, StructOpt)]
+struct Settings {
     #[structopt(long, env)]
     incentive: Option<u64>,
 
@@ -40,31 +62,83 @@ fn main() -> Result<()> {
         .unwrap_or_else(cli::load_program_from_idl);
     println!("program_id: {}", program_id);
 
-    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+    let authority_keypair = keypair_of(&Opt::clap().get_matches(), "authority-keypair").unwrap();
 
-    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let client = Client::new(get_cluster(), Keypair::copy(&authority_keypair));
     let program = client.program(program_id);
 
-    let pool = NFTPool::get_address(&program.id());
-
-    let tx = program
-        .request()
-        .accounts(taker::accounts::AccountsChangeLoanSetting {
-            owner: pool_owner_keypair.pubkey(),
-            pool,
-        })
-        .args(taker::instruction::ChangeLoanSettings {
-            incentive: opt.incentive,
-            interest_rate: opt.interest_rate,
-            service_fee_rate: opt.service_fee_rate,
-            max_loan_duration: opt.max_loan_duration,
-            mortgage_rate: opt.mortgage_rate,
-        })
-        .signer(&pool_owner_keypair)
-        .send()?;
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let settings_change = SettingsChange::get_address(&program.id(), &pool);
+
+    let request = match opt.cmd {
+        Command::Set(settings) => program
+            .request()
+            .accounts(taker::accounts::AccountsChangeLoanSetting {
+                settings_admin: authority_keypair.pubkey(),
+                pool,
+                clock: sysvar::clock::id(),
+            })
+            .args(taker::instruction::ChangeLoanSettings {
+                incentive: settings.incentive,
+                interest_rate: settings.interest_rate,
+                service_fee_rate: settings.service_fee_rate,
+                max_loan_duration: settings.max_loan_duration,
+                mortgage_rate: settings.mortgage_rate,
+            }),
+        Command::Propose(settings) => program
+            .request()
+            .accounts(taker::accounts::AccountsProposeSettingsChange {
+                settings_admin: authority_keypair.pubkey(),
+                pool,
+                settings_change,
+                clock: sysvar::clock::id(),
+                rent: sysvar::rent::id(),
+                system_program: system_program::id(),
+            })
+            .args(taker::instruction::ProposeSettingsChange {
+                incentive: settings.incentive,
+                interest_rate: settings.interest_rate,
+                service_fee_rate: settings.service_fee_rate,
+                max_loan_duration: settings.max_loan_duration,
+                mortgage_rate: settings.mortgage_rate,
+                grace_period: None,
+                grace_penalty_rate: None,
+                auction_duration: None,
+                auction_start_rate: None,
+                interest_model: None,
+            }),
+        Command::Cancel => {
+            let content: SettingsChange = program.account(settings_change)?;
+            program
+                .request()
+                .accounts(taker::accounts::AccountsCancelSettingsChange {
+                    authority: authority_keypair.pubkey(),
+                    pool,
+                    settings_change,
+                    proposer: content.proposer(),
+                })
+                .args(taker::instruction::CancelSettingsChange {})
+        }
+        Command::Execute => {
+            let content: SettingsChange = program.account(settings_change)?;
+            println!("Effective at: {}", content.effective_at());
+            program
+                .request()
+                .accounts(taker::accounts::AccountsExecuteSettingsChange {
+                    pool,
+                    settings_change,
+                    proposer: content.proposer(),
+                    clock: sysvar::clock::id(),
+                })
+                .args(taker::instruction::ExecuteSettingsChange {})
+        }
+    };
+
+    let tx = request.signer(&authority_keypair).send()?;
 
     println!("The transaction is {}", tx);
     println!("Pool address: {}", pool);
+    println!("Settings change address: {}", settings_change);
 
     Ok(())
 }
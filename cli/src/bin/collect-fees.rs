@@ -0,0 +1,70 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use taker::NFTPool;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "transact",
+    about = "Sweeps the service fees accrued in the fee vault of the pool"
+)]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    fee_recipient_keypair: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let fee_recipient_keypair =
+        keypair_of(&Opt::clap().get_matches(), "fee-recipient-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&fee_recipient_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsCollectFees {
+            fee_recipient: fee_recipient_keypair.pubkey(),
+            pool,
+            pool_fee_vault: NFTPool::get_fee_vault_address(&program_id, &pool),
+            fee_recipient_dai_account: get_associated_token_address(
+                &fee_recipient_keypair.pubkey(),
+                &opt.dai_mint_address,
+            ),
+            spl_program: spl_token::id(),
+        })
+        .args(taker::instruction::CollectFees {})
+        .signer(&fee_recipient_keypair)
+        .send()?;
+
+    let content: NFTPool = program.account(pool)?;
+
+    println!("The transaction is {}", tx);
+    println!(
+        "Fees accrued: {}, collected: {}",
+        content.fees_accrued, content.fees_collected
+    );
+
+    Ok(())
+}
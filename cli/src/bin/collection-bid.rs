@@ -0,0 +1,106 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{
+    get_cluster, load_allowlist, load_program_from_idl, BidLimitsOpt, Keypair, LoanTermsOpt,
+};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use taker::{allowlist, NFTBid, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    lender_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    // file with the NFT mints of the collection, one per line
+    #[structopt(long, env)]
+    allowlist: PathBuf,
+
+    #[structopt(long)]
+    price: f64,
+
+    #[structopt(long)]
+    qty: u64,
+
+    #[structopt(flatten)]
+    terms: LoanTermsOpt,
+
+    #[structopt(flatten)]
+    limits: BidLimitsOpt,
+
+    // moves the DAI into the bid escrow instead of approving the pool
+    #[structopt(long)]
+    escrow: bool,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let lender_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let allowlist_root = allowlist::root(&load_allowlist(&opt.allowlist)?);
+    let bid = NFTBid::get_collection_address(
+        &program_id,
+        &pool,
+        &allowlist_root,
+        &lender_wallet_keypair.pubkey(),
+    );
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsPlaceCollectionBid {
+            pool,
+            lender_wallet_account: lender_wallet_keypair.pubkey(),
+
+            dai_mint: opt.dai_mint_address,
+            lender_dai_account: get_associated_token_address(
+                &lender_wallet_keypair.pubkey(),
+                &opt.dai_mint_address,
+            ),
+
+            bid_account: bid,
+            bid_escrow_account: NFTBid::get_escrow_address(&program_id, &bid),
+
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        })
+        .args(taker::instruction::PlaceCollectionBid {
+            allowlist_root,
+            price: (opt.price * 10f64.powf(9.)) as u64,
+            qty: opt.qty,
+            terms: opt.terms.terms()?,
+            escrow: opt.escrow,
+            expires_at: opt.limits.expires_at(),
+            min_amount: opt.limits.min_amount(),
+        })
+        .signer(&lender_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -5,7 +5,7 @@ use rand::rngs::OsRng;
 use solana_clap_utils::input_parsers::pubkey_of;
 use solana_sdk::{pubkey::Pubkey, signature::Keypair};
 use structopt::StructOpt;
-use taker::NFTDeposit;
+use taker::{NFTDeposit, NFTPool};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
@@ -13,6 +13,9 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
 
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
     #[structopt(long, env)]
     nft_mint_address: Pubkey,
 
@@ -34,8 +37,11 @@ fn main() -> Result<()> {
     let borrower_wallet_address =
         pubkey_of(&Opt::clap().get_matches(), "borrower-wallet-address").unwrap();
 
+    let pool = NFTPool::get_address(&program_id, &opt.pool_id);
+
     let deposit_account = NFTDeposit::get_address(
         &program_id,
+        &pool,
         &opt.nft_mint_address,
         &borrower_wallet_address,
         &opt.deposit_id,
@@ -14,6 +14,9 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
 
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
     #[structopt(long, env)]
     borrower_wallet_keypair: String,
 
@@ -38,7 +41,7 @@ fn main() -> Result<()> {
     let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
     let program = client.program(program_id);
 
-    let pool = NFTPool::get_address(&program.id());
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
 
     let deposit_id = solana_sdk::signature::Keypair::generate(&mut OsRng).pubkey();
 
@@ -53,7 +56,7 @@ fn main() -> Result<()> {
 
             borrower_nft_account: get_associated_token_address(
                 &borrower_wallet_keypair.pubkey(),
-                &opt.nft_mint_address
+                &opt.nft_mint_address,
             ),
             pool_nft_account: get_associated_token_address(&pool, &opt.nft_mint_address),
 
@@ -65,6 +68,7 @@ fn main() -> Result<()> {
 
             deposit_account: NFTDeposit::get_address(
                 &program_id,
+                &pool,
                 &opt.nft_mint_address,
                 &borrower_wallet_keypair.pubkey(),
                 &deposit_id,
@@ -0,0 +1,140 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_allowlist, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_clap_utils::input_parsers::pubkey_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use taker::{allowlist, NFTBid, NFTDeposit, NFTPool};
+
+// Extends a loan as allowed by the `max_extension` of the lender's bid
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    borrower_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    lender_wallet_address: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: Pubkey,
+
+    // secs to push the expiry of the loan by
+    #[structopt(long, env)]
+    extension: i64,
+
+    // the loan was taken against the lender's collection bid for this allowlist
+    #[structopt(long, env)]
+    allowlist: Option<PathBuf>,
+
+    // the lender the loan note was issued to, when the note was bought from it
+    #[structopt(long, env)]
+    note_lender: Option<Pubkey>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let borrower_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "borrower-wallet-keypair").unwrap();
+    let lender_wallet_address =
+        pubkey_of(&Opt::clap().get_matches(), "lender-wallet-address").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let deposit = NFTDeposit::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &borrower_wallet_keypair.pubkey(),
+        &opt.deposit_id,
+    );
+    let note_lender = opt.note_lender.unwrap_or(lender_wallet_address);
+    let loan_note_mint = NFTDeposit::get_loan_note_address(&program_id, &deposit, &note_lender);
+
+    let (bid_account, proof) = match &opt.allowlist {
+        Some(path) => {
+            let nft_mints = load_allowlist(path)?;
+            let proof = allowlist::proof(&nft_mints, &opt.nft_mint_address)
+                .ok_or_else(|| anyhow::anyhow!("The NFT is not in the allowlist"))?;
+            let bid_account = NFTBid::get_collection_address(
+                &program_id,
+                &pool,
+                &allowlist::root(&nft_mints),
+                &lender_wallet_address,
+            );
+            (bid_account, proof)
+        }
+        None => {
+            let bid_account = NFTBid::get_address(
+                &program_id,
+                &pool,
+                &opt.nft_mint_address,
+                &lender_wallet_address,
+            );
+            (bid_account, vec![])
+        }
+    };
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsExtendLoan {
+            pool,
+            borrower_wallet_account: borrower_wallet_keypair.pubkey(),
+            lender_wallet_account: lender_wallet_address,
+
+            pool_fee_vault: NFTPool::get_fee_vault_address(&program_id, &pool),
+            borrower_dai_account: get_associated_token_address(
+                &borrower_wallet_keypair.pubkey(),
+                &opt.dai_mint_address,
+            ),
+            lender_dai_account: get_associated_token_address(
+                &lender_wallet_address,
+                &opt.dai_mint_address,
+            ),
+
+            bid_account,
+            deposit_account: deposit,
+
+            loan_note_mint,
+            lender_note_account: get_associated_token_address(
+                &lender_wallet_address,
+                &loan_note_mint,
+            ),
+
+            spl_program: spl_token::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(taker::instruction::ExtendLoan {
+            extension: opt.extension,
+            proof,
+        })
+        .signer(&borrower_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use taker::{NFTDeposit, NFTListing, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    lender_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: Pubkey,
+
+    #[structopt(long, env)]
+    tai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: Pubkey,
+
+    // in bp per day, at most the maximum rate of the listing
+    #[structopt(long)]
+    interest_rate: u64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let lender_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
+    let lender_wallet_address = lender_wallet_keypair.pubkey();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let deposit = NFTDeposit::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &opt.borrower_wallet_address,
+        &opt.deposit_id,
+    );
+    let loan_note_mint =
+        NFTDeposit::get_loan_note_address(&program_id, &deposit, &lender_wallet_address);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsFundListing {
+            pool,
+            lender_wallet_account: lender_wallet_address,
+            borrower_wallet_account: opt.borrower_wallet_address,
+
+            pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
+            borrower_dai_account: get_associated_token_address(
+                &opt.borrower_wallet_address,
+                &opt.dai_mint_address,
+            ),
+            lender_dai_account: get_associated_token_address(
+                &lender_wallet_address,
+                &opt.dai_mint_address,
+            ),
+
+            lender_tai_account: get_associated_token_address(
+                &lender_wallet_address,
+                &opt.tai_mint_address,
+            ),
+            pool_tai_account: get_associated_token_address(&pool, &opt.tai_mint_address),
+
+            deposit_account: deposit,
+            listing_account: NFTListing::get_address(&program_id, &deposit),
+
+            loan_note_mint: loan_note_mint,
+            lender_note_account: get_associated_token_address(
+                &lender_wallet_address,
+                &loan_note_mint,
+            ),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(taker::instruction::FundListing {
+            interest_rate: opt.interest_rate,
+        })
+        .signer(&lender_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -15,6 +15,9 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
 
+    #[structopt(long, env)]
+    pool_id: Option<Pubkey>,
+
     #[structopt(long, env)]
     pool_owner_keypair: String,
 
@@ -42,7 +45,10 @@ fn main() -> Result<()> {
     let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
     let program = client.program(program_id);
 
-    let pool = NFTPool::get_address(&program.id());
+    let pool_id = opt
+        .pool_id
+        .unwrap_or_else(|| solana_sdk::signature::Keypair::new().pubkey());
+    let pool = NFTPool::get_address(&program.id(), &pool_id);
 
     let tx = program
         .request()
@@ -67,18 +73,20 @@ fn main() -> Result<()> {
                 &pool,
                 &opt.dai_mint_address,
             ),
+            pool_fee_vault: NFTPool::get_fee_vault_address(&program_id, &pool),
 
             ata_program: spl_associated_token_account::id(),
             spl_program: spl_token::id(),
             system_program: system_program::id(),
             rent: sysvar::rent::id(),
         })
-        .args(taker::instruction::Initialize {})
+        .args(taker::instruction::Initialize { pool_id })
         .signer(&pool_owner_keypair)
         .send()?;
 
     println!("The transaction is {}", tx);
     println!("Pool address: {}", pool);
+    println!("The pool_id is {}", pool_id);
 
     let rpc = program.rpc();
     let (h, _, _) = rpc
@@ -2,7 +2,6 @@ use anchor_client::Client;
 use anyhow::Result;
 use cli::{get_cluster, load_program_from_idl, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
-use solana_clap_utils::input_parsers::pubkey_of;
 use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
@@ -15,7 +14,7 @@ struct Opt {
     taker_program_address: Option<Pubkey>,
 
     #[structopt(long, env)]
-    pool_owner_address: String,
+    pool_id: Pubkey,
 
     #[structopt(long, env)]
     borrower_wallet_address: Pubkey,
@@ -34,6 +33,14 @@ struct Opt {
 
     #[structopt(long, env)]
     deposit_id: Pubkey,
+
+    // also close the deposit, its rent goes back to the borrower
+    #[structopt(long)]
+    close: bool,
+
+    // the lender the loan note was issued to, when the note was bought from it
+    #[structopt(long, env)]
+    note_lender: Option<Pubkey>,
 }
 
 fn main() -> Result<()> {
@@ -46,18 +53,29 @@ fn main() -> Result<()> {
 
     let lender_wallet_keypair =
         keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
-    let pool_owner_address = pubkey_of(&Opt::clap().get_matches(), "pool-owner-address").unwrap();
 
     let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
     let program = client.program(program_id);
 
-    let pool = NFTPool::get_address(&program.id());
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let deposit = NFTDeposit::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &opt.borrower_wallet_address,
+        &opt.deposit_id,
+    );
+    let note_lender = opt
+        .note_lender
+        .unwrap_or_else(|| lender_wallet_keypair.pubkey());
+    let loan_note_mint = NFTDeposit::get_loan_note_address(&program_id, &deposit, &note_lender);
 
     let tx = program
         .request()
         .accounts(taker::accounts::AccountsLiquidate {
             pool,
             lender_wallet_account: lender_wallet_keypair.pubkey(),
+            borrower_wallet_account: opt.borrower_wallet_address,
 
             nft_mint: opt.nft_mint_address,
             pool_nft_account: dbg!(get_associated_token_address(&pool, &opt.nft_mint_address)),
@@ -71,10 +89,7 @@ fn main() -> Result<()> {
                 &opt.dai_mint_address
             )),
             pool_dai_account: dbg!(get_associated_token_address(&pool, &opt.dai_mint_address)),
-            pool_owner_dai_account: dbg!(get_associated_token_address(
-                &pool_owner_address,
-                &opt.dai_mint_address
-            )),
+            pool_fee_vault: NFTPool::get_fee_vault_address(&program_id, &pool),
 
             lender_tai_account: dbg!(get_associated_token_address(
                 &lender_wallet_keypair.pubkey(),
@@ -82,12 +97,13 @@ fn main() -> Result<()> {
             )),
             pool_tai_account: dbg!(get_associated_token_address(&pool, &opt.tai_mint_address)),
 
-            deposit_account: dbg!(NFTDeposit::get_address(
-                &program_id,
-                &opt.nft_mint_address,
-                &opt.borrower_wallet_address,
-                &opt.deposit_id,
-            )),
+            deposit_account: deposit,
+
+            loan_note_mint: loan_note_mint,
+            lender_note_account: get_associated_token_address(
+                &lender_wallet_keypair.pubkey(),
+                &loan_note_mint,
+            ),
 
             ata_program: spl_associated_token_account::id(),
             spl_program: spl_token::id(),
@@ -95,7 +111,7 @@ fn main() -> Result<()> {
             rent: sysvar::rent::id(),
             clock: sysvar::clock::id(),
         })
-        .args(taker::instruction::Liquidate {})
+        .args(taker::instruction::Liquidate { close: opt.close })
         .signer(&lender_wallet_keypair)
         .send()?;
 
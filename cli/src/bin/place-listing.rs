@@ -0,0 +1,85 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use structopt::StructOpt;
+use taker::{NFTDeposit, NFTListing, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    borrower_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: Pubkey,
+
+    #[structopt(long)]
+    amount: f64,
+
+    // in bp per day, the highest rate a lender can fund the listing at
+    #[structopt(long)]
+    max_interest_rate: u64,
+
+    // secs
+    #[structopt(long)]
+    loan_duration: i64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let borrower_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "borrower-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let deposit = NFTDeposit::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &borrower_wallet_keypair.pubkey(),
+        &opt.deposit_id,
+    );
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsPlaceListing {
+            pool,
+            borrower_wallet_account: borrower_wallet_keypair.pubkey(),
+
+            deposit_account: deposit,
+            listing_account: NFTListing::get_address(&program_id, &deposit),
+
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        })
+        .args(taker::instruction::PlaceListing {
+            amount: (opt.amount * 10f64.powf(9.)) as u64,
+            max_interest_rate: opt.max_interest_rate,
+            loan_duration: opt.loan_duration,
+        })
+        .signer(&borrower_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
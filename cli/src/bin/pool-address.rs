@@ -8,6 +8,9 @@ use taker::NFTPool;
 struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
 }
 
 fn main() -> Result<()> {
@@ -18,7 +21,7 @@ fn main() -> Result<()> {
         .taker_program_address
         .unwrap_or_else(cli::load_program_from_idl);
 
-    let pool = NFTPool::get_address(&program_id);
+    let pool = NFTPool::get_address(&program_id, &opt.pool_id);
 
     println!("The pool address is {}", pool);
 
@@ -12,6 +12,9 @@ use taker::NFTPool;
 struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
 }
 
 fn main() -> Result<()> {
@@ -22,7 +25,7 @@ fn main() -> Result<()> {
         .taker_program_address
         .unwrap_or_else(cli::load_program_from_idl);
 
-    let pool_account = NFTPool::get_address(&program_id);
+    let pool_account = NFTPool::get_address(&program_id, &opt.pool_id);
 
     let client = Client::new(get_cluster(), Keypair::generate(&mut OsRng));
     let program = client.program(program_id);
@@ -0,0 +1,59 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use taker::NFTPool;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "transact",
+    about = "Proposes a new owner for the pool, who then calls accept-owner"
+)]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    pool_owner_keypair: String,
+
+    #[structopt(long)]
+    pending_owner: Pubkey,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let pool_owner_keypair = keypair_of(&Opt::clap().get_matches(), "pool-owner-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pool_owner_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsPoolOwner {
+            owner: pool_owner_keypair.pubkey(),
+            pool,
+        })
+        .args(taker::instruction::ProposeOwner {
+            pending_owner: opt.pending_owner,
+        })
+        .signer(&pool_owner_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
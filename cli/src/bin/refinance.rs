@@ -0,0 +1,152 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_allowlist, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_program, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use taker::{allowlist, AutoRefinance, NFTBid, NFTDeposit, NFTPool, RefinancePayoff};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    // the borrower, or anyone when the borrower opted in to automatic refinancing
+    #[structopt(long, env)]
+    payer_keypair: String,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: Pubkey,
+
+    // the new lender, whose bid takes over the loan
+    #[structopt(long, env)]
+    lender_wallet_address: Pubkey,
+
+    #[structopt(long, env)]
+    tai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: Pubkey,
+
+    // refinance with the lender's collection bid for this allowlist instead of the NFT bid
+    #[structopt(long, env)]
+    allowlist: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let payer_keypair = keypair_of(&Opt::clap().get_matches(), "payer-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&payer_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let deposit = NFTDeposit::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &opt.borrower_wallet_address,
+        &opt.deposit_id,
+    );
+    let current_lender = program
+        .account::<NFTDeposit>(deposit)?
+        .get_active_state()
+        .map_err(|e| anyhow::anyhow!("The loan is not active: {:?}", e))?
+        .lender;
+    let loan_note_mint =
+        NFTDeposit::get_loan_note_address(&program_id, &deposit, &opt.lender_wallet_address);
+
+    let (bid_account, proof) = match &opt.allowlist {
+        Some(path) => {
+            let nft_mints = load_allowlist(path)?;
+            let proof = allowlist::proof(&nft_mints, &opt.nft_mint_address)
+                .ok_or_else(|| anyhow::anyhow!("The NFT is not in the allowlist"))?;
+            let bid_account = NFTBid::get_collection_address(
+                &program_id,
+                &pool,
+                &allowlist::root(&nft_mints),
+                &opt.lender_wallet_address,
+            );
+            (bid_account, proof)
+        }
+        None => {
+            let bid_account = NFTBid::get_address(
+                &program_id,
+                &pool,
+                &opt.nft_mint_address,
+                &opt.lender_wallet_address,
+            );
+            (bid_account, vec![])
+        }
+    };
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsRefinance {
+            pool,
+            payer: payer_keypair.pubkey(),
+            borrower_wallet_account: opt.borrower_wallet_address,
+            lender_wallet_account: opt.lender_wallet_address,
+
+            pool_fee_vault: NFTPool::get_fee_vault_address(&program_id, &pool),
+            pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
+            lender_dai_account: get_associated_token_address(
+                &opt.lender_wallet_address,
+                &opt.dai_mint_address,
+            ),
+
+            pool_tai_account: get_associated_token_address(&pool, &opt.tai_mint_address),
+            lender_tai_account: get_associated_token_address(
+                &opt.lender_wallet_address,
+                &opt.tai_mint_address,
+            ),
+
+            deposit_account: deposit,
+            bid_account,
+            bid_escrow_account: NFTBid::get_escrow_address(&program_id, &bid_account),
+            auto_refinance_account: AutoRefinance::get_address(&program_id, &deposit),
+            payoff_account: RefinancePayoff::get_address(
+                &program_id,
+                &pool,
+                &deposit,
+                &current_lender,
+            ),
+
+            loan_note_mint: loan_note_mint,
+            lender_note_account: get_associated_token_address(
+                &opt.lender_wallet_address,
+                &loan_note_mint,
+            ),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(taker::instruction::Refinance { proof })
+        .signer(&payer_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use taker::{NFTDeposit, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    borrower_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: Pubkey,
+
+    // principal to pay back, the interest accrued so far is added to it
+    #[structopt(long, env)]
+    amount: f64,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let borrower_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "borrower-wallet-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsRepayPartial {
+            pool,
+            borrower_wallet_account: borrower_wallet_keypair.pubkey(),
+
+            pool_fee_vault: NFTPool::get_fee_vault_address(&program_id, &pool),
+            borrower_dai_account: get_associated_token_address(
+                &borrower_wallet_keypair.pubkey(),
+                &opt.dai_mint_address,
+            ),
+            pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
+
+            deposit_account: NFTDeposit::get_address(
+                &program_id,
+                &pool,
+                &opt.nft_mint_address,
+                &borrower_wallet_keypair.pubkey(),
+                &opt.deposit_id,
+            ),
+
+            spl_program: spl_token::id(),
+            clock: sysvar::clock::id(),
+        })
+        .args(taker::instruction::RepayPartial {
+            amount: (opt.amount * 10f64.powf(9.)) as u64,
+        })
+        .signer(&borrower_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
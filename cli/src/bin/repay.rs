@@ -2,7 +2,6 @@ use anchor_client::Client;
 use anyhow::Result;
 use cli::{get_cluster, load_program_from_idl, Keypair};
 use solana_clap_utils::input_parsers::keypair_of;
-use solana_clap_utils::input_parsers::pubkey_of;
 use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
@@ -15,14 +14,11 @@ struct Opt {
     taker_program_address: Option<Pubkey>,
 
     #[structopt(long, env)]
-    pool_owner_address: String,
+    pool_id: Pubkey,
 
     #[structopt(long, env)]
     borrower_wallet_keypair: String,
 
-    #[structopt(long, env)]
-    lender_wallet_address: String,
-
     #[structopt(long, env)]
     dai_mint_address: Pubkey,
 
@@ -43,14 +39,11 @@ fn main() -> Result<()> {
 
     let borrower_wallet_keypair =
         keypair_of(&Opt::clap().get_matches(), "borrower-wallet-keypair").unwrap();
-    let pool_owner_address = pubkey_of(&Opt::clap().get_matches(), "pool-owner-address").unwrap();
-    let lender_wallet_address =
-        pubkey_of(&Opt::clap().get_matches(), "lender-wallet-address").unwrap();
 
     let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
     let program = client.program(program_id);
 
-    let pool = NFTPool::get_address(&program.id());
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
 
     let tx = program
         .request()
@@ -58,18 +51,12 @@ fn main() -> Result<()> {
             pool,
             borrower_wallet_account: borrower_wallet_keypair.pubkey(),
 
-            pool_owner_dai_account: get_associated_token_address(
-                &pool_owner_address,
-                &opt.dai_mint_address,
-            ),
+            pool_fee_vault: NFTPool::get_fee_vault_address(&program_id, &pool),
             borrower_dai_account: get_associated_token_address(
                 &borrower_wallet_keypair.pubkey(),
                 &opt.dai_mint_address,
             ),
-            lender_dai_account: get_associated_token_address(
-                &lender_wallet_address,
-                &opt.dai_mint_address,
-            ),
+            pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
 
             borrower_nft_account: get_associated_token_address(
                 &borrower_wallet_keypair.pubkey(),
@@ -79,6 +66,7 @@ fn main() -> Result<()> {
 
             deposit_account: NFTDeposit::get_address(
                 &program_id,
+                &pool,
                 &opt.nft_mint_address,
                 &borrower_wallet_keypair.pubkey(),
                 &opt.deposit_id,
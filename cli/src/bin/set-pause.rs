@@ -0,0 +1,86 @@
+use anchor_client::Client;
+use anyhow::{anyhow, Result};
+use cli::{get_cluster, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use taker::NFTPool;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "transact",
+    about = "Pauses the listed instructions of the pool, resumes the others"
+)]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    // the owner or the pauser of the pool
+    #[structopt(long, env)]
+    pauser_keypair: String,
+
+    // deposit, place-bid, borrow, repay-partial, extend-loan, auction, liquidate, withdraw-locked-asset,
+    // close-deposit, place-listing, refinance, set-auto-refinance, collect-fees or all. None resumes
+    // the pool
+    #[structopt(long)]
+    pause: Vec<String>,
+}
+
+fn pause_flag(name: &str) -> Result<u32> {
+    Ok(match name {
+        "deposit" => NFTPool::PAUSE_DEPOSIT,
+        "place-bid" => NFTPool::PAUSE_PLACE_BID,
+        "borrow" => NFTPool::PAUSE_BORROW,
+        "repay-partial" => NFTPool::PAUSE_REPAY_PARTIAL,
+        "extend-loan" => NFTPool::PAUSE_EXTEND_LOAN,
+        "auction" => NFTPool::PAUSE_AUCTION,
+        "liquidate" => NFTPool::PAUSE_LIQUIDATE,
+        "withdraw-locked-asset" => NFTPool::PAUSE_WITHDRAW_LOCKED_ASSET,
+        "close-deposit" => NFTPool::PAUSE_CLOSE_DEPOSIT,
+        "place-listing" => NFTPool::PAUSE_PLACE_LISTING,
+        "refinance" => NFTPool::PAUSE_REFINANCE,
+        "set-auto-refinance" => NFTPool::PAUSE_SET_AUTO_REFINANCE,
+        "collect-fees" => NFTPool::PAUSE_COLLECT_FEES,
+        "all" => NFTPool::PAUSE_ALL,
+        _ => return Err(anyhow!("unknown instruction {}", name)),
+    })
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(cli::load_program_from_idl);
+
+    let mut paused = 0;
+    for name in &opt.pause {
+        paused |= pause_flag(name)?;
+    }
+
+    let pauser_keypair = keypair_of(&Opt::clap().get_matches(), "pauser-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&pauser_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsSetPause {
+            authority: pauser_keypair.pubkey(),
+            pool,
+        })
+        .args(taker::instruction::SetPause { paused })
+        .signer(&pauser_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Pool address: {}", pool);
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, sysvar};
+use structopt::StructOpt;
+use taker::{NFTDeposit, NFTPool};
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "transact",
+    about = "Starts the auction of the NFT of an expired loan"
+)]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    // anyone can start the auction, this wallet only pays the transaction
+    #[structopt(long, env)]
+    payer_keypair: String,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: Pubkey,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let payer_keypair = keypair_of(&Opt::clap().get_matches(), "payer-keypair").unwrap();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&payer_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsStartAuction {
+            pool,
+            deposit_account: dbg!(NFTDeposit::get_address(
+                &program_id,
+                &pool,
+                &opt.nft_mint_address,
+                &opt.borrower_wallet_address,
+                &opt.deposit_id,
+            )),
+            clock: sysvar::clock::id(),
+        })
+        .args(taker::instruction::StartAuction {})
+        .signer(&payer_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+    println!("Payer: {}", payer_keypair.pubkey());
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anchor_client::anchor_lang::{AccountDeserialize, Discriminator};
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_account_decoder::UiAccountEncoding;
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use structopt::StructOpt;
+use taker::{NFTDeposit, NFTPool};
+
+// Offset of NFTDeposit::borrower: discriminator, deposit_id, nft_mint
+const BORROWER_OFFSET: usize = 8 + 32 + 32;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "transact",
+    about = "Closes the finished deposits of a borrower and refunds their rent"
+)]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    borrower_wallet_keypair: String,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let borrower_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "borrower-wallet-keypair").unwrap();
+    let borrower = borrower_wallet_keypair.pubkey();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+
+    let accounts = program.rpc().get_program_accounts_with_config(
+        &program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: 0,
+                    bytes: MemcmpEncodedBytes::Binary(
+                        bs58::encode(NFTDeposit::discriminator()).into_string(),
+                    ),
+                    encoding: None,
+                }),
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: BORROWER_OFFSET,
+                    bytes: MemcmpEncodedBytes::Binary(borrower.to_string()),
+                    encoding: None,
+                }),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    for (address, account) in accounts {
+        let deposit = NFTDeposit::try_deserialize(&mut account.data.as_slice())?;
+
+        // the borrower may have deposits in other pools of the program
+        let in_pool = NFTDeposit::get_address(
+            &program_id,
+            &pool,
+            &deposit.nft_mint(),
+            &borrower,
+            &deposit.deposit_id(),
+        ) == address;
+
+        if !in_pool || !deposit.is_closable() {
+            continue;
+        }
+
+        let tx = program
+            .request()
+            .accounts(taker::accounts::AccountsCloseDeposit {
+                pool,
+                borrower_wallet_account: borrower,
+                deposit_account: address,
+            })
+            .args(taker::instruction::CloseDeposit {})
+            .send()?;
+
+        println!(
+            "Closed deposit {} ({:?}), the transaction is {}",
+            address,
+            deposit.state(),
+            tx
+        );
+    }
+
+    Ok(())
+}
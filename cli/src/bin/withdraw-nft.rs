@@ -11,7 +11,7 @@ use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
 use solana_sdk::{pubkey::Pubkey, signature::Signer};
 use spl_associated_token_account::get_associated_token_address;
 use structopt::StructOpt;
-use taker::{NFTDeposit, NFTPool, TakerError};
+use taker::{CustomError, NFTDeposit, NFTPool};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
@@ -19,6 +19,9 @@ struct Opt {
     #[structopt(long, env, short = "p")]
     taker_program_address: Option<Pubkey>,
 
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
     #[structopt(long, env)]
     borrower_wallet_keypair: String,
 
@@ -27,6 +30,10 @@ struct Opt {
 
     #[structopt(long, env)]
     deposit_id: Pubkey,
+
+    // also close the deposit and get its rent back
+    #[structopt(long)]
+    close: bool,
 }
 
 fn main() -> Result<()> {
@@ -43,7 +50,7 @@ fn main() -> Result<()> {
     let client = Client::new(get_cluster(), Keypair::copy(&borrower_wallet_keypair));
     let program = client.program(program_id);
 
-    let pool = NFTPool::get_address(&program.id());
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
 
     let resp = program
         .request()
@@ -60,6 +67,7 @@ fn main() -> Result<()> {
 
             deposit_account: dbg!(NFTDeposit::get_address(
                 &program_id,
+                &pool,
                 &opt.nft_mint_address,
                 &borrower_wallet_keypair.pubkey(),
                 &opt.deposit_id
@@ -69,6 +77,7 @@ fn main() -> Result<()> {
         })
         .args(taker::instruction::WithdrawNft {
             deposit_id: opt.deposit_id,
+            close: opt.close,
         })
         .signer(&borrower_wallet_keypair)
         .send();
@@ -93,7 +102,7 @@ fn main() -> Result<()> {
                 }),
             ..
         })) => {
-            println!("Error: {}", TakerError::from_code(code));
+            println!("Error: {}", CustomError::from(code));
         }
         Err(e) => println!("{:?}", e),
     };
@@ -0,0 +1,109 @@
+use anchor_client::Client;
+use anyhow::Result;
+use cli::{get_cluster, load_program_from_idl, Keypair};
+use solana_clap_utils::input_parsers::keypair_of;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use spl_associated_token_account::get_associated_token_address;
+use structopt::StructOpt;
+use taker::{NFTDeposit, NFTPool, RefinancePayoff};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "transact", about = "Making transactions to the Taker Protocol")]
+struct Opt {
+    #[structopt(long, env, short = "p")]
+    taker_program_address: Option<Pubkey>,
+
+    #[structopt(long, env)]
+    pool_id: Pubkey,
+
+    #[structopt(long, env)]
+    lender_wallet_keypair: String,
+
+    #[structopt(long, env)]
+    borrower_wallet_address: Pubkey,
+
+    #[structopt(long, env)]
+    tai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    dai_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    nft_mint_address: Pubkey,
+
+    #[structopt(long, env)]
+    deposit_id: Pubkey,
+
+    // the lender the loan note was issued to, when the note was bought from it
+    #[structopt(long, env)]
+    note_lender: Option<Pubkey>,
+}
+
+fn main() -> Result<()> {
+    solana_logger::setup_with("solana=debug");
+
+    let opt = Opt::from_args();
+    let program_id = opt
+        .taker_program_address
+        .unwrap_or_else(load_program_from_idl);
+
+    let lender_wallet_keypair =
+        keypair_of(&Opt::clap().get_matches(), "lender-wallet-keypair").unwrap();
+    let lender_wallet_address = lender_wallet_keypair.pubkey();
+
+    let client = Client::new(get_cluster(), Keypair::copy(&lender_wallet_keypair));
+    let program = client.program(program_id);
+
+    let pool = NFTPool::get_address(&program.id(), &opt.pool_id);
+    let deposit = NFTDeposit::get_address(
+        &program_id,
+        &pool,
+        &opt.nft_mint_address,
+        &opt.borrower_wallet_address,
+        &opt.deposit_id,
+    );
+    let note_lender = opt.note_lender.unwrap_or(lender_wallet_address);
+    let loan_note_mint = NFTDeposit::get_loan_note_address(&program_id, &deposit, &note_lender);
+
+    let tx = program
+        .request()
+        .accounts(taker::accounts::AccountsWithdrawRefinancePayoff {
+            pool,
+            lender_wallet_account: lender_wallet_address,
+
+            lender_tai_account: get_associated_token_address(
+                &lender_wallet_address,
+                &opt.tai_mint_address,
+            ),
+            pool_tai_account: get_associated_token_address(&pool, &opt.tai_mint_address),
+
+            lender_dai_account: get_associated_token_address(
+                &lender_wallet_address,
+                &opt.dai_mint_address,
+            ),
+            pool_dai_account: get_associated_token_address(&pool, &opt.dai_mint_address),
+
+            deposit_account: deposit,
+            payoff_account: RefinancePayoff::get_address(
+                &program_id,
+                &pool,
+                &deposit,
+                &note_lender,
+            ),
+
+            loan_note_mint: loan_note_mint,
+            lender_note_account: get_associated_token_address(
+                &lender_wallet_address,
+                &loan_note_mint,
+            ),
+
+            spl_program: spl_token::id(),
+        })
+        .args(taker::instruction::WithdrawRefinancePayoff {})
+        .signer(&lender_wallet_keypair)
+        .send()?;
+
+    println!("The transaction is {}", tx);
+
+    Ok(())
+}
@@ -1,9 +1,15 @@
 use anchor_client::Cluster;
+use anyhow::{anyhow, Result};
 use derive_more::Deref;
 use serde::Deserialize;
 use serde_json::from_reader;
 use solana_sdk::pubkey::Pubkey;
-use std::{fs::File, str::FromStr};
+use std::{
+    fs::{read_to_string, File},
+    path::Path,
+    str::FromStr,
+};
+use structopt::StructOpt;
 
 #[derive(Debug, Deref)]
 pub struct Keypair(#[deref] pub solana_sdk::signature::Keypair, String);
@@ -51,3 +57,75 @@ pub fn get_cluster() -> Cluster {
         "wss://api.devnet.solana.com".into(),
     )
 }
+
+// Reads the NFT mints of a collection, one address per line
+pub fn load_allowlist(path: &Path) -> Result<Vec<Pubkey>> {
+    read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| Ok(line.parse()?))
+        .collect()
+}
+
+// Loan terms of a bid, the pool defaults are used when none is given
+#[derive(Debug, StructOpt)]
+pub struct LoanTermsOpt {
+    #[structopt(long, env)]
+    interest_rate: Option<u64>,
+
+    #[structopt(long, env)]
+    max_loan_duration: Option<i64>,
+
+    #[structopt(long, env)]
+    mortgage_rate: Option<u64>,
+
+    // secs a borrower can extend a loan by without the lender signing
+    #[structopt(long, env, default_value = "0")]
+    max_extension: i64,
+}
+
+impl LoanTermsOpt {
+    pub fn terms(&self) -> Result<Option<taker::LoanTerms>> {
+        match (
+            self.interest_rate,
+            self.max_loan_duration,
+            self.mortgage_rate,
+        ) {
+            (None, None, None) => Ok(None),
+            (Some(interest_rate), Some(max_loan_duration), Some(mortgage_rate)) => {
+                Ok(Some(taker::LoanTerms {
+                    interest_rate,
+                    max_loan_duration,
+                    mortgage_rate,
+                    max_extension: self.max_extension,
+                }))
+            }
+            _ => Err(anyhow!(
+                "--interest-rate, --max-loan-duration and --mortgage-rate go together"
+            )),
+        }
+    }
+}
+
+// Limits of the loans taken against a bid
+#[derive(Debug, StructOpt)]
+pub struct BidLimitsOpt {
+    // unix timestamp after which the bid cannot be borrowed against
+    #[structopt(long, env)]
+    expires_at: Option<i64>,
+
+    // smallest loan taken from the bid, in DAI
+    #[structopt(long, env, default_value = "0")]
+    min_amount: f64,
+}
+
+impl BidLimitsOpt {
+    pub fn expires_at(&self) -> Option<i64> {
+        self.expires_at
+    }
+
+    pub fn min_amount(&self) -> u64 {
+        (self.min_amount * 10f64.powf(9.)) as u64
+    }
+}
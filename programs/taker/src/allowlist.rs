@@ -0,0 +1,65 @@
+use anchor_lang::prelude::Pubkey;
+use solana_program::keccak;
+
+// A collection is committed on chain as the root of a keccak merkle tree over its NFT mints.
+// The leaves are keccak(mint) and every pair of nodes is hashed in sorted order, so a proof
+// is just the list of siblings from the leaf up to the root.
+
+pub fn leaf(nft_mint: &Pubkey) -> [u8; 32] {
+    keccak::hash(&nft_mint.to_bytes()).0
+}
+
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        keccak::hashv(&[a, b]).0
+    } else {
+        keccak::hashv(&[b, a]).0
+    }
+}
+
+pub fn verify(root: &[u8; 32], nft_mint: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf(nft_mint), |node, sibling| hash_pair(&node, sibling));
+
+    &computed == root
+}
+
+// Client side helpers to commit to a list of mints and to prove one of them, the last node
+// of a level with an odd length is carried up as is
+pub fn root(nft_mints: &[Pubkey]) -> [u8; 32] {
+    assert!(!nft_mints.is_empty(), "the allowlist is empty");
+
+    let mut level: Vec<_> = nft_mints.iter().map(leaf).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+pub fn proof(nft_mints: &[Pubkey], nft_mint: &Pubkey) -> Option<Vec<[u8; 32]>> {
+    let mut index = nft_mints.iter().position(|m| m == nft_mint)?;
+
+    let mut proof = vec![];
+    let mut level: Vec<_> = nft_mints.iter().map(leaf).collect();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+
+    Some(proof)
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [a, b] => hash_pair(a, b),
+            [a] => *a,
+            _ => unreachable!(),
+        })
+        .collect()
+}
@@ -0,0 +1,106 @@
+use crate::TakerError;
+use anchor_lang::__private::ErrorCode as AnchorError; // anchor 0.11 only exports it here
+use num_traits::FromPrimitive;
+use spl_token::error::TokenError;
+use std::convert::TryFrom;
+use std::fmt;
+
+// Anchor reports the TakerError variants as ProgramError::Custom(variant + ERROR_CODE_OFFSET),
+// its own framework errors use the codes in [100, 300)
+pub const ERROR_CODE_OFFSET: u32 = anchor_lang::__private::ERROR_CODE_OFFSET;
+const ANCHOR_ERROR_CODE_START: u32 = 100;
+
+impl TryFrom<u32> for TakerError {
+    type Error = u32;
+
+    // Gives the code back if it is not a Taker error
+    fn try_from(code: u32) -> Result<Self, u32> {
+        code.checked_sub(ERROR_CODE_OFFSET)
+            .and_then(TakerError::from_u32)
+            .ok_or(code)
+    }
+}
+
+// A decoded InstructionError::Custom code returned by a Taker instruction
+#[derive(Debug, Clone)]
+pub enum CustomError {
+    Taker(TakerError),
+    Anchor(AnchorError),
+    Token(TokenError), // codes below 100 are assumed to come from the SPL token program
+    Unknown(u32),
+}
+
+impl From<u32> for CustomError {
+    fn from(code: u32) -> Self {
+        if let Ok(err) = TakerError::try_from(code) {
+            return CustomError::Taker(err);
+        }
+
+        if code < ANCHOR_ERROR_CODE_START {
+            return TokenError::from_u32(code)
+                .map(CustomError::Token)
+                .unwrap_or(CustomError::Unknown(code));
+        }
+
+        anchor_error(code)
+            .map(CustomError::Anchor)
+            .unwrap_or(CustomError::Unknown(code))
+    }
+}
+
+impl fmt::Display for CustomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CustomError::Taker(err) => write!(f, "Taker error {:?}: {}", err, err),
+            CustomError::Anchor(err) => write!(f, "Anchor error {:?}: {}", err, err),
+            CustomError::Token(err) => write!(f, "Token program error {:?}: {}", err, err),
+            CustomError::Unknown(code) => write!(f, "Unknown error code {}", code),
+        }
+    }
+}
+
+// Anchor's ErrorCode has gaps between the groups of codes and does not implement FromPrimitive
+fn anchor_error(code: u32) -> Option<AnchorError> {
+    use AnchorError::*;
+
+    let err = match code {
+        100 => InstructionMissing,
+        101 => InstructionFallbackNotFound,
+        102 => InstructionDidNotDeserialize,
+        103 => InstructionDidNotSerialize,
+
+        120 => IdlInstructionStub,
+        121 => IdlInstructionInvalidProgram,
+
+        140 => ConstraintMut,
+        141 => ConstraintHasOne,
+        142 => ConstraintSigner,
+        143 => ConstraintRaw,
+        144 => ConstraintOwner,
+        145 => ConstraintRentExempt,
+        146 => ConstraintSeeds,
+        147 => ConstraintExecutable,
+        148 => ConstraintState,
+        149 => ConstraintAssociated,
+        150 => ConstraintAssociatedInit,
+        151 => ConstraintClose,
+        152 => ConstraintAddress,
+
+        160 => AccountDiscriminatorAlreadySet,
+        161 => AccountDiscriminatorNotFound,
+        162 => AccountDiscriminatorMismatch,
+        163 => AccountDidNotDeserialize,
+        164 => AccountDidNotSerialize,
+        165 => AccountNotEnoughKeys,
+        166 => AccountNotMutable,
+        167 => AccountNotProgramOwned,
+
+        180 => StateInvalidAddress,
+
+        299 => Deprecated,
+
+        _ => return None,
+    };
+
+    Some(err)
+}
@@ -0,0 +1,190 @@
+use crate::TakerError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use fehler::{throw, throws};
+use std::convert::TryInto;
+
+// Fixed-point scale of the growth factors and the rates
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+const BP: u128 = 10000;
+
+// How a loan accrues interest. `rate` is the rate of the loan in bp per day and `utilization` the
+// utilization of the pool when the loan started, in bp. The interest is rounded up so that a short loan
+// or a small principal never accrues zero interest.
+pub trait InterestModel {
+    fn interest(
+        &self,
+        principal: u64,
+        rate: u64,
+        duration: u64,
+        utilization: u64,
+    ) -> Result<u64, TakerError>;
+}
+
+// rate * duration on the principal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimpleInterest;
+
+// The interest is added to the principal at the end of every day, the last day accrues simply
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailyCompoundInterest;
+
+// The rate of the loan applies at the kink. Below it the rate falls linearly to zero with the
+// utilization of the pool, above it the rate rises by up to `jump_rate` bp of itself at 100% utilization
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct KinkedInterest {
+    pub kink: u16,      // in bp of utilization
+    pub jump_rate: u16, // in bp of the rate of the loan
+}
+
+// The interest model of a pool, snapshotted by the loans when they start
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum InterestCurve {
+    #[default]
+    Simple,
+    DailyCompound,
+    Kinked(KinkedInterest),
+}
+
+impl InterestCurve {
+    #[throws(TakerError)]
+    pub fn validate(&self) {
+        if let InterestCurve::Kinked(KinkedInterest { kink, .. }) = self {
+            if *kink == 0 || *kink as u128 >= BP {
+                throw!(TakerError::InvalidInterestModel)
+            }
+        }
+    }
+}
+
+impl InterestModel for InterestCurve {
+    fn interest(
+        &self,
+        principal: u64,
+        rate: u64,
+        duration: u64,
+        utilization: u64,
+    ) -> Result<u64, TakerError> {
+        match self {
+            InterestCurve::Simple => {
+                SimpleInterest.interest(principal, rate, duration, utilization)
+            }
+            InterestCurve::DailyCompound => {
+                DailyCompoundInterest.interest(principal, rate, duration, utilization)
+            }
+            InterestCurve::Kinked(model) => model.interest(principal, rate, duration, utilization),
+        }
+    }
+}
+
+impl InterestModel for SimpleInterest {
+    #[throws(TakerError)]
+    fn interest(&self, principal: u64, rate: u64, duration: u64, _utilization: u64) -> u64 {
+        to_u64(mul_div_ceil(
+            principal as u128 * rate as u128,
+            duration as u128,
+            SECONDS_PER_DAY as u128 * BP,
+        )?)?
+    }
+}
+
+impl InterestModel for DailyCompoundInterest {
+    #[throws(TakerError)]
+    fn interest(&self, principal: u64, rate: u64, duration: u64, _utilization: u64) -> u64 {
+        let daily_growth = WAD + rate as u128 * WAD / BP;
+        let days = duration / SECONDS_PER_DAY;
+        let rest = (duration % SECONDS_PER_DAY) as u128;
+
+        let growth = wad_pow(daily_growth, days)?;
+        let last_day_growth = WAD + rate as u128 * WAD * rest / (BP * SECONDS_PER_DAY as u128);
+        let growth = wad_mul(growth, last_day_growth)?;
+
+        to_u64(wad_mul_ceil(principal as u128, growth - WAD)?)?
+    }
+}
+
+impl KinkedInterest {
+    // The rate at `utilization` in WAD per day
+    #[throws(TakerError)]
+    pub fn rate_at(&self, rate: u64, utilization: u64) -> u128 {
+        let rate = rate as u128 * WAD / BP;
+        let (kink, utilization) = (self.kink as u128, utilization.min(BP as u64) as u128);
+
+        if utilization <= kink {
+            rate * utilization / kink
+        } else {
+            let jump = mul_div_floor(rate * self.jump_rate as u128, utilization - kink, BP)?;
+            rate + jump / (BP - kink)
+        }
+    }
+}
+
+impl InterestModel for KinkedInterest {
+    #[throws(TakerError)]
+    fn interest(&self, principal: u64, rate: u64, duration: u64, utilization: u64) -> u64 {
+        let rate = self.rate_at(rate, utilization)?;
+        let accrued = mul_div_floor(rate, duration as u128, SECONDS_PER_DAY as u128)?;
+
+        to_u64(mul_div_ceil(principal as u128, accrued, WAD)?)?
+    }
+}
+
+#[throws(TakerError)]
+fn mul_div_floor(a: u128, b: u128, c: u128) -> u128 {
+    a.checked_mul(b).ok_or(TakerError::MathOverflow)? / c
+}
+
+#[throws(TakerError)]
+fn mul_div_ceil(a: u128, b: u128, c: u128) -> u128 {
+    let product = a.checked_mul(b).ok_or(TakerError::MathOverflow)?;
+    product / c + (product % c != 0) as u128
+}
+
+#[throws(TakerError)]
+fn wad_mul(a: u128, b: u128) -> u128 {
+    wad_mul_rounded(a, b, false)?
+}
+
+#[throws(TakerError)]
+fn wad_mul_ceil(a: u128, b: u128) -> u128 {
+    wad_mul_rounded(a, b, true)?
+}
+
+// a * b / WAD on the integer and fractional parts of a and b, so that it only overflows when the
+// result does. The plain product overflows once the growth of a long loan passes about 340x
+#[throws(TakerError)]
+fn wad_mul_rounded(a: u128, b: u128, round_up: bool) -> u128 {
+    let (a_int, a_frac) = (a / WAD, a % WAD);
+    let (b_int, b_frac) = (b / WAD, b % WAD);
+    let frac = a_frac * b_frac;
+    let frac = frac / WAD + (round_up && frac % WAD != 0) as u128;
+
+    a_int
+        .checked_mul(b_int)
+        .and_then(|v| v.checked_mul(WAD))
+        .and_then(|v| v.checked_add(a_int.checked_mul(b_frac)?))
+        .and_then(|v| v.checked_add(a_frac.checked_mul(b_int)?))
+        .and_then(|v| v.checked_add(frac))
+        .ok_or(TakerError::MathOverflow)?
+}
+
+// base^exp by squaring, base in WAD
+#[throws(TakerError)]
+fn wad_pow(mut base: u128, mut exp: u64) -> u128 {
+    let mut result = WAD;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = wad_mul(result, base)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = wad_mul(base, base)?;
+        }
+    }
+    result
+}
+
+#[throws(TakerError)]
+fn to_u64(v: u128) -> u64 {
+    v.try_into().map_err(|_| TakerError::MathOverflow)?
+}
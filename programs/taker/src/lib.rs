@@ -1,41 +1,91 @@
+// the instructions and their helpers take the accounts one by one
+#![allow(clippy::too_many_arguments)]
+
+pub mod allowlist;
+mod errors;
+mod interest;
 mod nft_bid;
 mod nft_deposit;
+mod nft_listing;
 mod nft_pool;
+mod refinance;
+mod settings_change;
 mod utils;
 
-pub use nft_deposit::{DepositState, LoanActiveState, LoanRepayedState};
+pub use errors::{CustomError, ERROR_CODE_OFFSET};
+pub use interest::{
+    DailyCompoundInterest, InterestCurve, InterestModel, KinkedInterest, SimpleInterest,
+};
+pub use nft_bid::{BidTarget, LoanTerms};
+pub use nft_deposit::{DepositState, LoanActiveState, LoanAuctionState, LoanRepayedState};
+pub use nft_pool::{LoanSettings, SettingsCaps};
+pub use settings_change::QueuedChange;
 
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, TokenAccount};
 use fehler::throw;
+use num_derive::FromPrimitive;
 use solana_program::pubkey::Pubkey;
-use std::u64;
 
 pub trait DerivedAccountIdentifier {
     const SEED: &'static [u8];
 }
 
-// The contract account should have address find_program_address(&[seed], program_id)
+// Exposes the anchor generated entrypoint so that the program can be loaded natively,
+// e.g. by solana-program-test in the integration tests
+#[cfg(not(feature = "no-entrypoint"))]
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    entry(program_id, accounts, data)
+}
+
+// The contract account should have address find_program_address(&[seed, pool_id], program_id)
 #[account]
 #[derive(Debug)]
 pub struct NFTPool {
     pub bump_seed: u8,
+    pub pool_id: Pubkey, // distinguishes the pools hosted by the same program
     pub owner: Pubkey,
     pub tkr_mint: Pubkey,
     pub tai_mint: Pubkey,
     pub dai_mint: Pubkey,
-    pub incentive: u64,         // incentive amount when user mortgage their NFT
-    pub max_loan_duration: i64, // max loan duration before liquidation, secs
-    pub service_fee_rate: u64,  // in bp, one ten thousandth, fee rate charged by taker
-    pub interest_rate: u64,     // in bp, one ten thousandth
-    pub mortgage_rate: u64,     // in bp, mortgage rate to calculate real borrow amount
+    pub incentive: u64,          // incentive amount when user mortgage their NFT
+    pub max_loan_duration: i64,  // default max loan duration before liquidation, secs
+    pub service_fee_rate: u64,   // in bp, one ten thousandth, fee rate charged by taker
+    pub interest_rate: u64,      // in bp, one ten thousandth, default interest rate per day
+    pub mortgage_rate: u64,      // in bp, default mortgage rate to calculate real borrow amount
+    pub grace_period: i64,       // secs after the expiry during which the borrower can still repay
+    pub grace_penalty_rate: u64, // in bp of the outstanding principal, paid to the lender when repaying late
+    pub auction_duration: i64, // secs the NFT of a liquidated loan is on sale, 0 gives it to the lender
+    pub auction_start_rate: u64, // in bp of the lender's claim, the price the auction starts from
+    pub interest_model: InterestCurve, // how the loans accrue interest, snapshotted when they start
+    pub pending_owner: Pubkey, // proposed by the owner, default when no transfer is pending
+    pub fee_recipient: Pubkey, // collects the service fee
+    pub settings_admin: Pubkey, // changes the loan settings
+    pub pauser: Pubkey,
+    pub paused: u32, // bits of NFTPool::PAUSE_*, the instructions stopped by the pauser
+    pub settings_caps: SettingsCaps, // set by the owner, bound the loan settings
+    pub settings_delay: i64, // secs before the changes of the loan settings apply
+    pub fee_vault: Pubkey, // DAI account of the pool holding the service fees, see `collect_fees`
+    pub fees_accrued: u64, // all the service fees ever paid into the fee vault
+    pub fees_collected: u64, // all the service fees ever swept by the fee recipient
+    pub outstanding_amount: u64, // principal the active loans still owe, see `utilization`
+    pub offered_amount: u64, // DAI the open bids still offer
 }
 
 #[account]
 #[derive(Debug)]
 pub struct NFTBid {
+    pub target: BidTarget,
     pub price: u64, // DAI Price
     pub qty: u64,
+    pub terms: LoanTerms,
+    pub escrowed: bool, // the DAI of the bid waits in its escrow instead of behind an approval
+    pub expires_at: i64, // borrowing against the bid stops at this time, 0 if it never expires
+    pub min_amount: u64, // smallest amount a loan can take from the bid
 }
 
 // One NFTDeposit corresponds to one token
@@ -43,14 +93,53 @@ pub struct NFTBid {
 #[derive(Debug)]
 pub struct NFTDeposit {
     deposit_id: Pubkey,
+    nft_mint: Pubkey,
+    borrower: Pubkey,
     state: DepositState,
 }
 
+// A loan request of the borrower on a deposit, any lender can fund it with `fund_listing`
+#[account]
+#[derive(Debug)]
+pub struct NFTListing {
+    pub deposit: Pubkey,
+    pub borrower: Pubkey,       // funded the account and gets the rent back
+    pub amount: u64,            // DAI Price, as for the bids
+    pub max_interest_rate: u64, // in bp per day, the highest rate the borrower accepts
+    pub loan_duration: i64,     // secs
+}
+
+// The borrower of the deposit accepts to be refinanced at better rates without signing, see `refinance`
+#[account]
+#[derive(Debug)]
+pub struct AutoRefinance {
+    pub deposit: Pubkey,
+    pub borrower: Pubkey, // funded the account and gets the rent back
+}
+
+// What a lender refinanced out of a loan gets back by returning the TAI of the loan, as with a repayed loan
+#[account]
+#[derive(Debug)]
+pub struct RefinancePayoff {
+    pub lender: Pubkey,
+    pub tai_required_to_unlock: u64,
+    pub lender_withdrawable: u64,
+}
+
+// A change of the loan settings or of the caps waiting for the settings delay of its pool, one per pool
+#[account]
+#[derive(Debug)]
+pub struct SettingsChange {
+    proposer: Pubkey, // funded the account and gets the rent back
+    change: QueuedChange,
+    effective_at: i64,
+}
+
 #[program]
 pub mod taker {
     use super::*;
 
-    pub fn initialize(ctx: Context<AccountsInitialize>) -> Result<()> {
+    pub fn initialize(ctx: Context<AccountsInitialize>, pool_id: Pubkey) -> Result<()> {
         let AccountsInitialize {
             pool_owner,
             pool,
@@ -62,6 +151,7 @@ pub mod taker {
             pool_tkr_account,
             pool_tai_account,
             pool_dai_account,
+            pool_fee_vault,
 
             ata_program,
             spl_program,
@@ -71,6 +161,7 @@ pub mod taker {
 
         let pool = NFTPool::new_checked(
             ctx.program_id,
+            &pool_id,
             pool,
             pool_owner,
             tkr_mint,
@@ -98,8 +189,20 @@ pub mod taker {
             )?;
         }
 
+        NFTPool::create_fee_vault(
+            ctx.program_id,
+            &pool,
+            pool_owner,
+            dai_mint,
+            pool_fee_vault,
+            spl_program,
+            system,
+            rent,
+        )?;
+
         emit!(EventInitialized {
-            account: *pool.to_account_info().key
+            account: *pool.to_account_info().key,
+            pool_id,
         });
 
         Ok(())
@@ -113,25 +216,417 @@ pub mod taker {
         max_loan_duration: Option<i64>,
         mortgage_rate: Option<u64>,
     ) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
+        let AccountsChangeLoanSetting { pool, clock, .. } = ctx.accounts;
+        let mut settings = pool.loan_settings();
+
+        settings.incentive = incentive.unwrap_or(settings.incentive);
+        settings.interest_rate = interest_rate.unwrap_or(settings.interest_rate);
+        settings.service_fee_rate = service_fee_rate.unwrap_or(settings.service_fee_rate);
+        settings.max_loan_duration = max_loan_duration.unwrap_or(settings.max_loan_duration);
+        settings.mortgage_rate = mortgage_rate.unwrap_or(settings.mortgage_rate);
+
+        settings.validate(&pool.settings_caps)?;
+
+        // with a delay, borrowers and lenders get to see the change before it applies
+        if pool.settings_delay != 0 {
+            throw!(TakerError::SettingsChangeMustBeQueued)
+        }
+        pool.set_loan_settings(&settings);
+
+        emit!(EventLoanSettingChanged {
+            incentive: settings.incentive,
+            interest_rate: settings.interest_rate,
+            service_fee_rate: settings.service_fee_rate,
+            max_loan_duration: settings.max_loan_duration,
+            mortgage_rate: settings.mortgage_rate,
+            grace_period: settings.grace_period,
+            grace_penalty_rate: settings.grace_penalty_rate,
+            auction_duration: settings.auction_duration,
+            auction_start_rate: settings.auction_start_rate,
+            interest_model: settings.interest_model,
+            effective_at: clock.unix_timestamp,
+        });
+        Ok(())
+    }
 
-        incentive.map(|v| pool.incentive = v);
-        interest_rate.map(|v| pool.interest_rate = v);
-        service_fee_rate.map(|v| pool.service_fee_rate = v);
-        max_loan_duration.map(|v| pool.max_loan_duration = v);
-        mortgage_rate.map(|v| pool.mortgage_rate = v);
+    // Queues the change until the settings delay of the pool is over, see `execute_settings_change`
+    pub fn propose_settings_change(
+        ctx: Context<AccountsProposeSettingsChange>,
+        incentive: Option<u64>,
+        interest_rate: Option<u64>,
+        service_fee_rate: Option<u64>,
+        max_loan_duration: Option<i64>,
+        mortgage_rate: Option<u64>,
+        grace_period: Option<i64>,
+        grace_penalty_rate: Option<u64>,
+        auction_duration: Option<i64>,
+        auction_start_rate: Option<u64>,
+        interest_model: Option<InterestCurve>,
+    ) -> Result<()> {
+        let AccountsProposeSettingsChange {
+            settings_admin,
+            pool,
+            settings_change,
+            clock,
+            rent,
+            system_program,
+        } = ctx.accounts;
+        let mut settings = pool.loan_settings();
+
+        settings.incentive = incentive.unwrap_or(settings.incentive);
+        settings.interest_rate = interest_rate.unwrap_or(settings.interest_rate);
+        settings.service_fee_rate = service_fee_rate.unwrap_or(settings.service_fee_rate);
+        settings.max_loan_duration = max_loan_duration.unwrap_or(settings.max_loan_duration);
+        settings.mortgage_rate = mortgage_rate.unwrap_or(settings.mortgage_rate);
+        settings.grace_period = grace_period.unwrap_or(settings.grace_period);
+        settings.grace_penalty_rate = grace_penalty_rate.unwrap_or(settings.grace_penalty_rate);
+        settings.auction_duration = auction_duration.unwrap_or(settings.auction_duration);
+        settings.auction_start_rate = auction_start_rate.unwrap_or(settings.auction_start_rate);
+        settings.interest_model = interest_model.unwrap_or(settings.interest_model);
+
+        settings.validate(&pool.settings_caps)?;
+
+        let effective_at = clock
+            .unix_timestamp
+            .checked_add(pool.settings_delay)
+            .ok_or(TakerError::MathOverflow)?;
+        SettingsChange::propose(
+            ctx.program_id,
+            pool.to_account_info().key,
+            QueuedChange::Settings(settings),
+            effective_at,
+            settings_admin,
+            settings_change,
+            rent,
+            system_program,
+        )?;
 
         emit!(EventLoanSettingChanged {
-            incentive: pool.incentive,
-            interest_rate: pool.interest_rate,
-            service_fee_rate: pool.service_fee_rate,
-            max_loan_duration: pool.max_loan_duration,
-            mortgage_rate: pool.mortgage_rate,
+            incentive: settings.incentive,
+            interest_rate: settings.interest_rate,
+            service_fee_rate: settings.service_fee_rate,
+            max_loan_duration: settings.max_loan_duration,
+            mortgage_rate: settings.mortgage_rate,
+            grace_period: settings.grace_period,
+            grace_penalty_rate: settings.grace_penalty_rate,
+            auction_duration: settings.auction_duration,
+            auction_start_rate: settings.auction_start_rate,
+            interest_model: settings.interest_model,
+            effective_at,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_settings_change(ctx: Context<AccountsCancelSettingsChange>) -> Result<()> {
+        let AccountsCancelSettingsChange {
+            authority,
+            pool,
+            settings_change,
+            proposer,
+        } = ctx.accounts;
+
+        if authority.key != &pool.owner && authority.key != &pool.settings_admin {
+            throw!(TakerError::NotAuhorized)
+        }
+
+        let settings_change =
+            SettingsChange::load(ctx.program_id, pool.to_account_info().key, settings_change)?;
+        SettingsChange::close(&settings_change, proposer)?;
+
+        emit!(EventSettingsChangeCancelled {
+            effective_at: settings_change.effective_at(),
+        });
+        Ok(())
+    }
+
+    // Anyone can apply the queued change once the delay is over
+    pub fn execute_settings_change(ctx: Context<AccountsExecuteSettingsChange>) -> Result<()> {
+        let AccountsExecuteSettingsChange {
+            pool,
+            settings_change,
+            proposer,
+            clock,
+        } = ctx.accounts;
+
+        let settings_change =
+            SettingsChange::load(ctx.program_id, pool.to_account_info().key, settings_change)?;
+        if clock.unix_timestamp < settings_change.effective_at() {
+            throw!(TakerError::SettingsTimelocked)
+        }
+
+        match settings_change.change() {
+            QueuedChange::Settings(settings) => {
+                // the owner may have lowered the caps since the proposal
+                settings.validate(&pool.settings_caps)?;
+                pool.set_loan_settings(&settings);
+            }
+            QueuedChange::Caps {
+                settings_caps,
+                settings_delay,
+            } => {
+                // the settings admin may have changed the settings since the proposal
+                pool.loan_settings().validate(&settings_caps)?;
+                pool.settings_caps = settings_caps;
+                pool.settings_delay = settings_delay;
+
+                emit!(EventSettingsCapsChanged {
+                    settings_caps,
+                    settings_delay,
+                });
+            }
+        }
+        SettingsChange::close(&settings_change, proposer)?;
+
+        emit!(EventLoanSettingApplied {
+            effective_at: settings_change.effective_at(),
+        });
+        Ok(())
+    }
+
+    // The owner bounds what the settings admin can do. Only lowering the caps or lengthening
+    // the delay applies right away, see `propose_caps_change` for the rest
+    pub fn change_settings_caps(
+        ctx: Context<AccountsPoolOwner>,
+        settings_caps: SettingsCaps,
+        settings_delay: i64,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+
+        settings_caps.validate()?;
+        if settings_delay < 0 {
+            throw!(TakerError::InvalidSettingsDelay)
+        }
+
+        // the current settings must be within the new caps
+        pool.loan_settings().validate(&settings_caps)?;
+
+        // borrowers and lenders get to see the owner loosen the bounds too
+        if pool.settings_delay != 0
+            && (settings_caps.loosens(&pool.settings_caps) || settings_delay < pool.settings_delay)
+        {
+            throw!(TakerError::SettingsChangeMustBeQueued)
+        }
+
+        pool.settings_caps = settings_caps;
+        pool.settings_delay = settings_delay;
+
+        emit!(EventSettingsCapsChanged {
+            settings_caps,
+            settings_delay,
+        });
+        Ok(())
+    }
+
+    // Queues the caps and the delay until the current settings delay is over, see `execute_settings_change`
+    pub fn propose_caps_change(
+        ctx: Context<AccountsProposeCapsChange>,
+        settings_caps: SettingsCaps,
+        settings_delay: i64,
+    ) -> Result<()> {
+        let AccountsProposeCapsChange {
+            owner,
+            pool,
+            settings_change,
+            clock,
+            rent,
+            system_program,
+        } = ctx.accounts;
+
+        settings_caps.validate()?;
+        if settings_delay < 0 {
+            throw!(TakerError::InvalidSettingsDelay)
+        }
+
+        pool.loan_settings().validate(&settings_caps)?;
+
+        let effective_at = clock
+            .unix_timestamp
+            .checked_add(pool.settings_delay)
+            .ok_or(TakerError::MathOverflow)?;
+        SettingsChange::propose(
+            ctx.program_id,
+            pool.to_account_info().key,
+            QueuedChange::Caps {
+                settings_caps,
+                settings_delay,
+            },
+            effective_at,
+            owner,
+            settings_change,
+            rent,
+            system_program,
+        )?;
+
+        emit!(EventSettingsCapsProposed {
+            settings_caps,
+            settings_delay,
+            effective_at,
+        });
+        Ok(())
+    }
+
+    // As `change_loan_settings`, the loans snapshot the grace settings when they start
+    pub fn change_liquidation_settings(
+        ctx: Context<AccountsChangeLoanSetting>,
+        grace_period: Option<i64>,
+        grace_penalty_rate: Option<u64>,
+        auction_duration: Option<i64>,
+        auction_start_rate: Option<u64>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let mut settings = pool.loan_settings();
+
+        settings.grace_period = grace_period.unwrap_or(settings.grace_period);
+        settings.grace_penalty_rate = grace_penalty_rate.unwrap_or(settings.grace_penalty_rate);
+        settings.auction_duration = auction_duration.unwrap_or(settings.auction_duration);
+        settings.auction_start_rate = auction_start_rate.unwrap_or(settings.auction_start_rate);
+
+        settings.validate(&pool.settings_caps)?;
+
+        if pool.settings_delay != 0 {
+            throw!(TakerError::SettingsChangeMustBeQueued)
+        }
+        pool.set_loan_settings(&settings);
+
+        emit!(EventLiquidationSettingChanged {
+            grace_period: pool.grace_period,
+            grace_penalty_rate: pool.grace_penalty_rate,
+            auction_duration: pool.auction_duration,
+            auction_start_rate: pool.auction_start_rate,
+        });
+        Ok(())
+    }
+
+    // The ownership moves once the proposed owner accepts it, so that it cannot go to a wrong key
+    pub fn propose_owner(ctx: Context<AccountsPoolOwner>, pending_owner: Pubkey) -> Result<()> {
+        ctx.accounts.pool.pending_owner = pending_owner;
+
+        emit!(EventOwnerProposed { pending_owner });
+        Ok(())
+    }
+
+    pub fn accept_owner(ctx: Context<AccountsAcceptOwner>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let previous_owner = pool.owner;
+
+        pool.owner = pool.pending_owner;
+        pool.pending_owner = Pubkey::default();
+
+        emit!(EventOwnerChanged {
+            previous_owner,
+            owner: pool.owner,
+        });
+        Ok(())
+    }
+
+    // The owner hands out the other roles, e.g. the settings to a multisig and the fees to a treasury
+    pub fn change_roles(
+        ctx: Context<AccountsPoolOwner>,
+        fee_recipient: Option<Pubkey>,
+        settings_admin: Option<Pubkey>,
+        pauser: Option<Pubkey>,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+
+        pool.fee_recipient = fee_recipient.unwrap_or(pool.fee_recipient);
+        pool.settings_admin = settings_admin.unwrap_or(pool.settings_admin);
+        pool.pauser = pauser.unwrap_or(pool.pauser);
+
+        emit!(EventRolesChanged {
+            fee_recipient: pool.fee_recipient,
+            settings_admin: pool.settings_admin,
+            pauser: pool.pauser,
+        });
+        Ok(())
+    }
+
+    // Sweeps the service fees accrued in the fee vault since the last collection
+    pub fn collect_fees(ctx: Context<AccountsCollectFees>) -> Result<()> {
+        let AccountsCollectFees {
+            fee_recipient,
+            pool,
+            pool_fee_vault,
+            fee_recipient_dai_account,
+            spl_program,
+        } = ctx.accounts;
+
+        pool.ensure_not_paused(NFTPool::PAUSE_COLLECT_FEES)?;
+
+        pool.ensure_fee_vault(pool_fee_vault.to_account_info().key)?;
+
+        if fee_recipient_dai_account.mint != pool.dai_mint {
+            throw!(TakerError::MintMismatch)
+        }
+
+        let amount = pool
+            .fees_accrued
+            .checked_sub(pool.fees_collected)
+            .ok_or(TakerError::MathOverflow)?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_fee_vault.to_account_info(),
+                    to: fee_recipient_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            amount,
+        )?;
+        pool.fees_collected = pool.fees_accrued;
+
+        emit!(EventFeesCollected {
+            fee_recipient: *fee_recipient.key,
+            amount,
+            fees_collected: pool.fees_collected,
         });
         Ok(())
     }
 
-    // Deposits NFT asset into the pool, creating an entry of NFTListing
+    // Stops the instructions whose bit is set in `paused`, 0 resumes them all. repay and
+    // withdraw_nft cannot be paused so that the borrowers can always get their NFT back
+    pub fn set_pause(ctx: Context<AccountsSetPause>, paused: u32) -> Result<()> {
+        let AccountsSetPause { authority, pool } = ctx.accounts;
+
+        if authority.key != &pool.owner && authority.key != &pool.pauser {
+            throw!(TakerError::NotAuhorized)
+        }
+
+        if paused & !NFTPool::PAUSE_ALL != 0 {
+            throw!(TakerError::InvalidPauseFlags)
+        }
+        pool.paused = paused;
+
+        emit!(EventPauseChanged { paused });
+        Ok(())
+    }
+
+    // Applies to the loans started from now on, running loans keep the model they started with.
+    // As `change_loan_settings`, a pool with a settings delay queues it with `propose_settings_change`
+    pub fn change_interest_model(
+        ctx: Context<AccountsChangeLoanSetting>,
+        interest_model: InterestCurve,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let settings = LoanSettings {
+            interest_model,
+            ..pool.loan_settings()
+        };
+
+        settings.validate(&pool.settings_caps)?;
+
+        if pool.settings_delay != 0 {
+            throw!(TakerError::SettingsChangeMustBeQueued)
+        }
+        pool.set_loan_settings(&settings);
+
+        emit!(EventInterestModelChanged { interest_model });
+        Ok(())
+    }
+
+    // Deposits NFT asset into the pool, creating an entry of NFTDeposit
     pub fn deposit_nft(ctx: Context<AccountsDepositNFT>, deposit_id: Pubkey) -> Result<()> {
         let AccountsDepositNFT {
             pool,
@@ -155,9 +650,17 @@ pub mod taker {
             system_program,
         } = ctx.accounts;
 
-        assert_eq!(tkr_mint.to_account_info().key, &pool.tkr_mint);
-        assert_eq!(pool_tkr_account.mint, pool.tkr_mint);
-        assert_eq!(nft_mint.decimals, 0);
+        pool.ensure_not_paused(NFTPool::PAUSE_DEPOSIT)?;
+
+        if tkr_mint.to_account_info().key != &pool.tkr_mint
+            || pool_tkr_account.mint != pool.tkr_mint
+        {
+            throw!(TakerError::MintMismatch)
+        }
+
+        if nft_mint.decimals != 0 {
+            throw!(TakerError::NotAnNFT)
+        }
 
         // allocate the NFT ATA for the pool if not allocated
         NFTPool::ensure_pool_token_account(
@@ -186,6 +689,7 @@ pub mod taker {
         // error out if the account exists
         let deposit_account = NFTDeposit::deposit(
             ctx.program_id,
+            pool.to_account_info().key,
             &deposit_id,
             nft_mint.to_account_info().key,
             borrower_wallet_account,
@@ -216,7 +720,7 @@ pub mod taker {
                     to: borrower_tkr_account.clone(),
                     authority: pool.to_account_info(),
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
             ),
             pool.incentive,
         )?;
@@ -233,7 +737,11 @@ pub mod taker {
     }
 
     // withdraw the deposited NFT
-    pub fn withdraw_nft(ctx: Context<AccountsWithdrawNFT>, deposit_id: Pubkey) -> Result<()> {
+    pub fn withdraw_nft(
+        ctx: Context<AccountsWithdrawNFT>,
+        deposit_id: Pubkey,
+        close: bool,
+    ) -> Result<()> {
         // TODO: Do we set the minimal nft lock in time?
         let AccountsWithdrawNFT {
             pool,
@@ -248,12 +756,14 @@ pub mod taker {
         // verify the deposit account indeed belongs to the user
         let (_, bump) = NFTDeposit::get_address_with_bump(
             ctx.program_id,
+            pool.to_account_info().key,
             nft_mint.to_account_info().key,
             borrower_wallet_account.key,
             &deposit_id,
         );
         NFTDeposit::verify_address(
             ctx.program_id,
+            pool.to_account_info().key,
             nft_mint.to_account_info().key,
             borrower_wallet_account.key,
             &deposit_id,
@@ -273,7 +783,7 @@ pub mod taker {
                     to: borrower_nft_account.to_account_info(),
                     authority: pool.to_account_info(),
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
             ),
             1,
         )?;
@@ -283,10 +793,30 @@ pub mod taker {
             to: *borrower_wallet_account.key,
         });
 
+        if close {
+            NFTDeposit::close(deposit_account, borrower_wallet_account)?;
+
+            emit!(EventDepositClosed {
+                deposit_id,
+                borrower: *borrower_wallet_account.key,
+            });
+        }
+
         Ok(())
     }
 
-    pub fn place_bid(ctx: Context<AccountsPlaceBid>, price: u64, qty: u64) -> Result<()> {
+    // The bid uses the default terms of the pool when `terms` is not given. With `escrow`, the DAI
+    // of the bid moves into its escrow instead of being approved to the pool. Loans take between
+    // `min_amount` and `price` from the bid, until `expires_at` if given
+    pub fn place_bid(
+        ctx: Context<AccountsPlaceBid>,
+        price: u64,
+        qty: u64,
+        terms: Option<LoanTerms>,
+        escrow: bool,
+        expires_at: Option<i64>,
+        min_amount: u64,
+    ) -> Result<()> {
         if qty == 0 {
             return Ok(());
         }
@@ -295,41 +825,75 @@ pub mod taker {
             pool,
             lender_wallet_account,
             nft_mint,
+            dai_mint,
             lender_dai_account,
             bid_account,
+            bid_escrow_account,
             spl_program,
             system_program,
             rent,
         } = ctx.accounts;
 
+        pool.ensure_not_paused(NFTPool::PAUSE_PLACE_BID)?;
+
         if qty > nft_mint.supply {
             throw!(TakerError::NFTBidQtyLargerThanSupply);
         }
 
-        assert_eq!(nft_mint.decimals, 0);
+        let terms = terms.unwrap_or_else(|| pool.default_loan_terms());
+        terms.validate()?;
 
-        anchor_spl::token::approve(
-            CpiContext::new(
-                spl_program.clone(),
-                anchor_spl::token::Approve {
-                    to: lender_dai_account.to_account_info(),
-                    delegate: pool.to_account_info(),
-                    authority: lender_wallet_account.to_account_info(),
-                },
-            ),
-            price * qty,
-        )?;
+        if min_amount > price {
+            throw!(TakerError::InvalidBidLimits)
+        }
+
+        if nft_mint.decimals != 0 {
+            throw!(TakerError::NotAnNFT)
+        }
 
         // create the bid account if not created
         let mut bid_account = NFTBid::ensure(
             ctx.program_id,
-            nft_mint.to_account_info().key,
+            pool.to_account_info().key,
+            &BidTarget::Mint(*nft_mint.to_account_info().key),
             lender_wallet_account,
             bid_account,
             rent,
             system_program,
         )?;
-        bid_account.set(price, qty);
+        let offered = bid_account.offered();
+        bid_account.set(price, qty, terms, escrow);
+        bid_account.set_limits(expires_at.unwrap_or(0), min_amount);
+        pool.update_offered(offered, bid_account.offered());
+
+        // an escrowed bid holds its whole amount, the escrow of an approved bid is refunded
+        let amount = price.checked_mul(qty).ok_or(TakerError::MathOverflow)?;
+        NFTBid::fund_escrow(
+            ctx.program_id,
+            pool,
+            &bid_account.to_account_info(),
+            bid_escrow_account,
+            dai_mint,
+            lender_wallet_account,
+            lender_dai_account,
+            if escrow { amount } else { 0 },
+            spl_program,
+            system_program,
+            rent,
+        )?;
+        if !escrow {
+            anchor_spl::token::approve(
+                CpiContext::new(
+                    spl_program.clone(),
+                    anchor_spl::token::Approve {
+                        to: lender_dai_account.to_account_info(),
+                        delegate: pool.to_account_info(),
+                        authority: lender_wallet_account.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        }
 
         // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
         bid_account.exit(ctx.program_id)?;
@@ -339,6 +903,10 @@ pub mod taker {
             from: *lender_wallet_account.key,
             price,
             qty,
+            terms,
+            escrow,
+            expires_at: expires_at.unwrap_or(0),
+            min_amount,
         });
 
         Ok(())
@@ -346,24 +914,31 @@ pub mod taker {
 
     pub fn cancel_bid(ctx: Context<AccountsCancelBid>, revoke: bool) -> Result<()> {
         let AccountsCancelBid {
+            pool,
             lender_wallet_account,
             nft_mint,
             lender_dai_account,
             bid_account,
+            bid_escrow_account,
             spl_program,
         } = ctx.accounts;
 
-        assert_eq!(nft_mint.decimals, 0);
+        if nft_mint.decimals != 0 {
+            throw!(TakerError::NotAnNFT)
+        }
 
+        let target = BidTarget::Mint(*nft_mint.to_account_info().key);
         let (_, bump) = NFTBid::get_address_with_bump(
             ctx.program_id,
-            nft_mint.to_account_info().key,
+            pool.to_account_info().key,
+            &target,
             lender_wallet_account.key,
         );
 
         NFTBid::verify_address(
             ctx.program_id,
-            nft_mint.to_account_info().key,
+            pool.to_account_info().key,
+            &target,
             lender_wallet_account.key,
             bump,
             bid_account.to_account_info().key,
@@ -376,8 +951,19 @@ pub mod taker {
             qty: bid_account.qty,
         });
 
+        pool.update_offered(bid_account.offered(), 0);
         bid_account.cancel();
 
+        NFTBid::close_escrow(
+            ctx.program_id,
+            pool,
+            &bid_account.to_account_info(),
+            bid_escrow_account,
+            lender_wallet_account,
+            lender_dai_account,
+            spl_program,
+        )?;
+
         if revoke {
             solana_program::program::invoke(
                 &spl_token::instruction::revoke(
@@ -397,46 +983,269 @@ pub mod taker {
         Ok(())
     }
 
-    pub fn borrow(ctx: Context<AccountsBorrow>, amount: u64) -> Result<()> {
-        let AccountsBorrow {
+    // A bid that can be borrowed against with any NFT of the collection whose allowlist has the given root
+    pub fn place_collection_bid(
+        ctx: Context<AccountsPlaceCollectionBid>,
+        allowlist_root: [u8; 32],
+        price: u64,
+        qty: u64,
+        terms: Option<LoanTerms>,
+        escrow: bool,
+        expires_at: Option<i64>,
+        min_amount: u64,
+    ) -> Result<()> {
+        if qty == 0 {
+            return Ok(());
+        }
+
+        let AccountsPlaceCollectionBid {
             pool,
-            borrower_wallet_account,
             lender_wallet_account,
-
-            nft_mint,
-
-            pool_dai_account,
-            borrower_dai_account,
+            dai_mint,
             lender_dai_account,
-
-            pool_tai_account,
-            lender_tai_account,
-
             bid_account,
-            deposit_account,
-
+            bid_escrow_account,
             spl_program,
-            clock,
+            system_program,
+            rent,
         } = ctx.accounts;
 
-        if amount > bid_account.price {
-            throw!(TakerError::NFTBorrowExceedBidAmount)
-        }
+        pool.ensure_not_paused(NFTPool::PAUSE_PLACE_BID)?;
 
-        assert_eq!(lender_tai_account.mint, pool.tai_mint);
-        assert_eq!(pool_tai_account.mint, pool.tai_mint);
-        assert_eq!(lender_dai_account.mint, pool.dai_mint);
-        assert_eq!(borrower_dai_account.mint, pool.dai_mint);
+        let terms = terms.unwrap_or_else(|| pool.default_loan_terms());
+        terms.validate()?;
 
-        let (_, bump) = NFTDeposit::get_address_with_bump(
-            ctx.program_id,
-            nft_mint.to_account_info().key,
-            borrower_wallet_account.key,
-            &deposit_account.deposit_id,
-        );
+        if min_amount > price {
+            throw!(TakerError::InvalidBidLimits)
+        }
 
-        NFTDeposit::verify_address(
+        // create the bid account if not created
+        let mut bid_account = NFTBid::ensure(
             ctx.program_id,
+            pool.to_account_info().key,
+            &BidTarget::Collection(allowlist_root),
+            lender_wallet_account,
+            bid_account,
+            rent,
+            system_program,
+        )?;
+        let offered = bid_account.offered();
+        bid_account.set(price, qty, terms, escrow);
+        bid_account.set_limits(expires_at.unwrap_or(0), min_amount);
+        pool.update_offered(offered, bid_account.offered());
+
+        // an escrowed bid holds its whole amount, the escrow of an approved bid is refunded
+        let amount = price.checked_mul(qty).ok_or(TakerError::MathOverflow)?;
+        NFTBid::fund_escrow(
+            ctx.program_id,
+            pool,
+            &bid_account.to_account_info(),
+            bid_escrow_account,
+            dai_mint,
+            lender_wallet_account,
+            lender_dai_account,
+            if escrow { amount } else { 0 },
+            spl_program,
+            system_program,
+            rent,
+        )?;
+        if !escrow {
+            anchor_spl::token::approve(
+                CpiContext::new(
+                    spl_program.clone(),
+                    anchor_spl::token::Approve {
+                        to: lender_dai_account.to_account_info(),
+                        delegate: pool.to_account_info(),
+                        authority: lender_wallet_account.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        }
+
+        // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
+        bid_account.exit(ctx.program_id)?;
+
+        emit!(EventCollectionBidPlaced {
+            allowlist_root,
+            from: *lender_wallet_account.key,
+            price,
+            qty,
+            terms,
+            escrow,
+            expires_at: expires_at.unwrap_or(0),
+            min_amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_collection_bid(
+        ctx: Context<AccountsCancelCollectionBid>,
+        allowlist_root: [u8; 32],
+        revoke: bool,
+    ) -> Result<()> {
+        let AccountsCancelCollectionBid {
+            pool,
+            lender_wallet_account,
+            lender_dai_account,
+            bid_account,
+            bid_escrow_account,
+            spl_program,
+        } = ctx.accounts;
+
+        let target = BidTarget::Collection(allowlist_root);
+        let (_, bump) = NFTBid::get_address_with_bump(
+            ctx.program_id,
+            pool.to_account_info().key,
+            &target,
+            lender_wallet_account.key,
+        );
+
+        NFTBid::verify_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            &target,
+            lender_wallet_account.key,
+            bump,
+            bid_account.to_account_info().key,
+        )?;
+
+        emit!(EventCollectionBidCancelled {
+            allowlist_root,
+            from: *lender_wallet_account.key,
+            price: bid_account.price,
+            qty: bid_account.qty,
+        });
+
+        pool.update_offered(bid_account.offered(), 0);
+        bid_account.cancel();
+
+        NFTBid::close_escrow(
+            ctx.program_id,
+            pool,
+            &bid_account.to_account_info(),
+            bid_escrow_account,
+            lender_wallet_account,
+            lender_dai_account,
+            spl_program,
+        )?;
+
+        if revoke {
+            solana_program::program::invoke(
+                &spl_token::instruction::revoke(
+                    &spl_token::id(),
+                    lender_dai_account.to_account_info().key,
+                    lender_wallet_account.to_account_info().key,
+                    &[lender_wallet_account.key],
+                )?,
+                &[
+                    lender_dai_account.to_account_info(),
+                    lender_wallet_account.to_account_info(),
+                    spl_program.clone(),
+                ],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // `proof` is only needed for collection bids, it shows that the NFT is in the allowlist
+    pub fn borrow(ctx: Context<AccountsBorrow>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        let AccountsBorrow {
+            pool,
+            borrower_wallet_account,
+            lender_wallet_account,
+
+            nft_mint,
+
+            pool_dai_account,
+            borrower_dai_account,
+            lender_dai_account,
+
+            pool_tai_account,
+            lender_tai_account,
+
+            bid_account,
+            bid_escrow_account,
+            deposit_account,
+
+            loan_note_mint,
+            lender_note_account,
+
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        pool.ensure_not_paused(NFTPool::PAUSE_BORROW)?;
+
+        // the bid must be the lender's, otherwise any bid could be paired with the lender's DAI approval
+        let (_, bump) = NFTBid::get_address_with_bump(
+            ctx.program_id,
+            pool.to_account_info().key,
+            &bid_account.target,
+            lender_wallet_account.key,
+        );
+        NFTBid::verify_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            &bid_account.target,
+            lender_wallet_account.key,
+            bump,
+            bid_account.to_account_info().key,
+        )
+        .map_err(|_| TakerError::BidLenderMismatch)?;
+
+        if &lender_dai_account.owner != lender_wallet_account.key {
+            throw!(TakerError::LenderAccountMismatch)
+        }
+
+        if !bid_account
+            .target
+            .covers(nft_mint.to_account_info().key, &proof)
+        {
+            throw!(TakerError::NFTNotCoveredByBid)
+        }
+
+        if &deposit_account.nft_mint() != nft_mint.to_account_info().key {
+            throw!(TakerError::DepositMintMismatch)
+        }
+
+        // decrease the bid qty by 1, the amount must be within the limits of the bid
+        let offered = bid_account.offered();
+        bid_account.trade(1, amount, clock.unix_timestamp)?;
+        pool.update_offered(offered, bid_account.offered());
+
+        // the pool is the delegate of the lenders' DAI, the pool side must be the pool's own
+        if &pool_dai_account.owner != pool.to_account_info().key
+            || &pool_tai_account.owner != pool.to_account_info().key
+        {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        if lender_tai_account.mint != pool.tai_mint
+            || pool_tai_account.mint != pool.tai_mint
+            || lender_dai_account.mint != pool.dai_mint
+            || borrower_dai_account.mint != pool.dai_mint
+            || pool_dai_account.mint != pool.dai_mint
+        {
+            throw!(TakerError::MintMismatch)
+        }
+
+        let (_, bump) = NFTDeposit::get_address_with_bump(
+            ctx.program_id,
+            pool.to_account_info().key,
+            nft_mint.to_account_info().key,
+            borrower_wallet_account.key,
+            &deposit_account.deposit_id,
+        );
+
+        NFTDeposit::verify_address(
+            ctx.program_id,
+            pool.to_account_info().key,
             nft_mint.to_account_info().key,
             borrower_wallet_account.key,
             &deposit_account.deposit_id,
@@ -444,39 +1253,54 @@ pub mod taker {
             deposit_account.to_account_info().key,
         )?;
 
-        // set related records
+        // set related records, the loan follows the terms of the bid and the current settings of the pool
+        let terms = bid_account.terms;
         let total_amount = amount;
         let borrowed_amount = total_amount
-            .checked_mul(pool.mortgage_rate)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
+            .checked_mul(terms.mortgage_rate)
+            .and_then(|v| v.checked_div(10000))
+            .ok_or(TakerError::MathOverflow)?;
 
-        if borrowed_amount <= 0 {
+        if borrowed_amount == 0 {
             throw!(TakerError::BorrowedAmountTooSmall)
         }
 
+        // the loan is priced on the utilization of the pool with the loan in it
+        pool.add_outstanding(borrowed_amount);
         deposit_account.start_borrow(
             *lender_wallet_account.key,
             total_amount,
             borrowed_amount,
             clock.unix_timestamp,
-            pool.max_loan_duration,
+            &terms,
+            pool,
         )?;
 
-        // decrease the bid qty by 1;
-        bid_account.trade(1)?;
-
-        // transfer DAI to the pool
+        // transfer DAI to the pool, from the escrow of the bid or the lender's account
+        let lender_funds = if bid_account.escrowed {
+            let (_, bump) = NFTBid::get_escrow_address_with_bump(
+                ctx.program_id,
+                bid_account.to_account_info().key,
+            );
+            NFTBid::verify_escrow_address(
+                ctx.program_id,
+                bid_account.to_account_info().key,
+                bump,
+                bid_escrow_account.key,
+            )?;
+            bid_escrow_account.clone()
+        } else {
+            lender_dai_account.to_account_info()
+        };
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: lender_dai_account.to_account_info(),
+                    from: lender_funds,
                     to: pool_dai_account.to_account_info(),
-                    authority: pool.to_account_info(), // The pool is the delegate
+                    authority: pool.to_account_info(), // The pool owns the escrow or is the delegate
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
             ),
             total_amount,
         )?;
@@ -490,7 +1314,7 @@ pub mod taker {
                     to: borrower_dai_account.to_account_info(),
                     authority: pool.to_account_info(), // The pool is the delegate
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
             ),
             borrowed_amount,
         )?;
@@ -504,124 +1328,144 @@ pub mod taker {
                     to: lender_tai_account.to_account_info(),
                     authority: pool.to_account_info(), // The pool is the delegate
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
             ),
             borrowed_amount,
         )?;
 
+        // the claim of the lender on the loan is the note, it can be transferred
+        NFTDeposit::issue_loan_note(
+            ctx.program_id,
+            pool,
+            deposit_account.to_account_info().key,
+            lender_wallet_account,
+            borrower_wallet_account,
+            loan_note_mint,
+            lender_note_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
         emit!(EventBorrowed {
             borrower: *borrower_wallet_account.key,
             lender: *lender_wallet_account.key,
             amount: borrowed_amount,
-            length: pool.max_loan_duration
+            length: terms.max_loan_duration
         });
 
         Ok(())
     }
 
-    pub fn repay(ctx: Context<AccountsRepay>) -> Result<()> {
-        let AccountsRepay {
+    // Publishes a loan request on the deposit, placing it again updates the request
+    pub fn place_listing(
+        ctx: Context<AccountsPlaceListing>,
+        amount: u64,
+        max_interest_rate: u64,
+        loan_duration: i64,
+    ) -> Result<()> {
+        let AccountsPlaceListing {
             pool,
             borrower_wallet_account,
-            pool_owner_dai_account,
-            borrower_dai_account,
-            lender_dai_account,
-
-            borrower_nft_account,
-            pool_nft_account,
-
             deposit_account,
-            spl_program,
-            clock,
+            listing_account,
+            rent,
+            system_program,
         } = ctx.accounts;
 
-        let loan = deposit_account.get_active_state()?;
+        pool.ensure_not_paused(NFTPool::PAUSE_PLACE_LISTING)?;
 
-        if clock.unix_timestamp > loan.expired_at {
-            throw!(TakerError::LoanLiquidated)
+        if amount == 0 {
+            throw!(TakerError::BorrowedAmountTooSmall)
         }
 
-        assert!(pool_owner_dai_account.owner == pool.owner);
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
 
-        let (interest, fee) = pool.calculate_interest_and_fee(
-            loan.borrowed_amount,
-            clock.unix_timestamp.saturating_sub(loan.started_at),
-        );
+        if &deposit_account.borrower() != borrower_wallet_account.key {
+            throw!(TakerError::BorrowerMismatch)
+        }
 
-        // transfer fee to the owner
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: borrower_dai_account.to_account_info(),
-                    to: pool_owner_dai_account.to_account_info(),
-                    authority: borrower_wallet_account.to_account_info(),
-                },
-            ),
-            fee,
+        if !matches!(deposit_account.state(), DepositState::PendingLoan) {
+            throw!(TakerError::BorrowAlreadyStarted)
+        }
+
+        // create the listing account if not created
+        let mut listing_account = NFTListing::ensure(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            borrower_wallet_account,
+            listing_account,
+            rent,
+            system_program,
         )?;
+        listing_account.set(amount, max_interest_rate, loan_duration);
 
-        let lender_income = interest.checked_sub(fee).unwrap();
-        let repayed_amount = loan.total_amount.checked_add(lender_income).unwrap();
+        // the request must make a valid loan at its highest rate
+        let terms = listing_account.terms(max_interest_rate, pool.mortgage_rate)?;
 
-        // transfer the DAI to the pool, waiting for the lender to withdraw
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: borrower_dai_account.to_account_info(),
-                    to: lender_dai_account.to_account_info(),
-                    authority: borrower_wallet_account.to_account_info(),
-                },
-            ),
-            repayed_amount,
-        )?;
+        // Persistent back the data. Since we created the ProgramAccount by ourselves, we need to do this manually.
+        listing_account.exit(ctx.program_id)?;
 
-        // transfer the NFT to the borrower
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                spl_program.clone(),
-                anchor_spl::token::Transfer {
-                    from: pool_nft_account.to_account_info(),
-                    to: borrower_nft_account.to_account_info(),
-                    authority: pool.to_account_info(),
-                },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
-            ),
-            1,
+        emit!(EventListingPlaced {
+            deposit_id: deposit_account.deposit_id(),
+            borrower: *borrower_wallet_account.key,
+            amount,
+            max_interest_rate,
+            loan_duration: terms.max_loan_duration,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_listing(ctx: Context<AccountsCancelListing>) -> Result<()> {
+        let AccountsCancelListing {
+            borrower_wallet_account,
+            listing_account,
+        } = ctx.accounts;
+
+        let (_, bump) = NFTListing::get_address_with_bump(ctx.program_id, &listing_account.deposit);
+        NFTListing::verify_address(
+            ctx.program_id,
+            &listing_account.deposit,
+            bump,
+            listing_account.to_account_info().key,
         )?;
 
-        // set corresponding records
-        deposit_account.repay(loan.borrowed_amount.checked_add(lender_income).unwrap())?;
+        // also checks the borrower
+        NFTListing::close(listing_account, borrower_wallet_account)?;
 
-        emit!(EventRepayed {
+        emit!(EventListingCancelled {
+            deposit: listing_account.deposit,
             borrower: *borrower_wallet_account.key,
-            lender: loan.lender,
-            amount: repayed_amount,
-            fee,
-            lender_income
         });
 
         Ok(())
     }
 
-    pub fn liquidate(ctx: Context<AccountsLiquidate>) -> Result<()> {
-        let AccountsLiquidate {
+    // The lender fills the listing of the borrower at `interest_rate`, the loan starts as with `borrow`
+    pub fn fund_listing(ctx: Context<AccountsFundListing>, interest_rate: u64) -> Result<()> {
+        let AccountsFundListing {
             pool,
             lender_wallet_account,
+            borrower_wallet_account,
 
-            pool_owner_dai_account,
             pool_dai_account,
+            borrower_dai_account,
             lender_dai_account,
 
-            nft_mint,
-            pool_nft_account,
-            lender_nft_account,
-
-            lender_tai_account,
             pool_tai_account,
+            lender_tai_account,
 
             deposit_account,
+            listing_account,
+
+            loan_note_mint,
+            lender_note_account,
 
             ata_program,
             spl_program,
@@ -630,218 +1474,1589 @@ pub mod taker {
             clock,
         } = ctx.accounts;
 
-        let loan = deposit_account.get_active_state()?;
+        pool.ensure_not_paused(NFTPool::PAUSE_BORROW)?;
 
-        if clock.unix_timestamp <= loan.expired_at {
-            throw!(TakerError::LoanNotExpired)
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        let (_, bump) = NFTListing::get_address_with_bump(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+        );
+        NFTListing::verify_address(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            bump,
+            listing_account.to_account_info().key,
+        )?;
+
+        if &deposit_account.borrower() != borrower_wallet_account.key {
+            throw!(TakerError::BorrowerMismatch)
         }
 
-        // Transfer the corresponding TAI to the pool
+        // the pool signs the transfers out of its accounts, they must be the pool's own
+        if &pool_dai_account.owner != pool.to_account_info().key
+            || &pool_tai_account.owner != pool.to_account_info().key
+        {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        if lender_tai_account.mint != pool.tai_mint
+            || pool_tai_account.mint != pool.tai_mint
+            || lender_dai_account.mint != pool.dai_mint
+            || borrower_dai_account.mint != pool.dai_mint
+            || pool_dai_account.mint != pool.dai_mint
+        {
+            throw!(TakerError::MintMismatch)
+        }
+
+        // the loan follows the request of the borrower and the current settings of the pool
+        let terms = listing_account.terms(interest_rate, pool.mortgage_rate)?;
+        let total_amount = listing_account.amount;
+        let borrowed_amount = total_amount
+            .checked_mul(terms.mortgage_rate)
+            .and_then(|v| v.checked_div(10000))
+            .ok_or(TakerError::MathOverflow)?;
+
+        if borrowed_amount == 0 {
+            throw!(TakerError::BorrowedAmountTooSmall)
+        }
+
+        // the loan is priced on the utilization of the pool with the loan in it
+        pool.add_outstanding(borrowed_amount);
+        deposit_account.start_borrow(
+            *lender_wallet_account.key,
+            total_amount,
+            borrowed_amount,
+            clock.unix_timestamp,
+            &terms,
+            pool,
+        )?;
+
+        // transfer DAI to the pool, the lender signs for it
         anchor_spl::token::transfer(
             CpiContext::new(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: lender_tai_account.to_account_info(),
-                    to: pool_tai_account.to_account_info(),
-                    authority: lender_wallet_account.to_account_info(),
+                    from: lender_dai_account.to_account_info(),
+                    to: pool_dai_account.to_account_info(),
+                    authority: lender_wallet_account.clone(),
                 },
             ),
-            loan.borrowed_amount,
+            total_amount,
         )?;
 
-        // charge service fee using max_borrow_duration
-        let (_, fee) =
-            pool.calculate_interest_and_fee(loan.borrowed_amount, pool.max_loan_duration);
-
-        // transfer fee to the owner
+        // transfer DAI to the borrower
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
                     from: pool_dai_account.to_account_info(),
-                    to: pool_owner_dai_account.to_account_info(),
+                    to: borrower_dai_account.to_account_info(),
                     authority: pool.to_account_info(),
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
             ),
-            fee,
+            borrowed_amount,
         )?;
 
-        let withdrawable = loan.total_amount - loan.borrowed_amount - fee;
-
-        // Transfer the remaining DAI to the lender
+        // transfer TAI to the lender
         anchor_spl::token::transfer(
             CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: pool_dai_account.to_account_info(),
-                    to: lender_dai_account.to_account_info(),
+                    from: pool_tai_account.to_account_info(),
+                    to: lender_tai_account.to_account_info(),
                     authority: pool.to_account_info(),
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
             ),
-            withdrawable,
+            borrowed_amount,
         )?;
 
-        // allocate the NFT ATA for the lender if not allocate
-        NFTPool::ensure_user_token_account(
+        // the claim of the lender on the loan is the note, it can be transferred
+        NFTDeposit::issue_loan_note(
+            ctx.program_id,
+            pool,
+            deposit_account.to_account_info().key,
             lender_wallet_account,
-            nft_mint,
-            lender_nft_account,
+            lender_wallet_account,
+            loan_note_mint,
+            lender_note_account,
             ata_program,
             spl_program,
             system_program,
             rent,
         )?;
 
-        // Transfer the NFT to the lender
+        // the listing is filled, its rent goes back to the borrower
+        NFTListing::close(listing_account, borrower_wallet_account)?;
+
+        emit!(EventListingFunded {
+            deposit_id: deposit_account.deposit_id(),
+            borrower: *borrower_wallet_account.key,
+            lender: *lender_wallet_account.key,
+            amount: borrowed_amount,
+            interest_rate,
+            length: terms.max_loan_duration,
+        });
+
+        Ok(())
+    }
+
+    pub fn repay(ctx: Context<AccountsRepay>) -> Result<()> {
+        let AccountsRepay {
+            pool,
+            borrower_wallet_account,
+            pool_fee_vault,
+            borrower_dai_account,
+            pool_dai_account,
+
+            borrower_nft_account,
+            pool_nft_account,
+
+            deposit_account,
+            spl_program,
+            clock,
+        } = ctx.accounts;
+
+        // the deposit must belong to this pool, whose DAI records the repayment
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        if &deposit_account.borrower() != borrower_wallet_account.key {
+            throw!(TakerError::BorrowerMismatch)
+        }
+
+        // the repayment waits in the pool for the lender, see `withdraw_locked_asset`
+        if &pool_dai_account.owner != pool.to_account_info().key
+            || &pool_nft_account.owner != pool.to_account_info().key
+        {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        if pool_dai_account.mint != pool.dai_mint || borrower_dai_account.mint != pool.dai_mint {
+            throw!(TakerError::MintMismatch)
+        }
+
+        if pool_nft_account.mint != deposit_account.nft_mint() {
+            throw!(TakerError::DepositMintMismatch)
+        }
+
+        let loan = deposit_account.get_active_state()?;
+
+        // a late borrower can still repay during the grace period of the loan, with a penalty for the lender
+        if clock.unix_timestamp > loan.grace_end() {
+            throw!(TakerError::LoanLiquidated)
+        }
+
+        let penalty = if clock.unix_timestamp > loan.expired_at() {
+            loan.grace_penalty()
+        } else {
+            0
+        };
+
+        pool.ensure_fee_vault(pool_fee_vault.to_account_info().key)?;
+
+        let (interest, fee) =
+            loan.calculate_interest_and_fee(clock.unix_timestamp.saturating_sub(loan.started_at))?;
+
+        // transfer fee to the fee vault
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: pool_fee_vault.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+        pool.accrue_fee(fee)?;
+
+        let lender_income = interest
+            .checked_sub(fee)
+            .and_then(|v| v.checked_add(penalty))
+            .ok_or(TakerError::MathOverflow)?;
+        let repayed_amount = loan
+            .outstanding_amount()
+            .checked_add(lender_income)
+            .ok_or(TakerError::MathOverflow)?;
+
+        // transfer the DAI to the pool, waiting for the lender to withdraw
         anchor_spl::token::transfer(
             CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: pool_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            repayed_amount,
+        )?;
+
+        // transfer the NFT to the borrower
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
                     from: pool_nft_account.to_account_info(),
-                    to: lender_nft_account.to_account_info(),
+                    to: borrower_nft_account.to_account_info(),
                     authority: pool.to_account_info(),
                 },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
             ),
             1,
         )?;
 
         // set corresponding records
-        deposit_account.liquidate()?;
+        pool.remove_outstanding(loan.outstanding_amount());
+        // the pool now holds the mortgage margin plus the repayed amount, both belong to the lender
+        deposit_account.repay(
+            loan.total_amount
+                .checked_add(lender_income)
+                .ok_or(TakerError::MathOverflow)?,
+        )?;
 
-        emit!(EventLiquidated {
-            lender: *lender_wallet_account.key,
-            loan_id: deposit_account.deposit_id,
-            withdrawable,
+        emit!(EventRepayed {
+            borrower: *borrower_wallet_account.key,
+            lender: loan.lender,
+            amount: repayed_amount,
+            fee,
+            lender_income,
+            penalty,
         });
 
         Ok(())
     }
 
-    pub fn withdraw_locked_asset(ctx: Context<AccountsWithdrawLockedAsset>) -> Result<()> {
-        let AccountsWithdrawLockedAsset {
+    // Pays back part of the principal with the interest accrued so far, the NFT stays locked
+    // until `repay` settles the rest of the loan
+    pub fn repay_partial(ctx: Context<AccountsRepayPartial>, amount: u64) -> Result<()> {
+        let AccountsRepayPartial {
             pool,
-            lender_wallet_account,
-
-            lender_tai_account,
-            pool_tai_account,
-
-            lender_dai_account,
+            borrower_wallet_account,
+            pool_fee_vault,
+            borrower_dai_account,
             pool_dai_account,
 
             deposit_account,
-
             spl_program,
+            clock,
         } = ctx.accounts;
 
-        let repay = deposit_account.get_repayed_state()?;
+        pool.ensure_not_paused(NFTPool::PAUSE_REPAY_PARTIAL)?;
 
-        // Transfer the TAI to the pool
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        if &deposit_account.borrower() != borrower_wallet_account.key {
+            throw!(TakerError::BorrowerMismatch)
+        }
+
+        let loan = deposit_account.get_active_state()?;
+
+        if clock.unix_timestamp > loan.expired_at() {
+            throw!(TakerError::LoanLiquidated)
+        }
+
+        pool.ensure_fee_vault(pool_fee_vault.to_account_info().key)?;
+
+        if &pool_dai_account.owner != pool.to_account_info().key {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        if pool_dai_account.mint != pool.dai_mint || borrower_dai_account.mint != pool.dai_mint {
+            throw!(TakerError::MintMismatch)
+        }
+
+        let (interest, fee) =
+            loan.calculate_interest_and_fee(clock.unix_timestamp.saturating_sub(loan.started_at))?;
+        let lender_income = interest.checked_sub(fee).ok_or(TakerError::MathOverflow)?;
+
+        let loan = deposit_account.repay_partial(amount, lender_income, clock.unix_timestamp)?;
+        pool.remove_outstanding(amount);
+
+        // transfer fee to the fee vault
         anchor_spl::token::transfer(
             CpiContext::new(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: lender_tai_account.to_account_info(),
-                    to: pool_tai_account.to_account_info(),
-                    authority: lender_wallet_account.to_account_info(),
+                    from: borrower_dai_account.to_account_info(),
+                    to: pool_fee_vault.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
                 },
             ),
-            repay.tai_required_to_unlock,
+            fee,
         )?;
+        pool.accrue_fee(fee)?;
 
-        // Transfer the DAI to the lender
+        // the principal and the lender's interest wait in the pool, the lender still holds the TAI
+        // of the whole loan and gets them back with `withdraw_locked_asset` or `liquidate`
+        let repayed_amount = amount
+            .checked_add(lender_income)
+            .ok_or(TakerError::MathOverflow)?;
         anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
+            CpiContext::new(
                 spl_program.clone(),
                 anchor_spl::token::Transfer {
-                    from: pool_dai_account.to_account_info(),
-                    to: lender_dai_account.to_account_info(),
-                    authority: pool.to_account_info(),
+                    from: borrower_dai_account.to_account_info(),
+                    to: pool_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
                 },
-                &[&[NFTPool::SEED, &[pool.bump_seed]]],
             ),
-            repay.lender_withdrawable,
+            repayed_amount,
         )?;
 
-        deposit_account.clear()?;
-
-        emit!(EventWithDrawLockedAsset {
-            lender: *lender_wallet_account.key,
-            amount: repay.lender_withdrawable,
+        emit!(EventPartiallyRepayed {
+            borrower: *borrower_wallet_account.key,
+            lender: loan.lender,
+            amount,
+            fee,
+            lender_income,
+            outstanding: loan.outstanding_amount(),
         });
 
         Ok(())
     }
-}
-
-#[derive(Accounts)]
-pub struct AccountsInitialize<'info> {
-    #[account(signer)]
-    pub pool_owner: AccountInfo<'info>, // also the funder and the fee collector
-    #[account(mut)]
-    pub pool: AccountInfo<'info>, // We cannot use  ProgramAccount<'info, TakerContract> here because it is not allocated yet
 
-    pub tkr_mint: CpiAccount<'info, Mint>,
-    #[account(mut)]
-    pub pool_tkr_account: AccountInfo<'info>, // this is not allocated yet
+    // Pays the interest accrued so far and pushes the expiry of the loan by `extension` secs.
+    // The holder of the loan note agrees by signing, or beforehand through the `max_extension` of its bid,
+    // in which case the loan never runs longer than the `max_loan_duration` of the bid from now
+    pub fn extend_loan(
+        ctx: Context<AccountsExtendLoan>,
+        extension: i64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let AccountsExtendLoan {
+            pool,
+            borrower_wallet_account,
+            lender_wallet_account,
 
-    pub tai_mint: CpiAccount<'info, Mint>,
-    #[account(mut)]
-    pub pool_tai_account: AccountInfo<'info>, // this is not allocated yet
+            pool_fee_vault,
+            borrower_dai_account,
+            lender_dai_account,
 
-    pub dai_mint: CpiAccount<'info, Mint>,
-    #[account(mut)]
-    pub pool_dai_account: AccountInfo<'info>, // this is not allocated yet
+            bid_account,
+            deposit_account,
 
-    pub ata_program: AccountInfo<'info>,
-    pub spl_program: AccountInfo<'info>,
-    pub system_program: AccountInfo<'info>,
-    pub rent: Sysvar<'info, Rent>,
-}
-#[derive(Accounts)]
-pub struct AccountsChangeLoanSetting<'info> {
-    #[account(signer)]
-    pub owner: AccountInfo<'info>, // only owner can change the setting
-    #[account(mut, has_one = owner)]
-    pub pool: ProgramAccount<'info, NFTPool>,
-}
+            loan_note_mint,
+            lender_note_account,
 
-#[derive(Accounts)]
-pub struct AccountsDepositNFT<'info> {
-    pub pool: ProgramAccount<'info, NFTPool>,
-    #[account(signer)]
-    pub borrower_wallet_account: AccountInfo<'info>,
+            spl_program,
+            clock,
+        } = ctx.accounts;
 
-    pub nft_mint: CpiAccount<'info, Mint>,
-    pub tkr_mint: CpiAccount<'info, Mint>,
+        pool.ensure_not_paused(NFTPool::PAUSE_EXTEND_LOAN)?;
 
-    #[account(mut)]
-    pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
-    #[account(mut)]
-    pub pool_nft_account: AccountInfo<'info>, // potentially this is not allocated yet
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
 
-    #[account(mut)]
-    pub borrower_tkr_account: AccountInfo<'info>, // potentially this is not allocated yet
-    #[account(mut)]
-    pub pool_tkr_account: CpiAccount<'info, TokenAccount>,
+        if &deposit_account.borrower() != borrower_wallet_account.key {
+            throw!(TakerError::BorrowerMismatch)
+        }
 
-    #[account(mut)]
-    pub deposit_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTDeposit>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+        let loan = deposit_account.get_active_state()?;
 
-    pub rent: Sysvar<'info, Rent>,
+        if clock.unix_timestamp > loan.expired_at() {
+            throw!(TakerError::LoanLiquidated)
+        }
 
-    pub ata_program: AccountInfo<'info>,
-    pub spl_program: AccountInfo<'info>,
-    pub system_program: AccountInfo<'info>,
-}
+        if extension <= 0 {
+            throw!(TakerError::InvalidExtension)
+        }
+
+        // the holder of the loan note consents and is paid in place of the lender
+        NFTDeposit::verify_loan_note_holder(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            &loan.lender,
+            lender_wallet_account.key,
+            loan_note_mint,
+            lender_note_account,
+        )?;
+
+        if !lender_wallet_account.is_signer {
+            let bid_account = ProgramAccount::<NFTBid>::try_from(bid_account)?;
+
+            let (_, bump) = NFTBid::get_address_with_bump(
+                ctx.program_id,
+                pool.to_account_info().key,
+                &bid_account.target,
+                lender_wallet_account.key,
+            );
+            NFTBid::verify_address(
+                ctx.program_id,
+                pool.to_account_info().key,
+                &bid_account.target,
+                lender_wallet_account.key,
+                bump,
+                bid_account.to_account_info().key,
+            )
+            .map_err(|_| TakerError::BidLenderMismatch)?;
+
+            if !bid_account
+                .target
+                .covers(&deposit_account.nft_mint(), &proof)
+            {
+                throw!(TakerError::NFTNotCoveredByBid)
+            }
+
+            // a cancelled or filled bid no longer speaks for the lender
+            if bid_account.qty == 0 || bid_account.price == 0 {
+                throw!(TakerError::ExtensionNotAllowed)
+            }
+            if bid_account.is_expired(clock.unix_timestamp) {
+                throw!(TakerError::BidExpired)
+            }
+
+            let terms = bid_account.terms;
+            let longest = clock
+                .unix_timestamp
+                .checked_add(terms.max_loan_duration)
+                .ok_or(TakerError::MathOverflow)?;
+            if extension > terms.max_extension
+                || loan.expired_at().saturating_add(extension) > longest
+            {
+                throw!(TakerError::ExtensionNotAllowed)
+            }
+        }
+
+        if &lender_dai_account.owner != lender_wallet_account.key {
+            throw!(TakerError::LenderAccountMismatch)
+        }
+
+        if lender_dai_account.mint != pool.dai_mint || borrower_dai_account.mint != pool.dai_mint {
+            throw!(TakerError::MintMismatch)
+        }
+
+        pool.ensure_fee_vault(pool_fee_vault.to_account_info().key)?;
+
+        let (interest, fee) =
+            loan.calculate_interest_and_fee(clock.unix_timestamp.saturating_sub(loan.started_at))?;
+
+        // transfer fee to the fee vault
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: pool_fee_vault.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+        pool.accrue_fee(fee)?;
+
+        // the rest of the interest goes straight to the lender
+        let lender_income = interest.checked_sub(fee).ok_or(TakerError::MathOverflow)?;
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: borrower_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: borrower_wallet_account.to_account_info(),
+                },
+            ),
+            lender_income,
+        )?;
+
+        let loan = deposit_account.extend(clock.unix_timestamp, extension)?;
+
+        emit!(EventLoanExtended {
+            borrower: *borrower_wallet_account.key,
+            lender: loan.lender,
+            deposit_id: deposit_account.deposit_id,
+            fee,
+            lender_income,
+            expired_at: loan.expired_at(),
+        });
+
+        Ok(())
+    }
+
+    // Lets anyone refinance the loan of the deposit at better rates, see `refinance`
+    pub fn set_auto_refinance(ctx: Context<AccountsSetAutoRefinance>, enabled: bool) -> Result<()> {
+        let AccountsSetAutoRefinance {
+            pool,
+            borrower_wallet_account,
+            deposit_account,
+            auto_refinance_account,
+            rent,
+            system_program,
+        } = ctx.accounts;
+
+        if enabled {
+            pool.ensure_not_paused(NFTPool::PAUSE_SET_AUTO_REFINANCE)?;
+        }
+
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        if &deposit_account.borrower() != borrower_wallet_account.key {
+            throw!(TakerError::BorrowerMismatch)
+        }
+
+        if enabled {
+            AutoRefinance::enable(
+                ctx.program_id,
+                deposit_account.to_account_info().key,
+                borrower_wallet_account,
+                auto_refinance_account,
+                rent,
+                system_program,
+            )?;
+        } else {
+            AutoRefinance::disable(
+                ctx.program_id,
+                deposit_account.to_account_info().key,
+                borrower_wallet_account,
+                auto_refinance_account,
+            )?;
+        }
+
+        emit!(EventAutoRefinanceChanged {
+            deposit_id: deposit_account.deposit_id(),
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    // The lender of the bid pays off the current lender and takes over the loan, which restarts with the
+    // terms of the bid and the debt of the borrower as its principal. The borrower signs, or opted in with
+    // `set_auto_refinance` and the bid has a lower rate without shortening the loan. The current lender
+    // gets the DAI back from its RefinancePayoff by returning the TAI, the new lender receives new TAI
+    pub fn refinance(ctx: Context<AccountsRefinance>, proof: Vec<[u8; 32]>) -> Result<()> {
+        let AccountsRefinance {
+            pool,
+            payer,
+            borrower_wallet_account,
+            lender_wallet_account,
+
+            pool_fee_vault,
+            pool_dai_account,
+            lender_dai_account,
+
+            pool_tai_account,
+            lender_tai_account,
+
+            deposit_account,
+            bid_account,
+            bid_escrow_account,
+            auto_refinance_account,
+            payoff_account,
+
+            loan_note_mint,
+            lender_note_account,
+
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        pool.ensure_not_paused(NFTPool::PAUSE_REFINANCE)?;
+
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        if &deposit_account.borrower() != borrower_wallet_account.key {
+            throw!(TakerError::BorrowerMismatch)
+        }
+
+        let loan = deposit_account.get_active_state()?;
+
+        if clock.unix_timestamp > loan.expired_at() {
+            throw!(TakerError::LoanLiquidated)
+        }
+
+        if &loan.lender == lender_wallet_account.key {
+            throw!(TakerError::RefinanceToSameLender)
+        }
+
+        // the bid must be the new lender's, otherwise any bid could be paired with the lender's DAI approval
+        let (_, bump) = NFTBid::get_address_with_bump(
+            ctx.program_id,
+            pool.to_account_info().key,
+            &bid_account.target,
+            lender_wallet_account.key,
+        );
+        NFTBid::verify_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            &bid_account.target,
+            lender_wallet_account.key,
+            bump,
+            bid_account.to_account_info().key,
+        )
+        .map_err(|_| TakerError::BidLenderMismatch)?;
+
+        if !bid_account
+            .target
+            .covers(&deposit_account.nft_mint(), &proof)
+        {
+            throw!(TakerError::NFTNotCoveredByBid)
+        }
+
+        if &lender_dai_account.owner != lender_wallet_account.key {
+            throw!(TakerError::LenderAccountMismatch)
+        }
+
+        if &pool_dai_account.owner != pool.to_account_info().key {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        if lender_tai_account.mint != pool.tai_mint
+            || pool_tai_account.mint != pool.tai_mint
+            || lender_dai_account.mint != pool.dai_mint
+            || pool_dai_account.mint != pool.dai_mint
+        {
+            throw!(TakerError::MintMismatch)
+        }
+
+        pool.ensure_fee_vault(pool_fee_vault.to_account_info().key)?;
+
+        let terms = bid_account.terms;
+        if !borrower_wallet_account.is_signer {
+            if !AutoRefinance::is_enabled(
+                ctx.program_id,
+                deposit_account.to_account_info().key,
+                borrower_wallet_account.key,
+                auto_refinance_account,
+            )? {
+                throw!(TakerError::RefinanceNotAllowed)
+            }
+
+            let expired_at = clock
+                .unix_timestamp
+                .checked_add(terms.max_loan_duration)
+                .ok_or(TakerError::MathOverflow)?;
+            if terms.interest_rate >= loan.interest_rate || expired_at < loan.expired_at() {
+                throw!(TakerError::RefinanceTermsNotBetter)
+            }
+        }
+
+        // the borrower now owes the outstanding principal with the interest accrued so far to the new lender
+        let (interest, fee) =
+            loan.calculate_interest_and_fee(clock.unix_timestamp.saturating_sub(loan.started_at))?;
+        let payoff = loan
+            .outstanding_amount()
+            .checked_add(interest)
+            .ok_or(TakerError::MathOverflow)?;
+
+        // decrease the bid qty by 1, the payoff must be within the limits of the bid
+        let offered = bid_account.offered();
+        bid_account.trade(1, payoff, clock.unix_timestamp)?;
+        pool.update_offered(offered, bid_account.offered());
+
+        pool.remove_outstanding(loan.outstanding_amount());
+        pool.add_outstanding(payoff);
+        deposit_account.refinance(
+            *lender_wallet_account.key,
+            payoff,
+            clock.unix_timestamp,
+            &terms,
+            pool,
+        )?;
+
+        // the current lender is paid as if the loan was repayed
+        let lender_income = interest.checked_sub(fee).ok_or(TakerError::MathOverflow)?;
+        RefinancePayoff::credit(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+            &loan.lender,
+            loan.borrowed_amount,
+            loan.total_amount
+                .checked_add(lender_income)
+                .ok_or(TakerError::MathOverflow)?,
+            payer,
+            payoff_account,
+            rent,
+            system_program,
+        )?;
+
+        // transfer DAI to the pool and the fee vault, from the escrow of the bid or the lender's account
+        let lender_funds = if bid_account.escrowed {
+            let (_, bump) = NFTBid::get_escrow_address_with_bump(
+                ctx.program_id,
+                bid_account.to_account_info().key,
+            );
+            NFTBid::verify_escrow_address(
+                ctx.program_id,
+                bid_account.to_account_info().key,
+                bump,
+                bid_escrow_account.key,
+            )?;
+            bid_escrow_account.clone()
+        } else {
+            lender_dai_account.to_account_info()
+        };
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: lender_funds.clone(),
+                    to: pool_fee_vault.to_account_info(),
+                    authority: pool.to_account_info(), // The pool owns the escrow or is the delegate
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            fee,
+        )?;
+        pool.accrue_fee(fee)?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: lender_funds,
+                    to: pool_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            payoff.checked_sub(fee).ok_or(TakerError::MathOverflow)?,
+        )?;
+
+        // transfer TAI to the new lender
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_tai_account.to_account_info(),
+                    to: lender_tai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            payoff,
+        )?;
+
+        // the note of the previous lender now claims its payoff, the new lender gets a note of its own
+        NFTDeposit::issue_loan_note(
+            ctx.program_id,
+            pool,
+            deposit_account.to_account_info().key,
+            lender_wallet_account,
+            payer,
+            loan_note_mint,
+            lender_note_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        emit!(EventRefinanced {
+            borrower: *borrower_wallet_account.key,
+            deposit_id: deposit_account.deposit_id(),
+            previous_lender: loan.lender,
+            lender: *lender_wallet_account.key,
+            amount: payoff,
+            fee,
+            interest_rate: terms.interest_rate,
+            length: terms.max_loan_duration,
+        });
+
+        Ok(())
+    }
+
+    // The lender refinanced out of a loan returns its TAI and the loan note to get the payoff
+    pub fn withdraw_refinance_payoff(ctx: Context<AccountsWithdrawRefinancePayoff>) -> Result<()> {
+        let AccountsWithdrawRefinancePayoff {
+            pool,
+            lender_wallet_account,
+
+            lender_tai_account,
+            pool_tai_account,
+
+            lender_dai_account,
+            pool_dai_account,
+
+            deposit_account,
+            payoff_account,
+
+            loan_note_mint,
+            lender_note_account,
+
+            spl_program,
+        } = ctx.accounts;
+
+        let payoff = RefinancePayoff::load(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.key,
+            payoff_account,
+        )?;
+
+        // the holder of the note of the refinanced lender is paid in place of the lender
+        NFTDeposit::redeem_loan_note(
+            ctx.program_id,
+            deposit_account.key,
+            &payoff.lender,
+            lender_wallet_account,
+            loan_note_mint,
+            lender_note_account,
+            spl_program,
+        )?;
+
+        if &lender_dai_account.owner != lender_wallet_account.key {
+            throw!(TakerError::LenderAccountMismatch)
+        }
+
+        if lender_dai_account.mint != pool.dai_mint
+            || pool_dai_account.mint != pool.dai_mint
+            || pool_tai_account.mint != pool.tai_mint
+        {
+            throw!(TakerError::MintMismatch)
+        }
+
+        if &pool_dai_account.owner != pool.to_account_info().key
+            || &pool_tai_account.owner != pool.to_account_info().key
+        {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        // Transfer the TAI to the pool
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: lender_tai_account.to_account_info(),
+                    to: pool_tai_account.to_account_info(),
+                    authority: lender_wallet_account.to_account_info(),
+                },
+            ),
+            payoff.tai_required_to_unlock,
+        )?;
+
+        // Transfer the DAI to the lender
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            payoff.lender_withdrawable,
+        )?;
+
+        // the rent of the payoff goes to the lender as well
+        anchor_lang::AccountsClose::close(&payoff, lender_wallet_account.clone())?;
+
+        emit!(EventWithDrawLockedAsset {
+            lender: *lender_wallet_account.key,
+            amount: payoff.lender_withdrawable,
+        });
+
+        Ok(())
+    }
+
+    // Puts the NFT of a loan on sale once its grace period is over, anyone can start the auction
+    pub fn start_auction(ctx: Context<AccountsStartAuction>) -> Result<()> {
+        let AccountsStartAuction {
+            pool,
+            deposit_account,
+            clock,
+        } = ctx.accounts;
+
+        pool.ensure_not_paused(NFTPool::PAUSE_AUCTION)?;
+
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        let loan = deposit_account.get_active_state()?;
+        if loan.auction_duration <= 0 {
+            throw!(TakerError::AuctionDisabled)
+        }
+
+        if clock.unix_timestamp <= loan.grace_end() {
+            throw!(TakerError::LoanNotExpired)
+        }
+
+        let auction = deposit_account.start_auction(clock.unix_timestamp)?;
+        pool.remove_outstanding(loan.outstanding_amount());
+
+        emit!(EventAuctionStarted {
+            deposit_id: deposit_account.deposit_id,
+            start_price: auction.start_price,
+            end_price: auction.claim,
+            ended_at: auction.ended_at,
+        });
+
+        Ok(())
+    }
+
+    // Buys the NFT on sale at the current price. The price covers the claim of the lender,
+    // who withdraws it with `withdraw_locked_asset`, and the surplus goes to the borrower
+    pub fn buy_collateral(ctx: Context<AccountsBuyCollateral>, max_price: u64) -> Result<()> {
+        let AccountsBuyCollateral {
+            pool,
+            buyer_wallet_account,
+
+            pool_fee_vault,
+            buyer_dai_account,
+            borrower_dai_account,
+            pool_dai_account,
+
+            nft_mint,
+            buyer_nft_account,
+            pool_nft_account,
+
+            deposit_account,
+
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        pool.ensure_not_paused(NFTPool::PAUSE_AUCTION)?;
+
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        let auction = deposit_account.get_auction_state()?;
+
+        if clock.unix_timestamp > auction.ended_at {
+            throw!(TakerError::AuctionNotRunning)
+        }
+
+        let price = auction.price_at(clock.unix_timestamp);
+        if price > max_price {
+            throw!(TakerError::AuctionPriceTooHigh)
+        }
+
+        pool.ensure_fee_vault(pool_fee_vault.to_account_info().key)?;
+
+        if borrower_dai_account.owner != deposit_account.borrower() {
+            throw!(TakerError::BorrowerMismatch)
+        }
+
+        if &pool_dai_account.owner != pool.to_account_info().key {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        if pool_dai_account.mint != pool.dai_mint || borrower_dai_account.mint != pool.dai_mint {
+            throw!(TakerError::MintMismatch)
+        }
+
+        if &deposit_account.nft_mint() != nft_mint.to_account_info().key {
+            throw!(TakerError::DepositMintMismatch)
+        }
+
+        // transfer fee to the fee vault
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: buyer_dai_account.to_account_info(),
+                    to: pool_fee_vault.to_account_info(),
+                    authority: buyer_wallet_account.to_account_info(),
+                },
+            ),
+            auction.fee,
+        )?;
+        pool.accrue_fee(auction.fee)?;
+
+        // the rest of the claim waits in the pool for the lender
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: buyer_dai_account.to_account_info(),
+                    to: pool_dai_account.to_account_info(),
+                    authority: buyer_wallet_account.to_account_info(),
+                },
+            ),
+            auction
+                .claim
+                .checked_sub(auction.fee)
+                .ok_or(TakerError::MathOverflow)?,
+        )?;
+
+        let surplus = price
+            .checked_sub(auction.claim)
+            .ok_or(TakerError::MathOverflow)?;
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: buyer_dai_account.to_account_info(),
+                    to: borrower_dai_account.to_account_info(),
+                    authority: buyer_wallet_account.to_account_info(),
+                },
+            ),
+            surplus,
+        )?;
+
+        // allocate the NFT ATA for the buyer if not allocate
+        NFTPool::ensure_user_token_account(
+            buyer_wallet_account,
+            nft_mint,
+            buyer_nft_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        // Transfer the NFT to the buyer
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_nft_account.to_account_info(),
+                    to: buyer_nft_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        deposit_account.sell()?;
+
+        emit!(EventCollateralSold {
+            deposit_id: deposit_account.deposit_id,
+            buyer: *buyer_wallet_account.key,
+            price,
+            fee: auction.fee,
+            surplus,
+        });
+
+        Ok(())
+    }
+
+    // `close` also deallocates the deposit, refunding its rent to the borrower
+    pub fn liquidate(ctx: Context<AccountsLiquidate>, close: bool) -> Result<()> {
+        let AccountsLiquidate {
+            pool,
+            lender_wallet_account,
+            borrower_wallet_account,
+
+            pool_fee_vault,
+            pool_dai_account,
+            lender_dai_account,
+
+            nft_mint,
+            pool_nft_account,
+            lender_nft_account,
+
+            lender_tai_account,
+            pool_tai_account,
+
+            deposit_account,
+
+            loan_note_mint,
+            lender_note_account,
+
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+            clock,
+        } = ctx.accounts;
+
+        pool.ensure_not_paused(NFTPool::PAUSE_LIQUIDATE)?;
+
+        // the deposit must belong to this pool, whose NFT and DAI are paid out
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        if &pool_dai_account.owner != pool.to_account_info().key
+            || &pool_tai_account.owner != pool.to_account_info().key
+            || &pool_nft_account.owner != pool.to_account_info().key
+        {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        if pool_dai_account.mint != pool.dai_mint
+            || lender_dai_account.mint != pool.dai_mint
+            || pool_tai_account.mint != pool.tai_mint
+        {
+            throw!(TakerError::MintMismatch)
+        }
+
+        if pool_nft_account.mint != deposit_account.nft_mint() {
+            throw!(TakerError::DepositMintMismatch)
+        }
+
+        // the lender takes the NFT once the grace period is over, or once the auction ended unsold
+        let (lender, tai_required_to_unlock, pool_holdings, fee) = match deposit_account.state() {
+            DepositState::LoanActive(loan) => {
+                if clock.unix_timestamp <= loan.grace_end() {
+                    throw!(TakerError::LoanNotExpired)
+                }
+
+                if loan.auction_duration > 0 {
+                    throw!(TakerError::AuctionRequired)
+                }
+
+                let (_, fee) = loan.claim()?;
+                pool.remove_outstanding(loan.outstanding_amount());
+                (loan.lender, loan.borrowed_amount, loan.pool_holdings(), fee)
+            }
+            DepositState::LoanAuctioned(auction) => {
+                if clock.unix_timestamp <= auction.ended_at {
+                    throw!(TakerError::AuctionNotFinished)
+                }
+                (
+                    auction.lender,
+                    auction.tai_required_to_unlock,
+                    auction.pool_holdings,
+                    auction.fee,
+                )
+            }
+            _ => throw!(TakerError::LoanNotActive),
+        };
+        // the NFT covers the rest of the claim, the fee is taken out of what the pool holds for the lender
+        let fee = fee.min(pool_holdings);
+
+        // only the holder of the loan note can seize the collateral
+        NFTDeposit::redeem_loan_note(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            &lender,
+            lender_wallet_account,
+            loan_note_mint,
+            lender_note_account,
+            spl_program,
+        )?;
+
+        if &lender_dai_account.owner != lender_wallet_account.key {
+            throw!(TakerError::LenderAccountMismatch)
+        }
+
+        if &deposit_account.nft_mint() != nft_mint.to_account_info().key {
+            throw!(TakerError::DepositMintMismatch)
+        }
+
+        // Transfer the corresponding TAI to the pool
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: lender_tai_account.to_account_info(),
+                    to: pool_tai_account.to_account_info(),
+                    authority: lender_wallet_account.to_account_info(),
+                },
+            ),
+            tai_required_to_unlock,
+        )?;
+
+        // charge service fee over the loan duration left since the interest was last settled
+        pool.ensure_fee_vault(pool_fee_vault.to_account_info().key)?;
+
+        // transfer fee to the fee vault
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_dai_account.to_account_info(),
+                    to: pool_fee_vault.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            fee,
+        )?;
+        pool.accrue_fee(fee)?;
+
+        let withdrawable = pool_holdings
+            .checked_sub(fee)
+            .ok_or(TakerError::MathOverflow)?;
+
+        // Transfer the remaining DAI to the lender
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            withdrawable,
+        )?;
+
+        // allocate the NFT ATA for the lender if not allocate
+        NFTPool::ensure_user_token_account(
+            lender_wallet_account,
+            nft_mint,
+            lender_nft_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        // Transfer the NFT to the lender
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_nft_account.to_account_info(),
+                    to: lender_nft_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+
+        // set corresponding records
+        deposit_account.liquidate()?;
+
+        emit!(EventLiquidated {
+            lender: *lender_wallet_account.key,
+            loan_id: deposit_account.deposit_id,
+            withdrawable,
+        });
+
+        if close {
+            NFTDeposit::close(deposit_account, borrower_wallet_account)?;
+
+            emit!(EventDepositClosed {
+                deposit_id: deposit_account.deposit_id,
+                borrower: *borrower_wallet_account.key,
+            });
+        }
+
+        Ok(())
+    }
+
+    // `close` also deallocates the deposit, refunding its rent to the borrower
+    pub fn withdraw_locked_asset(
+        ctx: Context<AccountsWithdrawLockedAsset>,
+        close: bool,
+    ) -> Result<()> {
+        let AccountsWithdrawLockedAsset {
+            pool,
+            lender_wallet_account,
+            borrower_wallet_account,
+
+            lender_tai_account,
+            pool_tai_account,
+
+            lender_dai_account,
+            pool_dai_account,
+
+            deposit_account,
+
+            loan_note_mint,
+            lender_note_account,
+
+            spl_program,
+        } = ctx.accounts;
+
+        pool.ensure_not_paused(NFTPool::PAUSE_WITHDRAW_LOCKED_ASSET)?;
+
+        let repay = deposit_account.get_repayed_state()?;
+
+        // the deposit must belong to this pool, whose DAI is paid out
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        // the holder of the loan note is paid in place of the lender
+        NFTDeposit::redeem_loan_note(
+            ctx.program_id,
+            deposit_account.to_account_info().key,
+            &repay.lender,
+            lender_wallet_account,
+            loan_note_mint,
+            lender_note_account,
+            spl_program,
+        )?;
+
+        if &lender_dai_account.owner != lender_wallet_account.key {
+            throw!(TakerError::LenderAccountMismatch)
+        }
+
+        if lender_dai_account.mint != pool.dai_mint
+            || pool_dai_account.mint != pool.dai_mint
+            || pool_tai_account.mint != pool.tai_mint
+        {
+            throw!(TakerError::MintMismatch)
+        }
+
+        if &pool_dai_account.owner != pool.to_account_info().key
+            || &pool_tai_account.owner != pool.to_account_info().key
+        {
+            throw!(TakerError::PoolAccountMismatch)
+        }
+
+        // Transfer the TAI to the pool
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: lender_tai_account.to_account_info(),
+                    to: pool_tai_account.to_account_info(),
+                    authority: lender_wallet_account.to_account_info(),
+                },
+            ),
+            repay.tai_required_to_unlock,
+        )?;
+
+        // Transfer the DAI to the lender
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: pool_dai_account.to_account_info(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            repay.lender_withdrawable,
+        )?;
+
+        deposit_account.clear()?;
+
+        emit!(EventWithDrawLockedAsset {
+            lender: *lender_wallet_account.key,
+            amount: repay.lender_withdrawable,
+        });
+
+        if close {
+            NFTDeposit::close(deposit_account, borrower_wallet_account)?;
+
+            emit!(EventDepositClosed {
+                deposit_id: deposit_account.deposit_id,
+                borrower: *borrower_wallet_account.key,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Deallocates a deposit that is withdrawn, liquidated or cleared and refunds its rent to the borrower.
+    // Anyone can close a finished deposit since the lamports can only go back to the borrower.
+    pub fn close_deposit(ctx: Context<AccountsCloseDeposit>) -> Result<()> {
+        let AccountsCloseDeposit {
+            pool,
+            borrower_wallet_account,
+            deposit_account,
+        } = ctx.accounts;
+
+        pool.ensure_not_paused(NFTPool::PAUSE_CLOSE_DEPOSIT)?;
+
+        deposit_account.verify_own_address(
+            ctx.program_id,
+            pool.to_account_info().key,
+            deposit_account.to_account_info().key,
+        )?;
+
+        NFTDeposit::close(deposit_account, borrower_wallet_account)?;
+
+        emit!(EventDepositClosed {
+            deposit_id: deposit_account.deposit_id,
+            borrower: *borrower_wallet_account.key,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AccountsInitialize<'info> {
+    #[account(signer)]
+    pub pool_owner: AccountInfo<'info>, // also the funder, holds every role of the pool at first
+    #[account(mut)]
+    pub pool: AccountInfo<'info>, // We cannot use  ProgramAccount<'info, TakerContract> here because it is not allocated yet
+
+    pub tkr_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_tkr_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub tai_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_tai_account: AccountInfo<'info>, // this is not allocated yet
+
+    pub dai_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub pool_dai_account: AccountInfo<'info>, // this is not allocated yet
+    #[account(mut)]
+    pub pool_fee_vault: AccountInfo<'info>, // this is not allocated yet
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+#[derive(Accounts)]
+pub struct AccountsChangeLoanSetting<'info> {
+    #[account(signer)]
+    pub settings_admin: AccountInfo<'info>,
+    #[account(mut, has_one = settings_admin)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsProposeSettingsChange<'info> {
+    #[account(signer, mut)]
+    pub settings_admin: AccountInfo<'info>, // also the funder of the queued change
+    #[account(has_one = settings_admin)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub settings_change: AccountInfo<'info>, // this is not allocated yet
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsProposeCapsChange<'info> {
+    #[account(signer, mut)]
+    pub owner: AccountInfo<'info>, // also the funder of the queued change
+    #[account(has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub settings_change: AccountInfo<'info>, // this is not allocated yet
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCancelSettingsChange<'info> {
+    #[account(signer)]
+    pub authority: AccountInfo<'info>, // the owner or the settings admin
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub settings_change: AccountInfo<'info>,
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>, // gets the rent back
+}
+
+#[derive(Accounts)]
+pub struct AccountsExecuteSettingsChange<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub settings_change: AccountInfo<'info>,
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>, // gets the rent back
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsPoolOwner<'info> {
+    #[account(signer)]
+    pub owner: AccountInfo<'info>,
+    #[account(mut, has_one = owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCollectFees<'info> {
+    #[account(signer)]
+    pub fee_recipient: AccountInfo<'info>,
+    #[account(mut, has_one = fee_recipient)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub pool_fee_vault: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub fee_recipient_dai_account: CpiAccount<'info, TokenAccount>, // any DAI account of the fee recipient's choice
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsSetPause<'info> {
+    #[account(signer)]
+    pub authority: AccountInfo<'info>, // the owner or the pauser
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsAcceptOwner<'info> {
+    #[account(signer)]
+    pub pending_owner: AccountInfo<'info>,
+    #[account(mut, has_one = pending_owner)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+}
 
 #[derive(Accounts)]
-pub struct AccountsWithdrawNFT<'info> {
+pub struct AccountsDepositNFT<'info> {
     pub pool: ProgramAccount<'info, NFTPool>,
     #[account(signer)]
     pub borrower_wallet_account: AccountInfo<'info>,
 
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub tkr_mint: CpiAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_nft_account: AccountInfo<'info>, // potentially this is not allocated yet
+
+    #[account(mut)]
+    pub borrower_tkr_account: AccountInfo<'info>, // potentially this is not allocated yet
+    #[account(mut)]
+    pub pool_tkr_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTDeposit>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+
+    pub rent: Sysvar<'info, Rent>,
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsWithdrawNFT<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub borrower_wallet_account: AccountInfo<'info>,
+
     pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
     pub pool_nft_account: CpiAccount<'info, TokenAccount>,
@@ -856,104 +3071,400 @@ pub struct AccountsWithdrawNFT<'info> {
 
 #[derive(Accounts)]
 pub struct AccountsPlaceBid<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
-    #[account(signer)]
+    #[account(signer, mut)]
+    pub lender_wallet_account: AccountInfo<'info>, // also funds the bid and its escrow
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    pub dai_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bid_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTBid>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+    #[account(mut)]
+    pub bid_escrow_account: AccountInfo<'info>, // only allocated for escrowed bids
+
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCancelBid<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub lender_wallet_account: AccountInfo<'info>, // receives the rent of the escrow
+
+    pub nft_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bid_account: ProgramAccount<'info, NFTBid>,
+    #[account(mut)]
+    pub bid_escrow_account: AccountInfo<'info>,
+
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsPlaceCollectionBid<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub lender_wallet_account: AccountInfo<'info>, // also funds the bid and its escrow
+
+    pub dai_mint: CpiAccount<'info, Mint>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bid_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTBid>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
+    #[account(mut)]
+    pub bid_escrow_account: AccountInfo<'info>, // only allocated for escrowed bids
+
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCancelCollectionBid<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub lender_wallet_account: AccountInfo<'info>, // receives the rent of the escrow
+
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bid_account: ProgramAccount<'info, NFTBid>,
+    #[account(mut)]
+    pub bid_escrow_account: AccountInfo<'info>,
+
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsBorrow<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub borrower_wallet_account: AccountInfo<'info>, // funds the loan note
     pub lender_wallet_account: AccountInfo<'info>,
 
     pub nft_mint: CpiAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub lender_tai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_tai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    #[account(mut)]
+    pub bid_account: ProgramAccount<'info, NFTBid>,
+    #[account(mut)]
+    pub bid_escrow_account: AccountInfo<'info>, // only read for escrowed bids
+
+    #[account(mut)]
+    pub loan_note_mint: AccountInfo<'info>, // Possibly not allocated
+    #[account(mut)]
+    pub lender_note_account: AccountInfo<'info>, // Possibly not allocated
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsPlaceListing<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub borrower_wallet_account: AccountInfo<'info>, // also funds the listing
+
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    #[account(mut)]
+    pub listing_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTListing>, however, we've not allocated the space for it yet.
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsCancelListing<'info> {
+    #[account(signer, mut)]
+    pub borrower_wallet_account: AccountInfo<'info>, // receives the rent of the listing
+    #[account(mut)]
+    pub listing_account: ProgramAccount<'info, NFTListing>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsFundListing<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub lender_wallet_account: AccountInfo<'info>, // funds the loan note
+    #[account(mut)]
+    pub borrower_wallet_account: AccountInfo<'info>, // receives the rent of the listing
+
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub lender_tai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_tai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    #[account(mut)]
+    pub listing_account: ProgramAccount<'info, NFTListing>,
+
+    #[account(mut)]
+    pub loan_note_mint: AccountInfo<'info>, // Possibly not allocated
+    #[account(mut)]
+    pub lender_note_account: AccountInfo<'info>, // Possibly not allocated
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsSetAutoRefinance<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub borrower_wallet_account: AccountInfo<'info>, // funds the opt-in and gets its rent back
+
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    #[account(mut)]
+    pub auto_refinance_account: AccountInfo<'info>, // only allocated while the borrower opts in
+
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsRefinance<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub payer: AccountInfo<'info>, // funds the payoff of the current lender and the note of the new one
+    pub borrower_wallet_account: AccountInfo<'info>, // signs unless the borrower opted in
+    pub lender_wallet_account: AccountInfo<'info>,   // the new lender
+
+    #[account(mut)]
+    pub pool_fee_vault: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub pool_tai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub lender_tai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    #[account(mut)]
+    pub bid_account: ProgramAccount<'info, NFTBid>,
+    #[account(mut)]
+    pub bid_escrow_account: AccountInfo<'info>, // only read for escrowed bids
+    pub auto_refinance_account: AccountInfo<'info>, // only read when the borrower does not sign
+    #[account(mut)]
+    pub payoff_account: AccountInfo<'info>, // RefinancePayoff of the current lender
+
+    #[account(mut)]
+    pub loan_note_mint: AccountInfo<'info>, // of the new lender, possibly not allocated
+    #[account(mut)]
+    pub lender_note_account: AccountInfo<'info>, // Possibly not allocated
+
+    pub ata_program: AccountInfo<'info>,
+    pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsWithdrawRefinancePayoff<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub lender_wallet_account: AccountInfo<'info>, // holds the loan note, receives the rent of the payoff
+
+    #[account(mut)]
+    pub lender_tai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_tai_account: CpiAccount<'info, TokenAccount>,
+
     #[account(mut)]
     pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+
+    pub deposit_account: AccountInfo<'info>, // only its address, the deposit may be closed already
+    #[account(mut)]
+    pub payoff_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub loan_note_mint: AccountInfo<'info>, // of the refinanced lender
+    #[account(mut)]
+    pub lender_note_account: AccountInfo<'info>,
+
+    pub spl_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AccountsRepay<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+
+    #[account(signer)]
+    pub borrower_wallet_account: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub pool_fee_vault: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_nft_account: CpiAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
 
     #[account(mut)]
-    pub bid_account: AccountInfo<'info>, // Essentially this is ProgramAccount<NFTBid>, however, we've not allocated the space for it yet. We cannot use ProgramAccount here.
-
     pub spl_program: AccountInfo<'info>,
-    pub system_program: AccountInfo<'info>,
-    pub rent: Sysvar<'info, Rent>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsCancelBid<'info> {
+pub struct AccountsRepayPartial<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+
     #[account(signer)]
-    pub lender_wallet_account: AccountInfo<'info>,
+    pub borrower_wallet_account: AccountInfo<'info>,
 
-    pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
-    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub pool_fee_vault: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
 
     #[account(mut)]
-    pub bid_account: ProgramAccount<'info, NFTBid>,
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
 
     pub spl_program: AccountInfo<'info>,
+    pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsBorrow<'info> {
+pub struct AccountsExtendLoan<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
     #[account(signer)]
     pub borrower_wallet_account: AccountInfo<'info>,
-    pub lender_wallet_account: AccountInfo<'info>,
-
-    pub nft_mint: CpiAccount<'info, Mint>,
+    pub lender_wallet_account: AccountInfo<'info>, // signs unless the bid allows the extension
 
     #[account(mut)]
-    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
+    pub pool_fee_vault: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
     pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
     pub lender_dai_account: CpiAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub lender_tai_account: CpiAccount<'info, TokenAccount>,
-    #[account(mut)]
-    pub pool_tai_account: CpiAccount<'info, TokenAccount>,
-
+    pub bid_account: AccountInfo<'info>, // only read when the lender does not sign
     #[account(mut)]
     pub deposit_account: ProgramAccount<'info, NFTDeposit>,
-    #[account(mut)]
-    pub bid_account: ProgramAccount<'info, NFTBid>,
+
+    pub loan_note_mint: AccountInfo<'info>,
+    pub lender_note_account: AccountInfo<'info>, // the loan note, held by `lender_wallet_account`
 
     pub spl_program: AccountInfo<'info>,
     pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
-pub struct AccountsRepay<'info> {
+pub struct AccountsStartAuction<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+    pub clock: Sysvar<'info, Clock>,
+}
 
-    #[account(signer)]
-    pub borrower_wallet_account: AccountInfo<'info>,
+#[derive(Accounts)]
+pub struct AccountsBuyCollateral<'info> {
+    #[account(mut)]
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(signer, mut)]
+    pub buyer_wallet_account: AccountInfo<'info>, // also funds the NFT account of the buyer
 
     #[account(mut)]
-    pub pool_owner_dai_account: CpiAccount<'info, TokenAccount>, // for collecting fees
+    pub pool_fee_vault: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub borrower_dai_account: CpiAccount<'info, TokenAccount>,
+    pub buyer_dai_account: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub lender_dai_account: CpiAccount<'info, TokenAccount>,
+    pub borrower_dai_account: CpiAccount<'info, TokenAccount>, // receives the surplus
+    #[account(mut)]
+    pub pool_dai_account: CpiAccount<'info, TokenAccount>,
 
+    pub nft_mint: CpiAccount<'info, Mint>,
     #[account(mut)]
-    pub borrower_nft_account: CpiAccount<'info, TokenAccount>,
+    pub buyer_nft_account: AccountInfo<'info>, // this might not be allocated yet
     #[account(mut)]
     pub pool_nft_account: CpiAccount<'info, TokenAccount>,
 
     #[account(mut)]
     pub deposit_account: ProgramAccount<'info, NFTDeposit>,
 
-    #[account(mut)]
+    pub ata_program: AccountInfo<'info>,
     pub spl_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
     pub clock: Sysvar<'info, Clock>,
 }
 
 #[derive(Accounts)]
 pub struct AccountsLiquidate<'info> {
+    #[account(mut)]
     pub pool: ProgramAccount<'info, NFTPool>,
     #[account(signer)]
-    pub lender_wallet_account: AccountInfo<'info>,
+    pub lender_wallet_account: AccountInfo<'info>, // holds the loan note
+    #[account(mut)]
+    pub borrower_wallet_account: AccountInfo<'info>, // receives the rent of the deposit when closing
 
     #[account(mut)]
     pub pool_dai_account: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub pool_owner_dai_account: CpiAccount<'info, TokenAccount>,
+    pub pool_fee_vault: CpiAccount<'info, TokenAccount>,
     #[account(mut)]
     pub lender_dai_account: CpiAccount<'info, TokenAccount>,
 
@@ -971,6 +3482,11 @@ pub struct AccountsLiquidate<'info> {
     #[account(mut)]
     pub deposit_account: ProgramAccount<'info, NFTDeposit>,
 
+    #[account(mut)]
+    pub loan_note_mint: AccountInfo<'info>, // not allocated for the loans started before the notes
+    #[account(mut)]
+    pub lender_note_account: AccountInfo<'info>,
+
     pub ata_program: AccountInfo<'info>,
     pub spl_program: AccountInfo<'info>,
     pub system_program: AccountInfo<'info>,
@@ -983,7 +3499,9 @@ pub struct AccountsWithdrawLockedAsset<'info> {
     pub pool: ProgramAccount<'info, NFTPool>,
 
     #[account(signer)]
-    pub lender_wallet_account: AccountInfo<'info>,
+    pub lender_wallet_account: AccountInfo<'info>, // holds the loan note
+    #[account(mut)]
+    pub borrower_wallet_account: AccountInfo<'info>, // receives the rent of the deposit when closing
 
     #[account(mut)]
     pub lender_tai_account: CpiAccount<'info, TokenAccount>,
@@ -998,10 +3516,26 @@ pub struct AccountsWithdrawLockedAsset<'info> {
     #[account(mut)]
     pub deposit_account: ProgramAccount<'info, NFTDeposit>,
 
+    #[account(mut)]
+    pub loan_note_mint: AccountInfo<'info>, // not allocated for the loans started before the notes
+    #[account(mut)]
+    pub lender_note_account: AccountInfo<'info>,
+
     pub spl_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct AccountsCloseDeposit<'info> {
+    pub pool: ProgramAccount<'info, NFTPool>,
+    #[account(mut)]
+    pub borrower_wallet_account: AccountInfo<'info>, // receives the rent of the deposit
+
+    #[account(mut)]
+    pub deposit_account: ProgramAccount<'info, NFTDeposit>,
+}
+
 #[error]
+#[derive(FromPrimitive, PartialEq)]
 pub enum TakerError {
     #[msg("Not Authorized")]
     NotAuhorized = 0,
@@ -1064,18 +3598,195 @@ pub enum TakerError {
 
     #[msg("Loan has not been repayed")]
     LoanNotRepayed,
-}
 
-impl TakerError {
-    pub fn from_code(c: u32) -> TakerError {
-        unsafe { std::mem::transmute(c - 100) }
-    }
+    #[msg("NFT is not covered by the bid")]
+    NFTNotCoveredByBid,
+
+    #[msg("The bid does not belong to the lender")]
+    BidLenderMismatch,
+
+    #[msg("The DAI account does not belong to the lender")]
+    LenderAccountMismatch,
+
+    #[msg("The deposit is not for the NFT")]
+    DepositMintMismatch,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+
+    #[msg("Token mint does not match the pool")]
+    MintMismatch,
+
+    #[msg("The mint is not an NFT")]
+    NotAnNFT,
+
+    #[msg("The borrowed amount is larger than the loan amount")]
+    BorrowedAmountExceedTotal,
+
+    #[msg("The token account does not belong to the pool")]
+    PoolAccountMismatch,
+
+    #[msg("The deposit is still in use")]
+    DepositNotClosable,
+
+    #[msg("The wallet is not the borrower of the deposit")]
+    BorrowerMismatch,
+
+    #[msg("Invalid loan terms")]
+    InvalidLoanTerms,
+
+    #[msg("The extension must be positive")]
+    InvalidExtension,
+
+    #[msg("The lender did not allow this extension")]
+    ExtensionNotAllowed,
+
+    #[msg("A partial repayment must be positive and below the outstanding principal")]
+    InvalidRepayAmount,
+
+    #[msg("Invalid liquidation settings")]
+    InvalidLiquidationSettings,
+
+    #[msg("The NFT of the loan has to be auctioned first")]
+    AuctionRequired,
+
+    #[msg("The pool does not auction liquidated NFTs")]
+    AuctionDisabled,
+
+    #[msg("The NFT of the loan is not on sale")]
+    AuctionNotRunning,
+
+    #[msg("The auction is not finished yet")]
+    AuctionNotFinished,
+
+    #[msg("The auction price is above the maximum price of the buyer")]
+    AuctionPriceTooHigh,
+
+    #[msg("The kink of the interest model must be within (0, 10000) bp")]
+    InvalidInterestModel,
+
+    #[msg("The instruction is paused")]
+    ProtocolPaused,
+
+    #[msg("Unknown pause flags")]
+    InvalidPauseFlags,
+
+    #[msg("The mortgage rate must be within (0, 10000] bp")]
+    InvalidMortgageRate,
+
+    #[msg("The service fee rate cannot be above 10000 bp")]
+    InvalidServiceFeeRate,
+
+    #[msg("The loan duration must be positive")]
+    InvalidLoanDuration,
+
+    #[msg("The setting is above the cap set by the pool owner")]
+    SettingAboveCap,
+
+    #[msg("The settings delay cannot be negative")]
+    InvalidSettingsDelay,
+
+    #[msg("The delay of the pending loan settings is not over")]
+    SettingsTimelocked,
+
+    #[msg("The pool has a settings delay, propose the change instead")]
+    SettingsChangeMustBeQueued,
+
+    #[msg("A settings change is already queued for the pool")]
+    SettingsChangeAlreadyQueued,
+
+    #[msg("Settings change address not correct")]
+    SettingsChangeAddressNotCorrect,
+
+    #[msg("Proposer does not match the settings change")]
+    ProposerMismatch,
+
+    #[msg("Fee vault address not correct")]
+    FeeVaultAddressNotCorrect,
+
+    #[msg("Bid escrow address not correct")]
+    BidEscrowAddressNotCorrect,
+
+    #[msg("The minimum amount of the bid cannot be above its price")]
+    InvalidBidLimits,
+
+    #[msg("The bid has expired")]
+    BidExpired,
+
+    #[msg("The amount is below the minimum amount of the bid")]
+    BorrowBelowBidMinimum,
+
+    #[msg("The interest rate is above the maximum of the listing")]
+    ListingRateTooHigh,
+
+    #[msg("The borrower must sign or opt in to automatic refinancing")]
+    RefinanceNotAllowed,
+
+    #[msg("Automatic refinancing needs a lower rate without shortening the loan")]
+    RefinanceTermsNotBetter,
+
+    #[msg("The lender already holds the loan")]
+    RefinanceToSameLender,
+
+    #[msg("Auto refinance address not correct")]
+    AutoRefinanceAddressNotCorrect,
+
+    #[msg("Refinance payoff address not correct")]
+    RefinancePayoffAddressNotCorrect,
+
+    #[msg("Loan note address not correct")]
+    LoanNoteAddressNotCorrect,
+
+    #[msg("The lender still holds a loan note on this deposit")]
+    LoanNoteOutstanding,
+
+    #[msg("The signer does not hold the loan note")]
+    NotLoanNoteHolder,
+
+    #[msg("No settings change is queued for the pool")]
+    NoSettingsChangeQueued,
 }
 
 #[event]
 #[derive(Debug)]
 pub struct EventInitialized {
     account: Pubkey,
+    pool_id: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventPauseChanged {
+    paused: u32,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventOwnerProposed {
+    pending_owner: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventOwnerChanged {
+    previous_owner: Pubkey,
+    owner: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventRolesChanged {
+    fee_recipient: Pubkey,
+    settings_admin: Pubkey,
+    pauser: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventFeesCollected {
+    fee_recipient: Pubkey,
+    amount: u64,
+    fees_collected: u64,
 }
 
 #[event]
@@ -1086,6 +3797,39 @@ pub struct EventLoanSettingChanged {
     service_fee_rate: u64,
     max_loan_duration: i64,
     mortgage_rate: u64,
+    grace_period: i64,
+    grace_penalty_rate: u64,
+    auction_duration: i64,
+    auction_start_rate: u64,
+    interest_model: InterestCurve,
+    effective_at: i64, // later than now when the pool has a settings delay
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventLoanSettingApplied {
+    effective_at: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventSettingsChangeCancelled {
+    effective_at: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventSettingsCapsChanged {
+    settings_caps: SettingsCaps,
+    settings_delay: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventSettingsCapsProposed {
+    settings_caps: SettingsCaps,
+    settings_delay: i64,
+    effective_at: i64,
 }
 
 #[event]
@@ -1109,6 +3853,10 @@ pub struct EventNFTBidPlaced {
     from: Pubkey,
     price: u64,
     qty: u64,
+    terms: LoanTerms,
+    escrow: bool,
+    expires_at: i64,
+    min_amount: u64,
 }
 
 #[event]
@@ -1120,6 +3868,28 @@ pub struct EventNFTBidCancelled {
     qty: u64,
 }
 
+#[event]
+#[derive(Debug)]
+pub struct EventCollectionBidPlaced {
+    allowlist_root: [u8; 32],
+    from: Pubkey,
+    price: u64,
+    qty: u64,
+    terms: LoanTerms,
+    escrow: bool,
+    expires_at: i64,
+    min_amount: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventCollectionBidCancelled {
+    allowlist_root: [u8; 32],
+    from: Pubkey,
+    price: u64,
+    qty: u64,
+}
+
 #[event]
 #[derive(Debug)]
 pub struct EventBorrowed {
@@ -1129,6 +3899,54 @@ pub struct EventBorrowed {
     length: i64,
 }
 
+#[event]
+#[derive(Debug)]
+pub struct EventAutoRefinanceChanged {
+    deposit_id: Pubkey,
+    enabled: bool,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventRefinanced {
+    borrower: Pubkey,
+    deposit_id: Pubkey,
+    previous_lender: Pubkey,
+    lender: Pubkey,
+    amount: u64, // principal of the new loan, the payoff of the previous lender
+    fee: u64,
+    interest_rate: u64,
+    length: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventListingPlaced {
+    deposit_id: Pubkey,
+    borrower: Pubkey,
+    amount: u64,
+    max_interest_rate: u64,
+    loan_duration: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventListingCancelled {
+    deposit: Pubkey,
+    borrower: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventListingFunded {
+    deposit_id: Pubkey,
+    borrower: Pubkey,
+    lender: Pubkey,
+    amount: u64,
+    interest_rate: u64,
+    length: i64,
+}
+
 #[event]
 #[derive(Debug)]
 pub struct EventRepayed {
@@ -1137,6 +3955,41 @@ pub struct EventRepayed {
     amount: u64,
     fee: u64,
     lender_income: u64,
+    penalty: u64, // part of the lender income, charged when repaying during the grace period
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventAuctionStarted {
+    deposit_id: Pubkey,
+    start_price: u64,
+    end_price: u64,
+    ended_at: i64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventCollateralSold {
+    deposit_id: Pubkey,
+    buyer: Pubkey,
+    price: u64,
+    fee: u64,
+    surplus: u64, // paid back to the borrower
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventLiquidationSettingChanged {
+    grace_period: i64,
+    grace_penalty_rate: u64,
+    auction_duration: i64,
+    auction_start_rate: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventInterestModelChanged {
+    interest_model: InterestCurve,
 }
 
 #[event]
@@ -1153,3 +4006,32 @@ pub struct EventWithDrawLockedAsset {
     lender: Pubkey,
     amount: u64,
 }
+
+#[event]
+#[derive(Debug)]
+pub struct EventDepositClosed {
+    deposit_id: Pubkey,
+    borrower: Pubkey,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventPartiallyRepayed {
+    borrower: Pubkey,
+    lender: Pubkey,
+    amount: u64,
+    fee: u64,
+    lender_income: u64,
+    outstanding: u64,
+}
+
+#[event]
+#[derive(Debug)]
+pub struct EventLoanExtended {
+    borrower: Pubkey,
+    lender: Pubkey,
+    deposit_id: Pubkey,
+    fee: u64,
+    lender_income: u64,
+    expired_at: i64,
+}
@@ -1,38 +1,114 @@
 use anchor_lang::prelude::Pubkey;
 
-use crate::{utils, DerivedAccountIdentifier, NFTBid, TakerError};
+use crate::interest::SECONDS_PER_DAY;
+use crate::{allowlist, utils, DerivedAccountIdentifier, NFTBid, NFTPool, TakerError};
 use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
+use borsh::{BorshDeserialize, BorshSerialize};
 use fehler::{throw, throws};
+use solana_program::clock::UnixTimestamp;
 
 impl DerivedAccountIdentifier for NFTBid {
     const SEED: &'static [u8] = b"TakerNFTBid";
 }
 
+// The NFTs a bid lends against
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BidTarget {
+    Mint(Pubkey),         // a single NFT
+    Collection([u8; 32]), // any NFT of the collection, identified by the allowlist root
+}
+
+// The terms a lender offers for the loans taken against a bid
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoanTerms {
+    pub interest_rate: u64,     // in bp, one ten thousandth, per day
+    pub max_loan_duration: i64, // loan duration before liquidation, secs
+    pub mortgage_rate: u64,     // in bp, share of the bid price lent to the borrower
+    pub max_extension: i64,     // secs a borrower can extend a loan by without the lender signing
+}
+
+impl LoanTerms {
+    // in bp, the interest rate over the longest loan. Keeps the growth of a compounding loan below
+    // e^10, about 22000x, so that its interest can always be computed to repay or liquidate it
+    pub const MAX_LOAN_RATE: u64 = 100_000;
+
+    #[throws(TakerError)]
+    pub fn validate(&self) {
+        if self.max_loan_duration <= 0
+            || self.mortgage_rate == 0
+            || self.mortgage_rate > 10000
+            || self.max_extension < 0
+        {
+            throw!(TakerError::InvalidLoanTerms)
+        }
+
+        let loan_rate =
+            self.interest_rate as u128 * self.max_loan_duration as u128 / SECONDS_PER_DAY as u128;
+        if loan_rate > Self::MAX_LOAN_RATE as u128 {
+            throw!(TakerError::InvalidLoanTerms)
+        }
+    }
+}
+
+impl BidTarget {
+    const COLLECTION_SEED: &'static [u8] = b"TakerNFTCollectionBid";
+
+    // Collection bids live in their own address space so that they never collide with mint bids
+    fn seeds(&self) -> (&'static [u8], [u8; 32]) {
+        match self {
+            BidTarget::Mint(nft_mint) => (NFTBid::SEED, nft_mint.to_bytes()),
+            BidTarget::Collection(root) => (Self::COLLECTION_SEED, *root),
+        }
+    }
+
+    // For collection bids, `proof` shows that the mint is in the allowlist
+    pub fn covers(&self, nft_mint: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        match self {
+            BidTarget::Mint(mint) => mint == nft_mint,
+            BidTarget::Collection(root) => allowlist::verify(root, nft_mint, proof),
+        }
+    }
+}
+
 impl NFTBid {
+    pub const ESCROW_SEED: &'static [u8] = b"TakerNFTBidEscrow";
+
     #[throws(ProgramError)]
     pub fn ensure<'info>(
         program_id: &Pubkey,
-        nft_mint: &Pubkey,
+        pool: &Pubkey,
+        target: &BidTarget,
         wallet: &AccountInfo<'info>,
         bid_account: &AccountInfo<'info>,
         rent: &Sysvar<'info, Rent>,
         system: &AccountInfo<'info>,
     ) -> ProgramAccount<'info, Self> {
-        let (_, bump) = Self::get_address_with_bump(program_id, nft_mint, wallet.key);
+        let (_, bump) = Self::get_address_with_bump(program_id, pool, target, wallet.key);
 
-        Self::verify_address(program_id, nft_mint, wallet.key, bump, bid_account.key)?;
+        Self::verify_address(program_id, pool, target, wallet.key, bump, bid_account.key)?;
 
         if !crate::utils::is_account_allocated(bid_account) {
-            let instance = NFTBid { price: 0, qty: 0 };
+            let instance = NFTBid {
+                target: *target,
+                price: 0,
+                qty: 0,
+                terms: LoanTerms::default(),
+                escrowed: false,
+                expires_at: 0,
+                min_amount: 0,
+            };
 
             let acc_size = 8 + instance
                 .try_to_vec()
                 .map_err(|_| ProgramError::Custom(1))?
                 .len() as u64;
 
+            let (seed, target_key) = target.seeds();
             let seeds_with_bump: &[&[_]] = &[
-                Self::SEED,
-                &nft_mint.to_bytes(),
+                seed,
+                &pool.to_bytes(),
+                &target_key,
                 &wallet.key.to_bytes(),
                 &[bump],
             ];
@@ -43,8 +119,8 @@ impl NFTBid {
                 seeds_with_bump,
                 bid_account,
                 acc_size,
-                &rent,
-                &system,
+                rent,
+                system,
             )?;
 
             {
@@ -57,8 +133,18 @@ impl NFTBid {
         ProgramAccount::try_from(bid_account)?
     }
 
+    // Fills `qty` of the bid with loans of `amount` each
     #[throws(TakerError)]
-    pub fn trade(&mut self, qty: u64) {
+    pub fn trade(&mut self, qty: u64, amount: u64, now: UnixTimestamp) {
+        if self.is_expired(now) {
+            throw!(TakerError::BidExpired)
+        }
+        if amount > self.price {
+            throw!(TakerError::NFTBorrowExceedBidAmount)
+        }
+        if amount < self.min_amount {
+            throw!(TakerError::BorrowBelowBidMinimum)
+        }
         if qty > self.qty {
             throw!(TakerError::NFTOvertrade)
         }
@@ -68,47 +154,239 @@ impl NFTBid {
         }
     }
 
-    pub fn set(&mut self, price: u64, qty: u64) {
+    pub fn set(&mut self, price: u64, qty: u64, terms: LoanTerms, escrowed: bool) {
         self.price = price;
         self.qty = qty;
+        self.terms = terms;
+        self.escrowed = escrowed;
+    }
+
+    pub fn set_limits(&mut self, expires_at: UnixTimestamp, min_amount: u64) {
+        self.expires_at = expires_at;
+        self.min_amount = min_amount;
+    }
+
+    // The DAI the bid still offers, counted in the utilization of the pool
+    pub fn offered(&self) -> u64 {
+        self.price.saturating_mul(self.qty)
+    }
+
+    pub fn is_expired(&self, now: UnixTimestamp) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
     }
 
     pub fn cancel(&mut self) {
         self.price = 0;
         self.qty = 0;
+        self.escrowed = false;
+    }
+
+    // Moves DAI between the lender and the escrow of the bid until the escrow holds `amount`.
+    // The escrow is created on the first deposit, an escrow that was never created holds nothing
+    #[throws(ProgramError)]
+    pub fn fund_escrow<'info>(
+        program_id: &Pubkey,
+        pool: &ProgramAccount<'info, NFTPool>,
+        bid_account: &AccountInfo<'info>,
+        escrow_account: &AccountInfo<'info>,
+        dai_mint: &CpiAccount<'info, Mint>,
+        lender_wallet: &AccountInfo<'info>,
+        lender_dai_account: &CpiAccount<'info, TokenAccount>,
+        amount: u64,
+        spl_program: &AccountInfo<'info>,
+        system: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) {
+        let (_, bump) = Self::get_escrow_address_with_bump(program_id, bid_account.key);
+        Self::verify_escrow_address(program_id, bid_account.key, bump, escrow_account.key)?;
+
+        if &lender_dai_account.owner != lender_wallet.key {
+            throw!(TakerError::LenderAccountMismatch)
+        }
+
+        let held = if utils::is_account_allocated(escrow_account) {
+            anchor_spl::token::accessor::amount(escrow_account)?
+        } else if amount > 0 {
+            if dai_mint.to_account_info().key != &pool.dai_mint {
+                throw!(TakerError::MintMismatch)
+            }
+            utils::create_derived_token_account(
+                lender_wallet,
+                &[Self::ESCROW_SEED, &bid_account.key.to_bytes(), &[bump]],
+                escrow_account,
+                dai_mint,
+                &pool.to_account_info(),
+                spl_program,
+                system,
+                rent,
+            )?;
+            0
+        } else {
+            return;
+        };
+
+        if amount > held {
+            anchor_spl::token::transfer(
+                CpiContext::new(
+                    spl_program.clone(),
+                    anchor_spl::token::Transfer {
+                        from: lender_dai_account.to_account_info(),
+                        to: escrow_account.clone(),
+                        authority: lender_wallet.clone(),
+                    },
+                ),
+                amount - held,
+            )?;
+        } else if amount < held {
+            anchor_spl::token::transfer(
+                CpiContext::new_with_signer(
+                    spl_program.clone(),
+                    anchor_spl::token::Transfer {
+                        from: escrow_account.clone(),
+                        to: lender_dai_account.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+                ),
+                held - amount,
+            )?;
+        }
+    }
+
+    // Refunds the escrow of the bid and gives its rent back to the lender
+    #[throws(ProgramError)]
+    pub fn close_escrow<'info>(
+        program_id: &Pubkey,
+        pool: &ProgramAccount<'info, NFTPool>,
+        bid_account: &AccountInfo<'info>,
+        escrow_account: &AccountInfo<'info>,
+        lender_wallet: &AccountInfo<'info>,
+        lender_dai_account: &CpiAccount<'info, TokenAccount>,
+        spl_program: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_escrow_address_with_bump(program_id, bid_account.key);
+        Self::verify_escrow_address(program_id, bid_account.key, bump, escrow_account.key)?;
+
+        if !utils::is_account_allocated(escrow_account) {
+            return;
+        }
+
+        if &lender_dai_account.owner != lender_wallet.key {
+            throw!(TakerError::LenderAccountMismatch)
+        }
+
+        let seeds: &[&[&[u8]]] = &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]];
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::Transfer {
+                    from: escrow_account.clone(),
+                    to: lender_dai_account.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                seeds,
+            ),
+            anchor_spl::token::accessor::amount(escrow_account)?,
+        )?;
+
+        solana_program::program::invoke_signed(
+            &spl_token::instruction::close_account(
+                &spl_token::id(),
+                escrow_account.key,
+                lender_wallet.key,
+                pool.to_account_info().key,
+                &[],
+            )?,
+            &[
+                escrow_account.clone(),
+                lender_wallet.clone(),
+                pool.to_account_info(),
+                spl_program.clone(),
+            ],
+            seeds,
+        )?;
     }
 
     // An program derived account that stores nft bid
     // The address of the account is computed as follow:
-    // address = find_program_address([NFTBid::SEED, nft_mint_address, user_wallet_address], program_id)
+    // address = find_program_address([NFTBid::SEED, pool_address, nft_mint_address, user_wallet_address], program_id)
     // only the taker_contract_address can change the data in this account
-    pub fn get_address(program_id: &Pubkey, nft_mint: &Pubkey, wallet: &Pubkey) -> Pubkey {
-        Self::get_address_with_bump(program_id, nft_mint, wallet).0
+    pub fn get_address(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        nft_mint: &Pubkey,
+        wallet: &Pubkey,
+    ) -> Pubkey {
+        Self::get_address_with_bump(program_id, pool, &BidTarget::Mint(*nft_mint), wallet).0
+    }
+
+    // Same as above for collection bids:
+    // address = find_program_address([COLLECTION_SEED, pool_address, allowlist_root, user_wallet_address], program_id)
+    pub fn get_collection_address(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        allowlist_root: &[u8; 32],
+        wallet: &Pubkey,
+    ) -> Pubkey {
+        Self::get_address_with_bump(
+            program_id,
+            pool,
+            &BidTarget::Collection(*allowlist_root),
+            wallet,
+        )
+        .0
     }
 
     pub(crate) fn get_address_with_bump(
         program_id: &Pubkey,
-        nft_mint: &Pubkey,
+        pool: &Pubkey,
+        target: &BidTarget,
         wallet: &Pubkey,
     ) -> (Pubkey, u8) {
+        let (seed, target_key) = target.seeds();
         Pubkey::find_program_address(
-            &[Self::SEED, &nft_mint.to_bytes(), &wallet.to_bytes()],
+            &[seed, &pool.to_bytes(), &target_key, &wallet.to_bytes()],
             program_id,
         )
     }
 
+    // The DAI of an escrowed bid, held by a token account of the pool:
+    // address = find_program_address([NFTBid::ESCROW_SEED, bid_address], program_id)
+    pub fn get_escrow_address(program_id: &Pubkey, bid: &Pubkey) -> Pubkey {
+        Self::get_escrow_address_with_bump(program_id, bid).0
+    }
+
+    pub(crate) fn get_escrow_address_with_bump(program_id: &Pubkey, bid: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::ESCROW_SEED, &bid.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_escrow_address(program_id: &Pubkey, bid: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::ESCROW_SEED, &bid.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(TakerError::BidEscrowAddressNotCorrect);
+        }
+    }
+
     #[throws(ProgramError)]
     pub fn verify_address(
         program_id: &Pubkey,
-        nft_mint: &Pubkey,
+        pool: &Pubkey,
+        target: &BidTarget,
         wallet: &Pubkey,
         bump: u8,
         address: &Pubkey,
     ) {
+        let (seed, target_key) = target.seeds();
         let addr = Pubkey::create_program_address(
             &[
-                Self::SEED,
-                &nft_mint.to_bytes(),
+                seed,
+                &pool.to_bytes(),
+                &target_key,
                 &wallet.to_bytes(),
                 &[bump],
             ],
@@ -1,16 +1,19 @@
 use anchor_lang::prelude::Pubkey;
 use solana_program::clock::UnixTimestamp;
 
-use crate::{utils, DerivedAccountIdentifier, NFTDeposit, TakerError};
+use crate::interest::{InterestCurve, InterestModel, KinkedInterest};
+use crate::{utils, DerivedAccountIdentifier, LoanTerms, NFTDeposit, NFTPool, TakerError};
 use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, TokenAccount};
 use borsh::{BorshDeserialize, BorshSerialize};
 use fehler::{throw, throws};
+use std::convert::TryInto;
 
 impl DerivedAccountIdentifier for NFTDeposit {
     const SEED: &'static [u8] = b"TakerNFTDeposit";
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
 pub enum DepositState {
     PendingLoan,                   // Loan hasn't happened yet and the NFT is in the pool
     LoanActive(LoanActiveState),   // Loan is active
@@ -20,28 +23,157 @@ pub enum DepositState {
     Withdrawn,      // Loan did not happen and the NFT is withdrawn by the borrower
     LoanLiquidated, // Loan liquidated and the NFT is withdrawn by the lender
     LoanCleared,
+
+    LoanAuctioned(LoanAuctionState), // Loan expired and the NFT is on sale, see `start_auction`
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
 pub struct LoanActiveState {
-    pub total_amount: u64,
-    pub borrowed_amount: u64,      // amount of dai
-    pub started_at: UnixTimestamp, // in seconds
-    pub expired_at: UnixTimestamp, // in seconds
+    pub total_amount: u64, // dai owed to the lender once repayed, grows with the interest paid so far
+    pub borrowed_amount: u64, // amount of dai lent, the lender holds as much TAI
+    pub repayed_amount: u64, // principal already paid back through repay_partial
+    pub started_at: UnixTimestamp, // in seconds, the interest is settled up to here
+    pub duration: i64,     // in seconds, from started_at until the loan expires
     pub lender: Pubkey,
+    // Snapshotted when the loan starts, the loan settles with these rates only
+    pub interest_rate: u64,            // in bp per day, taken from the bid
+    pub service_fee_rate: u64,         // in bp, taken from the pool
+    pub interest_model: InterestCurve, // taken from the pool
+    pub grace_period: i64,             // in seconds after the expiry, taken from the pool
+    pub grace_penalty_rate: u64,       // in bp of the outstanding principal, taken from the pool
+    pub auction_duration: i64, // in seconds, 0 lets the lender take the NFT, taken from the pool
+    pub auction_start_rate: u64, // in bp of the claim of the lender, taken from the pool
+    pub utilization: u64,      // in bp, of the pool with the loan in it, prices the kinked model
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy)]
+// The claim of the lender is frozen when the auction starts, the price falls linearly
+// from `start_price` at `started_at` to `claim` at `ended_at`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct LoanAuctionState {
+    pub lender: Pubkey,
+    pub tai_required_to_unlock: u64,
+    pub pool_holdings: u64, // dai already in the pool for the lender
+    pub claim: u64,         // outstanding principal plus the interest over the loan, fee included
+    pub fee: u64,
+    pub start_price: u64,
+    pub started_at: UnixTimestamp,
+    pub ended_at: UnixTimestamp,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
 pub struct LoanRepayedState {
     pub tai_required_to_unlock: u64,
     pub lender_withdrawable: u64,
     pub lender: Pubkey,
 }
 
+impl LoanActiveState {
+    // Saturates, the terms bound the interest over a loan but not its duration
+    pub fn expired_at(&self) -> UnixTimestamp {
+        self.started_at.saturating_add(self.duration)
+    }
+
+    // The borrower can still repay until then, the lender takes the NFT afterwards
+    pub fn grace_end(&self) -> UnixTimestamp {
+        self.expired_at().saturating_add(self.grace_period)
+    }
+
+    // Charged on top of the interest when repaying during the grace period
+    pub fn grace_penalty(&self) -> u64 {
+        (self.outstanding_amount() as u128 * self.grace_penalty_rate as u128 / 10000) as u64
+    }
+
+    // The principal still to be repayed, repay_partial keeps it above zero
+    pub fn outstanding_amount(&self) -> u64 {
+        self.borrowed_amount - self.repayed_amount
+    }
+
+    // The interest accrued on the outstanding principal after `duration` seconds and the part of it
+    // charged as service fee
+    #[throws(TakerError)]
+    pub fn calculate_interest_and_fee(&self, duration: i64) -> (u64, u64) {
+        let duration: u64 = duration.try_into().map_err(|_| TakerError::MathOverflow)?;
+
+        let interest = self.interest_model.interest(
+            self.outstanding_amount(),
+            self.interest_rate,
+            duration,
+            self.utilization,
+        )?;
+        let fee = interest
+            .checked_mul(self.service_fee_rate)
+            .and_then(|v| v.checked_div(10000))
+            .ok_or(TakerError::MathOverflow)?;
+
+        (interest, fee)
+    }
+
+    // What the loan is worth to the lender once it expires: the outstanding principal with the interest
+    // over the rest of the loan, fee included, on top of the DAI the pool already holds for the lender
+    #[throws(TakerError)]
+    pub fn claim(&self) -> (u64, u64) {
+        let (interest, fee) = self.calculate_interest_and_fee(self.duration)?;
+        let claim = self
+            .outstanding_amount()
+            .checked_add(interest)
+            .ok_or(TakerError::MathOverflow)?;
+
+        (claim, fee)
+    }
+
+    // The DAI the pool holds for the lender: the mortgage margin, the repayed principal and the interest
+    pub fn pool_holdings(&self) -> u64 {
+        self.total_amount - self.outstanding_amount()
+    }
+
+    // The interest is settled up to `now`, the loan runs from there until `expired_at`
+    #[throws(TakerError)]
+    fn restart(&mut self, now: UnixTimestamp, expired_at: UnixTimestamp) {
+        self.duration = expired_at
+            .checked_sub(now)
+            .ok_or(TakerError::MathOverflow)?;
+        self.started_at = now;
+    }
+}
+
+impl LoanAuctionState {
+    pub fn price_at(&self, now: UnixTimestamp) -> u64 {
+        if now >= self.ended_at {
+            return self.claim;
+        }
+
+        let discount = self.start_price.saturating_sub(self.claim);
+        let elapsed = now.saturating_sub(self.started_at) as u128;
+        let length = (self.ended_at - self.started_at) as u128;
+        let fallen = (discount as u128 * elapsed / length) as u64;
+
+        self.start_price - fallen
+    }
+}
+
 impl NFTDeposit {
+    pub const LOAN_NOTE_SEED: &'static [u8] = b"TakerLoanNote";
+
+    pub fn deposit_id(&self) -> Pubkey {
+        self.deposit_id
+    }
+
+    pub fn nft_mint(&self) -> Pubkey {
+        self.nft_mint
+    }
+
+    pub fn borrower(&self) -> Pubkey {
+        self.borrower
+    }
+
+    pub fn state(&self) -> DepositState {
+        self.state
+    }
+
     #[throws(ProgramError)]
     pub fn deposit<'info>(
         program_id: &Pubkey,
+        pool: &Pubkey,
         deposit_id: &Pubkey,
         nft_mint: &Pubkey,
         borrower_wallet: &AccountInfo<'info>,
@@ -49,11 +181,17 @@ impl NFTDeposit {
         rent: &Sysvar<'info, Rent>,
         system_program: &AccountInfo<'info>,
     ) -> ProgramAccount<'info, Self> {
-        let (_, bump) =
-            Self::get_address_with_bump(program_id, nft_mint, borrower_wallet.key, deposit_id);
+        let (_, bump) = Self::get_address_with_bump(
+            program_id,
+            pool,
+            nft_mint,
+            borrower_wallet.key,
+            deposit_id,
+        );
 
         Self::verify_address(
             program_id,
+            pool,
             nft_mint,
             borrower_wallet.key,
             deposit_id,
@@ -61,19 +199,21 @@ impl NFTDeposit {
             deposit_account.key,
         )?;
 
-        // Do not reuse the loan record
-        // TODO: deallocate the loan record and give lamports back to the user.
+        // Do not reuse the loan record, finished records are deallocated by `close`
         if crate::utils::is_account_allocated(deposit_account) {
             throw!(TakerError::LoanAlreadyExist);
         }
 
         let instance = NFTDeposit {
             deposit_id: *deposit_id,
+            nft_mint: *nft_mint,
+            borrower: *borrower_wallet.key,
             state: DepositState::PendingLoan,
         };
 
         let seeds_with_bump: &[&[_]] = &[
             Self::SEED,
+            &pool.to_bytes(),
             &nft_mint.to_bytes(),
             &borrower_wallet.key.to_bytes(),
             &deposit_id.to_bytes(),
@@ -86,8 +226,8 @@ impl NFTDeposit {
             seeds_with_bump,
             deposit_account,
             Self::account_size() as u64,
-            &rent,
-            &system_program,
+            rent,
+            system_program,
         )?;
 
         {
@@ -101,6 +241,31 @@ impl NFTDeposit {
         loan_account
     }
 
+    // Deallocates a deposit in a terminal state and gives the rent back to the borrower who funded it.
+    // The account has no lamports left afterwards, so the runtime removes it at the end of the transaction.
+    #[throws(ProgramError)]
+    pub fn close<'info>(
+        deposit_account: &ProgramAccount<'info, Self>,
+        borrower_wallet: &AccountInfo<'info>,
+    ) {
+        if !deposit_account.is_closable() {
+            throw!(TakerError::DepositNotClosable);
+        }
+
+        if &deposit_account.borrower != borrower_wallet.key {
+            throw!(TakerError::BorrowerMismatch);
+        }
+
+        anchor_lang::AccountsClose::close(deposit_account, borrower_wallet.clone())?;
+    }
+
+    pub fn is_closable(&self) -> bool {
+        matches!(
+            self.state,
+            DepositState::Withdrawn | DepositState::LoanLiquidated | DepositState::LoanCleared
+        )
+    }
+
     #[throws(TakerError)]
     pub fn withdraw(&mut self) {
         use DepositState::*;
@@ -108,7 +273,7 @@ impl NFTDeposit {
         match self.state {
             PendingLoan => self.state = DepositState::Withdrawn,
             Withdrawn | LoanRepayed { .. } | LoanCleared => throw!(TakerError::NFTAlreadyWithdrawn),
-            LoanActive(_) | LoanLiquidated => throw!(TakerError::NFTLocked),
+            LoanActive(_) | LoanAuctioned(_) | LoanLiquidated => throw!(TakerError::NFTLocked),
         }
     }
 
@@ -119,46 +284,181 @@ impl NFTDeposit {
         total_amount: u64,
         borrowed_amount: u64,
         start: UnixTimestamp,
-        length: i64,
+        terms: &LoanTerms,
+        pool: &NFTPool,
     ) {
         if !matches!(self.state, DepositState::PendingLoan) {
             throw!(TakerError::BorrowAlreadyStarted)
         }
 
-        assert!(total_amount >= borrowed_amount);
+        self.start_loan(lender, total_amount, borrowed_amount, start, terms, pool)?;
+    }
+
+    // The loan moves to `lender` and starts over from `start` with `amount` as its principal.
+    // The new lender pays off exactly the principal, so the pool holds no margin for the loan.
+    // Returns the loan that was paid off
+    #[throws(TakerError)]
+    pub fn refinance(
+        &mut self,
+        lender: Pubkey,
+        amount: u64,
+        start: UnixTimestamp,
+        terms: &LoanTerms,
+        pool: &NFTPool,
+    ) -> LoanActiveState {
+        let loan = self.get_active_state()?;
+
+        self.start_loan(lender, amount, amount, start, terms, pool)?;
+
+        loan
+    }
+
+    #[throws(TakerError)]
+    fn start_loan(
+        &mut self,
+        lender: Pubkey,
+        total_amount: u64,
+        borrowed_amount: u64,
+        start: UnixTimestamp,
+        terms: &LoanTerms,
+        pool: &NFTPool,
+    ) {
+        if borrowed_amount > total_amount {
+            throw!(TakerError::BorrowedAmountExceedTotal)
+        }
+
         self.state = DepositState::LoanActive(LoanActiveState {
             lender,
             total_amount,
-            borrowed_amount,            // amount of dai
-            started_at: start,          // in seconds
-            expired_at: start + length, // in seconds
+            borrowed_amount,   // amount of dai
+            repayed_amount: 0, // amount of dai
+            started_at: start, // in seconds
+            duration: terms.max_loan_duration,
+            interest_rate: terms.interest_rate,
+            service_fee_rate: pool.service_fee_rate,
+            interest_model: pool.interest_model,
+            grace_period: pool.grace_period,
+            grace_penalty_rate: pool.grace_penalty_rate,
+            auction_duration: pool.auction_duration,
+            auction_start_rate: pool.auction_start_rate,
+            utilization: pool.utilization(),
         });
     }
 
+    // The interest is settled up to `now`, the loan then runs again from `now` until the later expiry
     #[throws(TakerError)]
-    pub fn repay(&mut self, lender_withdrawable: u64) {
+    pub fn extend(&mut self, now: UnixTimestamp, extension: i64) -> LoanActiveState {
+        let mut loan = self.get_active_state()?;
+        let expired_at = loan
+            .expired_at()
+            .checked_add(extension)
+            .ok_or(TakerError::MathOverflow)?;
+        loan.restart(now, expired_at)?;
+        self.state = DepositState::LoanActive(loan);
+
+        loan
+    }
+
+    // Pays back `amount` of the principal and settles the interest up to `now`. The lender's share of
+    // the interest stays in the pool with the principal until the loan is repayed
+    #[throws(TakerError)]
+    pub fn repay_partial(
+        &mut self,
+        amount: u64,
+        lender_income: u64,
+        now: UnixTimestamp,
+    ) -> LoanActiveState {
+        let mut loan = self.get_active_state()?;
+
+        // the last part of the principal is paid back by `repay`, which releases the NFT
+        if amount == 0 || amount >= loan.outstanding_amount() {
+            throw!(TakerError::InvalidRepayAmount)
+        }
+
+        loan.repayed_amount += amount;
+        loan.total_amount = loan
+            .total_amount
+            .checked_add(lender_income)
+            .ok_or(TakerError::MathOverflow)?;
+        let expired_at = loan.expired_at();
+        loan.restart(now, expired_at)?;
+        self.state = DepositState::LoanActive(loan);
+
+        loan
+    }
+
+    // Puts the NFT of an expired loan on sale with the auction settings the loan started with
+    #[throws(TakerError)]
+    pub fn start_auction(&mut self, now: UnixTimestamp) -> LoanAuctionState {
+        let loan = self.get_active_state()?;
+        let (claim, fee) = loan.claim()?;
+
+        let auction = LoanAuctionState {
+            lender: loan.lender,
+            tai_required_to_unlock: loan.borrowed_amount,
+            pool_holdings: loan.pool_holdings(),
+            claim,
+            fee,
+            start_price: claim
+                .checked_mul(loan.auction_start_rate)
+                .and_then(|v| v.checked_div(10000))
+                .ok_or(TakerError::MathOverflow)?,
+            started_at: now,
+            ended_at: now
+                .checked_add(loan.auction_duration)
+                .ok_or(TakerError::MathOverflow)?,
+        };
+        self.state = DepositState::LoanAuctioned(auction);
+
+        auction
+    }
+
+    // The buyer paid the claim of the lender, who gets the DAI back like for a repayed loan
+    #[throws(TakerError)]
+    pub fn sell(&mut self) {
         match self.state {
-            DepositState::LoanActive(LoanActiveState {
-                lender,
-                borrowed_amount,
-                ..
-            }) => {
+            DepositState::LoanAuctioned(auction) => {
                 self.state = DepositState::LoanRepayed(LoanRepayedState {
-                    tai_required_to_unlock: borrowed_amount,
-                    lender_withdrawable,
-                    lender,
+                    tai_required_to_unlock: auction.tai_required_to_unlock,
+                    lender_withdrawable: auction
+                        .pool_holdings
+                        .checked_add(
+                            auction
+                                .claim
+                                .checked_sub(auction.fee)
+                                .ok_or(TakerError::MathOverflow)?,
+                        )
+                        .ok_or(TakerError::MathOverflow)?,
+                    lender: auction.lender,
                 })
             }
-            _ => {
-                throw!(TakerError::LoanNotActive)
-            }
+            _ => throw!(TakerError::AuctionNotRunning),
         }
     }
 
+    #[throws(TakerError)]
+    pub fn get_auction_state(&self) -> LoanAuctionState {
+        match self.state {
+            DepositState::LoanAuctioned(auction) => auction,
+            _ => throw!(TakerError::AuctionNotRunning),
+        }
+    }
+
+    #[throws(TakerError)]
+    pub fn repay(&mut self, lender_withdrawable: u64) {
+        let loan = self.get_active_state()?;
+
+        self.state = DepositState::LoanRepayed(LoanRepayedState {
+            tai_required_to_unlock: loan.borrowed_amount,
+            lender_withdrawable,
+            lender: loan.lender,
+        })
+    }
+
     #[throws(TakerError)]
     pub fn liquidate(&mut self) {
         match self.state {
-            DepositState::LoanActive { .. } => {
+            DepositState::LoanActive { .. } | DepositState::LoanAuctioned { .. } => {
                 self.state = DepositState::LoanLiquidated;
             }
             _ => {
@@ -186,7 +486,8 @@ impl NFTDeposit {
             | DepositState::LoanLiquidated
             | DepositState::LoanRepayed { .. }
             | DepositState::Withdrawn
-            | DepositState::LoanCleared => throw!(TakerError::LoanNotActive),
+            | DepositState::LoanCleared
+            | DepositState::LoanAuctioned { .. } => throw!(TakerError::LoanNotActive),
             DepositState::LoanActive(s) => s,
         }
     }
@@ -198,26 +499,30 @@ impl NFTDeposit {
             | DepositState::LoanLiquidated
             | DepositState::Withdrawn
             | DepositState::LoanCleared => throw!(TakerError::LoanNotActive),
-            DepositState::LoanActive { .. } => throw!(TakerError::LoanNotRepayed),
+            DepositState::LoanActive { .. } | DepositState::LoanAuctioned { .. } => {
+                throw!(TakerError::LoanNotRepayed)
+            }
             DepositState::LoanRepayed(r) => r,
         }
     }
 
     // An program derived account that stores nft loan
     // The address of the account is computed as follow:
-    // address = find_program_address([NFTLoan::SEED, nft_mint_address, borrower_wallet_address, loan_id], program_id)
+    // address = find_program_address([NFTLoan::SEED, pool_address, nft_mint_address, borrower_wallet_address, loan_id], program_id)
     // only the taker_contract_address can change the data in this account
     pub fn get_address(
         program_id: &Pubkey,
+        pool: &Pubkey,
         nft_mint: &Pubkey,
         borrower_wallet: &Pubkey,
         deposit_id: &Pubkey,
     ) -> Pubkey {
-        Self::get_address_with_bump(program_id, nft_mint, borrower_wallet, deposit_id).0
+        Self::get_address_with_bump(program_id, pool, nft_mint, borrower_wallet, deposit_id).0
     }
 
     pub(crate) fn get_address_with_bump(
         program_id: &Pubkey,
+        pool: &Pubkey,
         nft_mint: &Pubkey,
         borrower_wallet: &Pubkey,
         deposit_id: &Pubkey,
@@ -225,6 +530,7 @@ impl NFTDeposit {
         Pubkey::find_program_address(
             &[
                 Self::SEED,
+                &pool.to_bytes(),
                 &nft_mint.to_bytes(),
                 &borrower_wallet.to_bytes(),
                 &deposit_id.to_bytes(),
@@ -236,6 +542,7 @@ impl NFTDeposit {
     #[throws(ProgramError)]
     pub fn verify_address(
         program_id: &Pubkey,
+        pool: &Pubkey,
         nft_mint: &Pubkey,
         borrower_wallet: &Pubkey,
         deposit_id: &Pubkey,
@@ -245,6 +552,7 @@ impl NFTDeposit {
         let addr = Pubkey::create_program_address(
             &[
                 Self::SEED,
+                &pool.to_bytes(),
                 &nft_mint.to_bytes(),
                 &borrower_wallet.to_bytes(),
                 &deposit_id.to_bytes(),
@@ -258,21 +566,247 @@ impl NFTDeposit {
         }
     }
 
-    fn account_size() -> usize {
+    // Checks that `address` is derived from the records of this deposit in `pool`
+    #[throws(ProgramError)]
+    pub fn verify_own_address(&self, program_id: &Pubkey, pool: &Pubkey, address: &Pubkey) {
+        let (_, bump) = Self::get_address_with_bump(
+            program_id,
+            pool,
+            &self.nft_mint,
+            &self.borrower,
+            &self.deposit_id,
+        );
+
+        Self::verify_address(
+            program_id,
+            pool,
+            &self.nft_mint,
+            &self.borrower,
+            &self.deposit_id,
+            bump,
+            address,
+        )?;
+    }
+
+    // Mints the loan note of `lender` on the deposit to the lender's associated account. The note is
+    // the claim on the loan: whoever holds it is paid in place of the lender when the loan ends
+    #[throws(ProgramError)]
+    pub fn issue_loan_note<'info>(
+        program_id: &Pubkey,
+        pool: &ProgramAccount<'info, NFTPool>,
+        deposit: &Pubkey,
+        lender: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        note_mint: &AccountInfo<'info>,
+        lender_note_account: &AccountInfo<'info>,
+        ata_program: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) {
+        let (_, bump) = Self::get_loan_note_address_with_bump(program_id, deposit, lender.key);
+        Self::verify_loan_note_address(program_id, deposit, lender.key, bump, note_mint.key)?;
+
+        if !utils::is_account_allocated(note_mint) {
+            utils::create_derived_mint(
+                funder,
+                &[
+                    Self::LOAN_NOTE_SEED,
+                    &deposit.to_bytes(),
+                    &lender.key.to_bytes(),
+                    &[bump],
+                ],
+                note_mint,
+                &pool.to_account_info(),
+                0,
+                spl_program,
+                system_program,
+                rent,
+            )?;
+        }
+
+        // the mint is reused once the previous note of the lender on the deposit is redeemed
+        let mint: CpiAccount<Mint> = CpiAccount::try_from(note_mint)?;
+        if mint.supply != 0 {
+            throw!(TakerError::LoanNoteOutstanding)
+        }
+
+        NFTPool::ensure_token_account_funded_by(
+            lender,
+            funder,
+            &mint,
+            lender_note_account,
+            ata_program,
+            spl_program,
+            system_program,
+            rent,
+        )?;
+
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                spl_program.clone(),
+                anchor_spl::token::MintTo {
+                    mint: note_mint.clone(),
+                    to: lender_note_account.clone(),
+                    authority: pool.to_account_info(),
+                },
+                &[&[NFTPool::SEED, &pool.pool_id.to_bytes(), &[pool.bump_seed]]],
+            ),
+            1,
+        )?;
+    }
+
+    // Checks that `holder` holds the loan note of `lender` on the deposit, the holder has the rights
+    // of the lender on the loan
+    #[throws(ProgramError)]
+    pub fn verify_loan_note_holder<'info>(
+        program_id: &Pubkey,
+        deposit: &Pubkey,
+        lender: &Pubkey,
+        holder: &Pubkey,
+        note_mint: &AccountInfo<'info>,
+        holder_note_account: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_loan_note_address_with_bump(program_id, deposit, lender);
+        Self::verify_loan_note_address(program_id, deposit, lender, bump, note_mint.key)?;
+
+        // nothing is burned when only checking, the note must be a genuine token account
+        if holder_note_account.owner != &spl_token::id() {
+            throw!(TakerError::NotLoanNoteHolder)
+        }
+
+        let note: CpiAccount<TokenAccount> =
+            CpiAccount::try_from(holder_note_account).map_err(|_| TakerError::NotLoanNoteHolder)?;
+        if &note.mint != note_mint.key || &note.owner != holder || note.amount != 1 {
+            throw!(TakerError::NotLoanNoteHolder)
+        }
+    }
+
+    // Burns the loan note of `lender` held by `holder`, who is then paid for the loan
+    #[throws(ProgramError)]
+    pub fn redeem_loan_note<'info>(
+        program_id: &Pubkey,
+        deposit: &Pubkey,
+        lender: &Pubkey,
+        holder: &AccountInfo<'info>,
+        note_mint: &AccountInfo<'info>,
+        holder_note_account: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+    ) {
+        Self::verify_loan_note_holder(
+            program_id,
+            deposit,
+            lender,
+            holder.key,
+            note_mint,
+            holder_note_account,
+        )?;
+
+        anchor_spl::token::burn(
+            CpiContext::new(
+                spl_program.clone(),
+                anchor_spl::token::Burn {
+                    mint: note_mint.clone(),
+                    to: holder_note_account.clone(),
+                    authority: holder.clone(),
+                },
+            ),
+            1,
+        )?;
+    }
+
+    // The mint of the loan note of `lender` on the deposit, owned by the token program:
+    // address = find_program_address([NFTDeposit::LOAN_NOTE_SEED, deposit_address, lender], program_id)
+    pub fn get_loan_note_address(program_id: &Pubkey, deposit: &Pubkey, lender: &Pubkey) -> Pubkey {
+        Self::get_loan_note_address_with_bump(program_id, deposit, lender).0
+    }
+
+    pub(crate) fn get_loan_note_address_with_bump(
+        program_id: &Pubkey,
+        deposit: &Pubkey,
+        lender: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                Self::LOAN_NOTE_SEED,
+                &deposit.to_bytes(),
+                &lender.to_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_loan_note_address(
+        program_id: &Pubkey,
+        deposit: &Pubkey,
+        lender: &Pubkey,
+        bump: u8,
+        address: &Pubkey,
+    ) {
+        let addr = Pubkey::create_program_address(
+            &[
+                Self::LOAN_NOTE_SEED,
+                &deposit.to_bytes(),
+                &lender.to_bytes(),
+                &[bump],
+            ],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(TakerError::LoanNoteAddressNotCorrect);
+        }
+    }
+
+    pub(crate) fn account_size() -> usize {
         // Borsh does not support vary size structure.
         // Pick the largest variant so that we are safe
-        let largest_instance = NFTDeposit {
-            deposit_id: Pubkey::new(&[0u8; 32]),
-            state: DepositState::LoanActive(LoanActiveState {
+        let lender = Pubkey::new(&[0u8; 32]);
+        let largest_states = [
+            DepositState::LoanActive(LoanActiveState {
                 total_amount: 0,
                 borrowed_amount: 0,
+                repayed_amount: 0,
                 started_at: 0,
-                expired_at: 0,
-                lender: Pubkey::new(&[0u8; 32]),
+                duration: 0,
+                lender,
+                interest_rate: 0,
+                service_fee_rate: 0,
+                interest_model: InterestCurve::Kinked(KinkedInterest {
+                    kink: 0,
+                    jump_rate: 0,
+                }),
+                grace_period: 0,
+                grace_penalty_rate: 0,
+                auction_duration: 0,
+                auction_start_rate: 0,
+                utilization: 0,
             }),
-        };
+            DepositState::LoanAuctioned(LoanAuctionState {
+                lender,
+                tai_required_to_unlock: 0,
+                pool_holdings: 0,
+                claim: 0,
+                fee: 0,
+                start_price: 0,
+                started_at: 0,
+                ended_at: 0,
+            }),
+        ];
 
-        let acc_size = 8 + largest_instance.try_to_vec().unwrap().len();
-        acc_size
+        largest_states
+            .iter()
+            .map(|state| {
+                let instance = NFTDeposit {
+                    deposit_id: Pubkey::new(&[0u8; 32]),
+                    nft_mint: Pubkey::new(&[0u8; 32]),
+                    borrower: Pubkey::new(&[0u8; 32]),
+                    state: *state,
+                };
+                8 + instance.try_to_vec().unwrap().len()
+            })
+            .max()
+            .unwrap()
     }
 }
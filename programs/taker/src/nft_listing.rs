@@ -0,0 +1,117 @@
+use crate::{utils, DerivedAccountIdentifier, LoanTerms, NFTListing, TakerError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for NFTListing {
+    const SEED: &'static [u8] = b"TakerNFTListing";
+}
+
+impl NFTListing {
+    pub(crate) fn account_size() -> usize {
+        let instance = NFTListing {
+            deposit: Pubkey::default(),
+            borrower: Pubkey::default(),
+            amount: 0,
+            max_interest_rate: 0,
+            loan_duration: 0,
+        };
+        8 + instance.try_to_vec().unwrap().len()
+    }
+
+    // Creates the listing of the deposit if not created, a deposit has at most one listing
+    #[throws(ProgramError)]
+    pub fn ensure<'info>(
+        program_id: &Pubkey,
+        deposit: &Pubkey,
+        borrower: &AccountInfo<'info>,
+        listing_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system_program: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, deposit);
+        Self::verify_address(program_id, deposit, bump, listing_account.key)?;
+
+        if !utils::is_account_allocated(listing_account) {
+            let instance = NFTListing {
+                deposit: *deposit,
+                borrower: *borrower.key,
+                amount: 0,
+                max_interest_rate: 0,
+                loan_duration: 0,
+            };
+
+            utils::create_derived_account_with_seed(
+                program_id,
+                borrower,
+                &[Self::SEED, &deposit.to_bytes(), &[bump]],
+                listing_account,
+                Self::account_size() as u64,
+                rent,
+                system_program,
+            )?;
+
+            {
+                let mut data = listing_account.try_borrow_mut_data()?;
+                let mut cursor = std::io::Cursor::new(&mut **data);
+                instance.try_serialize(&mut cursor)?;
+            }
+        }
+
+        ProgramAccount::try_from(listing_account)?
+    }
+
+    pub fn set(&mut self, amount: u64, max_interest_rate: u64, loan_duration: i64) {
+        self.amount = amount;
+        self.max_interest_rate = max_interest_rate;
+        self.loan_duration = loan_duration;
+    }
+
+    // The terms of a loan funded at `interest_rate`, the amount lent to the borrower follows the
+    // mortgage rate of the pool as for the bids placed with the default terms
+    #[throws(TakerError)]
+    pub fn terms(&self, interest_rate: u64, mortgage_rate: u64) -> LoanTerms {
+        if interest_rate > self.max_interest_rate {
+            throw!(TakerError::ListingRateTooHigh)
+        }
+
+        let terms = LoanTerms {
+            interest_rate,
+            max_loan_duration: self.loan_duration,
+            mortgage_rate,
+            max_extension: 0,
+        };
+        terms.validate()?;
+        terms
+    }
+
+    // Deallocates the funded or cancelled listing and gives the rent back to the borrower
+    #[throws(ProgramError)]
+    pub fn close<'info>(listing: &ProgramAccount<'info, Self>, borrower: &AccountInfo<'info>) {
+        if &listing.borrower != borrower.key {
+            throw!(TakerError::BorrowerMismatch);
+        }
+
+        anchor_lang::AccountsClose::close(listing, borrower.clone())?;
+    }
+
+    // The listing of the NFTDeposit at `deposit`
+    pub fn get_address(program_id: &Pubkey, deposit: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, deposit).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, deposit: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &deposit.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, deposit: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &deposit.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(TakerError::NFTListingAddressNotCorrect);
+        }
+    }
+}
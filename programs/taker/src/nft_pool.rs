@@ -1,21 +1,152 @@
-use crate::{utils, DerivedAccountIdentifier, NFTPool, TakerError};
+use crate::{
+    utils, DerivedAccountIdentifier, InterestCurve, KinkedInterest, LoanTerms, NFTPool, TakerError,
+};
 use anchor_lang::prelude::*;
 use anchor_spl::token::Mint;
+use borsh::{BorshDeserialize, BorshSerialize};
 use fehler::{throw, throws};
-use std::convert::TryInto;
-
-static SECONDS_PER_DAY: u64 = 24 * 60 * 60;
 
 type Result<T> = std::result::Result<T, ProgramError>;
 
+// The settings the loans start with, changed right away when the pool has no settings delay,
+// queued in a `SettingsChange` otherwise
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoanSettings {
+    pub incentive: u64,
+    pub interest_rate: u64,
+    pub service_fee_rate: u64,
+    pub max_loan_duration: i64,
+    pub mortgage_rate: u64,
+    pub grace_period: i64,
+    pub grace_penalty_rate: u64,
+    pub auction_duration: i64,
+    pub auction_start_rate: u64,
+    pub interest_model: InterestCurve,
+}
+
+// Set by the owner, the settings admin cannot go above them
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SettingsCaps {
+    pub max_incentive: u64,
+    pub max_interest_rate: u64,
+    pub max_service_fee_rate: u64,
+    pub max_loan_duration: i64,
+    pub max_mortgage_rate: u64,
+    pub max_grace_penalty_rate: u64,
+    pub max_jump_rate: u16, // of the kinked interest model
+}
+
+impl LoanSettings {
+    // The bounds of the protocol first, then the caps of the owner
+    #[throws(TakerError)]
+    pub fn validate(&self, caps: &SettingsCaps) {
+        // a larger mortgage rate lends more than the bid price
+        if self.mortgage_rate == 0 || self.mortgage_rate > 10000 {
+            throw!(TakerError::InvalidMortgageRate)
+        }
+        if self.service_fee_rate > 10000 {
+            throw!(TakerError::InvalidServiceFeeRate)
+        }
+        if self.max_loan_duration <= 0 {
+            throw!(TakerError::InvalidLoanDuration)
+        }
+        // the penalty is at most the outstanding principal and the auction never sells below the
+        // claim of the lender
+        if self.grace_period < 0
+            || self.grace_penalty_rate > 10000
+            || self.auction_duration < 0
+            || self.auction_start_rate < 10000
+        {
+            throw!(TakerError::InvalidLiquidationSettings)
+        }
+        self.interest_model.validate()?;
+
+        let jump_rate = match self.interest_model {
+            InterestCurve::Kinked(KinkedInterest { jump_rate, .. }) => jump_rate,
+            _ => 0,
+        };
+        if self.incentive > caps.max_incentive
+            || self.interest_rate > caps.max_interest_rate
+            || self.service_fee_rate > caps.max_service_fee_rate
+            || self.max_loan_duration > caps.max_loan_duration
+            || self.mortgage_rate > caps.max_mortgage_rate
+            || self.grace_penalty_rate > caps.max_grace_penalty_rate
+            || jump_rate > caps.max_jump_rate
+        {
+            throw!(TakerError::SettingAboveCap)
+        }
+    }
+}
+
+impl SettingsCaps {
+    // The bounds of the protocol
+    pub const UNBOUNDED: Self = Self {
+        max_incentive: u64::MAX,
+        max_interest_rate: u64::MAX,
+        max_service_fee_rate: 10000,
+        max_loan_duration: i64::MAX,
+        max_mortgage_rate: 10000,
+        max_grace_penalty_rate: 10000,
+        max_jump_rate: u16::MAX,
+    };
+
+    #[throws(TakerError)]
+    pub fn validate(&self) {
+        LoanSettings {
+            incentive: self.max_incentive,
+            interest_rate: self.max_interest_rate,
+            service_fee_rate: self.max_service_fee_rate,
+            max_loan_duration: self.max_loan_duration,
+            mortgage_rate: self.max_mortgage_rate,
+            grace_period: 0,
+            grace_penalty_rate: self.max_grace_penalty_rate,
+            auction_duration: 0,
+            auction_start_rate: 10000,
+            interest_model: InterestCurve::Simple,
+        }
+        .validate(&Self::UNBOUNDED)?
+    }
+
+    // Whether any of the caps is above the one of `caps`
+    pub fn loosens(&self, caps: &SettingsCaps) -> bool {
+        self.max_incentive > caps.max_incentive
+            || self.max_interest_rate > caps.max_interest_rate
+            || self.max_service_fee_rate > caps.max_service_fee_rate
+            || self.max_loan_duration > caps.max_loan_duration
+            || self.max_mortgage_rate > caps.max_mortgage_rate
+            || self.max_grace_penalty_rate > caps.max_grace_penalty_rate
+            || self.max_jump_rate > caps.max_jump_rate
+    }
+}
+
 impl DerivedAccountIdentifier for NFTPool {
     const SEED: &'static [u8] = b"TakerNFTPool";
 }
 
 impl NFTPool {
+    // The bits of `paused`. Cancelling bids and withdrawing refinance payoffs stay open, the lenders
+    // always get their DAI back
+    pub const PAUSE_DEPOSIT: u32 = 1 << 0;
+    pub const PAUSE_PLACE_BID: u32 = 1 << 1; // mint and collection bids
+    pub const PAUSE_BORROW: u32 = 1 << 2; // borrowing against bids and funding listings
+    pub const PAUSE_REPAY_PARTIAL: u32 = 1 << 3;
+    pub const PAUSE_EXTEND_LOAN: u32 = 1 << 4;
+    pub const PAUSE_AUCTION: u32 = 1 << 5; // starting auctions and buying their NFT
+    pub const PAUSE_LIQUIDATE: u32 = 1 << 6;
+    pub const PAUSE_WITHDRAW_LOCKED_ASSET: u32 = 1 << 7;
+    pub const PAUSE_CLOSE_DEPOSIT: u32 = 1 << 8;
+    pub const PAUSE_PLACE_LISTING: u32 = 1 << 9;
+    pub const PAUSE_REFINANCE: u32 = 1 << 10;
+    pub const PAUSE_SET_AUTO_REFINANCE: u32 = 1 << 11; // opting in only, opting out stays open
+    pub const PAUSE_COLLECT_FEES: u32 = 1 << 12;
+    pub const PAUSE_ALL: u32 = (1 << 13) - 1;
+
+    pub const FEE_VAULT_SEED: &'static [u8] = b"TakerFeeVault";
+
     #[throws(ProgramError)]
     pub fn new_checked<'info>(
         program_id: &Pubkey,
+        pool_id: &Pubkey,
         pool: &AccountInfo<'info>,
         pool_owner: &AccountInfo<'info>,
         tkr_mint: &CpiAccount<'info, Mint>,
@@ -24,16 +155,18 @@ impl NFTPool {
         rent: &Sysvar<'info, Rent>,
         system_program: &AccountInfo<'info>,
     ) -> ProgramAccount<'info, Self> {
-        let (_, bump) = NFTPool::get_address_with_bump(program_id);
-        NFTPool::verify_address(program_id, bump, &pool.key)?;
+        let (_, bump) = NFTPool::get_address_with_bump(program_id, pool_id);
+        NFTPool::verify_address(program_id, pool_id, bump, pool.key)?;
+        let fee_vault = NFTPool::get_fee_vault_address(program_id, pool.key);
 
         let instance = Self {
             bump_seed: bump,
+            pool_id: *pool_id,
             owner: *pool_owner.key,
             tkr_mint: *tkr_mint.to_account_info().key,
             tai_mint: *tai_mint.to_account_info().key,
             dai_mint: *dai_mint.to_account_info().key,
-            incentive: 100 * 10u64.pow(tkr_mint.decimals as u32) as u64,
+            incentive: 100 * 10u64.pow(tkr_mint.decimals as u32),
             max_loan_duration: 30 * 24 * 60 * 60, // 30 days
             // 5%
             service_fee_rate: 500,
@@ -41,9 +174,36 @@ impl NFTPool {
             interest_rate: 100,
             // 90%
             mortgage_rate: 9000,
+            grace_period: 0,
+            grace_penalty_rate: 0,
+            // no auction, the lender takes the NFT
+            auction_duration: 0,
+            // 150%
+            auction_start_rate: 15000,
+            interest_model: InterestCurve::Simple,
+            pending_owner: Pubkey::default(),
+            fee_recipient: *pool_owner.key,
+            settings_admin: *pool_owner.key,
+            pauser: *pool_owner.key,
+            paused: 0,
+            settings_caps: SettingsCaps::UNBOUNDED,
+            settings_delay: 0,
+            fee_vault,
+            fees_accrued: 0,
+            fees_collected: 0,
+            outstanding_amount: 0,
+            offered_amount: 0,
         };
 
-        let acc_size = 8 + instance
+        // room for the largest interest model
+        let largest_instance = Self {
+            interest_model: InterestCurve::Kinked(KinkedInterest {
+                kink: 0,
+                jump_rate: 0,
+            }),
+            ..instance.clone()
+        };
+        let acc_size = 8 + largest_instance
             .try_to_vec()
             .map_err(|_| ProgramError::Custom(1))?
             .len() as u64;
@@ -51,12 +211,12 @@ impl NFTPool {
         // allocate the space for the contract account
         utils::create_derived_account_with_seed(
             program_id, // The program ID of Taker Contract
-            &pool_owner,
-            &[Self::SEED, &[bump]],
-            &pool,
+            pool_owner,
+            &[Self::SEED, &pool_id.to_bytes(), &[bump]],
+            pool,
             acc_size,
-            &rent,
-            &system_program,
+            rent,
+            system_program,
         )?;
 
         // let the data borrow invalid after exiting the scope. Otherwise can cannot borrow it again in the ProgramAccount::try_from
@@ -95,6 +255,52 @@ impl NFTPool {
         Ok(())
     }
 
+    // A DAI account of the pool that holds the service fees apart from the DAI of the lenders
+    #[throws(ProgramError)]
+    pub fn create_fee_vault<'info>(
+        program_id: &Pubkey,
+        pool: &ProgramAccount<'info, Self>,
+        funder: &AccountInfo<'info>,
+        dai_mint: &CpiAccount<'info, Mint>,
+        fee_vault: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+        system_program: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) {
+        let pool_address = pool.to_account_info().key;
+        let (address, bump) = Self::get_fee_vault_address_with_bump(program_id, pool_address);
+        if &address != fee_vault.key {
+            throw!(TakerError::FeeVaultAddressNotCorrect);
+        }
+
+        utils::create_derived_token_account(
+            funder,
+            &[Self::FEE_VAULT_SEED, &pool_address.to_bytes(), &[bump]],
+            fee_vault,
+            dai_mint,
+            &pool.to_account_info(),
+            spl_program,
+            system_program,
+            rent,
+        )?;
+    }
+
+    #[throws(TakerError)]
+    pub fn ensure_fee_vault(&self, fee_vault: &Pubkey) {
+        if fee_vault != &self.fee_vault {
+            throw!(TakerError::FeeVaultAddressNotCorrect)
+        }
+    }
+
+    // Records the service fee paid into the fee vault
+    #[throws(TakerError)]
+    pub fn accrue_fee(&mut self, fee: u64) {
+        self.fees_accrued = self
+            .fees_accrued
+            .checked_add(fee)
+            .ok_or(TakerError::MathOverflow)?;
+    }
+
     pub fn ensure_user_token_account<'info>(
         user_wallet_account: &AccountInfo<'info>,
         mint: &CpiAccount<'info, Mint>,
@@ -120,35 +326,133 @@ impl NFTPool {
         Ok(())
     }
 
-    pub fn calculate_interest_and_fee(&self, borrowed_amount: u64, duration: i64) -> (u64, u64) {
-        let interest = borrowed_amount
-            .checked_mul(self.interest_rate)
-            .unwrap()
-            .checked_mul(duration.try_into().unwrap())
-            .unwrap()
-            .checked_div(SECONDS_PER_DAY)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
-        let fee = interest
-            .checked_mul(self.service_fee_rate)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
+    // Same as `ensure_user_token_account` but paid for by `funder`
+    pub fn ensure_token_account_funded_by<'info>(
+        user_wallet_account: &AccountInfo<'info>,
+        funder: &AccountInfo<'info>,
+        mint: &CpiAccount<'info, Mint>,
+        user_token_account: &AccountInfo<'info>,
+        ata_program: &AccountInfo<'info>,
+        spl_program: &AccountInfo<'info>,
+        system: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+    ) -> Result<()> {
+        if !utils::is_account_allocated(user_token_account) {
+            utils::create_associated_token_account(
+                user_wallet_account,
+                funder,
+                mint,
+                user_token_account,
+                ata_program,
+                spl_program,
+                system,
+                rent,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn loan_settings(&self) -> LoanSettings {
+        LoanSettings {
+            incentive: self.incentive,
+            interest_rate: self.interest_rate,
+            service_fee_rate: self.service_fee_rate,
+            max_loan_duration: self.max_loan_duration,
+            mortgage_rate: self.mortgage_rate,
+            grace_period: self.grace_period,
+            grace_penalty_rate: self.grace_penalty_rate,
+            auction_duration: self.auction_duration,
+            auction_start_rate: self.auction_start_rate,
+            interest_model: self.interest_model,
+        }
+    }
 
-        (interest, fee)
+    pub fn set_loan_settings(&mut self, settings: &LoanSettings) {
+        self.incentive = settings.incentive;
+        self.interest_rate = settings.interest_rate;
+        self.service_fee_rate = settings.service_fee_rate;
+        self.max_loan_duration = settings.max_loan_duration;
+        self.mortgage_rate = settings.mortgage_rate;
+        self.grace_period = settings.grace_period;
+        self.grace_penalty_rate = settings.grace_penalty_rate;
+        self.auction_duration = settings.auction_duration;
+        self.auction_start_rate = settings.auction_start_rate;
+        self.interest_model = settings.interest_model;
     }
-    pub fn get_address(program_id: &Pubkey) -> Pubkey {
-        Self::get_address_with_bump(program_id).0
+
+    #[throws(TakerError)]
+    pub fn ensure_not_paused(&self, flag: u32) {
+        if self.paused & flag != 0 {
+            throw!(TakerError::ProtocolPaused)
+        }
+    }
+
+    // The share of the DAI of the lenders that is lent out rather than still offered by the open bids, in bp
+    pub fn utilization(&self) -> u64 {
+        let supplied = self.outstanding_amount as u128 + self.offered_amount as u128;
+        if supplied == 0 {
+            return 0;
+        }
+        (self.outstanding_amount as u128 * 10000 / supplied) as u64
+    }
+
+    pub fn add_outstanding(&mut self, amount: u64) {
+        self.outstanding_amount = self.outstanding_amount.saturating_add(amount);
+    }
+
+    pub fn remove_outstanding(&mut self, amount: u64) {
+        self.outstanding_amount = self.outstanding_amount.saturating_sub(amount);
+    }
+
+    // A bid went from offering `before` to offering `after`
+    pub fn update_offered(&mut self, before: u64, after: u64) {
+        self.offered_amount = self
+            .offered_amount
+            .saturating_sub(before)
+            .saturating_add(after);
     }
 
-    pub(crate) fn get_address_with_bump(program_id: &Pubkey) -> (Pubkey, u8) {
-        Pubkey::find_program_address(&[Self::SEED], program_id)
+    // The terms of the bids that do not specify theirs
+    pub fn default_loan_terms(&self) -> LoanTerms {
+        LoanTerms {
+            interest_rate: self.interest_rate,
+            max_loan_duration: self.max_loan_duration,
+            mortgage_rate: self.mortgage_rate,
+            max_extension: 0, // extensions need the lender's signature
+        }
+    }
+
+    // An program derived account that stores the pool settings
+    // The address of the account is computed as follow:
+    // address = find_program_address([NFTPool::SEED, pool_id], program_id)
+    // so that one program can host multiple independent pools
+    pub fn get_address(program_id: &Pubkey, pool_id: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, pool_id).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, pool_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &pool_id.to_bytes()], program_id)
+    }
+
+    // address = find_program_address([NFTPool::FEE_VAULT_SEED, pool], program_id)
+    pub fn get_fee_vault_address(program_id: &Pubkey, pool: &Pubkey) -> Pubkey {
+        Self::get_fee_vault_address_with_bump(program_id, pool).0
+    }
+
+    pub(crate) fn get_fee_vault_address_with_bump(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::FEE_VAULT_SEED, &pool.to_bytes()], program_id)
     }
 
     #[throws(ProgramError)]
-    pub fn verify_address(program_id: &Pubkey, bump: u8, pool_address: &Pubkey) {
-        let addr = Pubkey::create_program_address(&[Self::SEED, &[bump]], program_id)?;
+    pub fn verify_address(program_id: &Pubkey, pool_id: &Pubkey, bump: u8, pool_address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &pool_id.to_bytes(), &[bump]],
+            program_id,
+        )?;
 
         if &addr != pool_address {
             throw!(TakerError::ContractAddressNotCorrect);
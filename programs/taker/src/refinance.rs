@@ -0,0 +1,269 @@
+use crate::{utils, AutoRefinance, DerivedAccountIdentifier, RefinancePayoff, TakerError};
+use anchor_lang::prelude::*;
+use fehler::{throw, throws};
+
+impl DerivedAccountIdentifier for AutoRefinance {
+    const SEED: &'static [u8] = b"TakerAutoRefinance";
+}
+
+impl DerivedAccountIdentifier for RefinancePayoff {
+    const SEED: &'static [u8] = b"TakerRefinancePayoff";
+}
+
+impl AutoRefinance {
+    pub(crate) fn account_size() -> usize {
+        let instance = AutoRefinance {
+            deposit: Pubkey::default(),
+            borrower: Pubkey::default(),
+        };
+        8 + instance.try_to_vec().unwrap().len()
+    }
+
+    // The borrower opts in, the account exists as long as the opt-in holds
+    #[throws(ProgramError)]
+    pub fn enable<'info>(
+        program_id: &Pubkey,
+        deposit: &Pubkey,
+        borrower: &AccountInfo<'info>,
+        auto_refinance_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system_program: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, deposit);
+        Self::verify_address(program_id, deposit, bump, auto_refinance_account.key)?;
+
+        if utils::is_account_allocated(auto_refinance_account) {
+            return;
+        }
+
+        let instance = AutoRefinance {
+            deposit: *deposit,
+            borrower: *borrower.key,
+        };
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            borrower,
+            &[Self::SEED, &deposit.to_bytes(), &[bump]],
+            auto_refinance_account,
+            Self::account_size() as u64,
+            rent,
+            system_program,
+        )?;
+
+        let mut data = auto_refinance_account.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut **data);
+        instance.try_serialize(&mut cursor)?;
+    }
+
+    // Withdraws the opt-in and gives the rent back to the borrower
+    #[throws(ProgramError)]
+    pub fn disable<'info>(
+        program_id: &Pubkey,
+        deposit: &Pubkey,
+        borrower: &AccountInfo<'info>,
+        auto_refinance_account: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, deposit);
+        Self::verify_address(program_id, deposit, bump, auto_refinance_account.key)?;
+
+        if !utils::is_account_allocated(auto_refinance_account) {
+            return;
+        }
+
+        let auto_refinance: ProgramAccount<Self> =
+            ProgramAccount::try_from(auto_refinance_account)?;
+        if &auto_refinance.borrower != borrower.key {
+            throw!(TakerError::BorrowerMismatch);
+        }
+
+        anchor_lang::AccountsClose::close(&auto_refinance, borrower.clone())?;
+    }
+
+    // Whether the borrower of the deposit has opted in
+    #[throws(ProgramError)]
+    pub fn is_enabled(
+        program_id: &Pubkey,
+        deposit: &Pubkey,
+        borrower: &Pubkey,
+        auto_refinance_account: &AccountInfo,
+    ) -> bool {
+        let (_, bump) = Self::get_address_with_bump(program_id, deposit);
+        Self::verify_address(program_id, deposit, bump, auto_refinance_account.key)?;
+
+        if !utils::is_account_allocated(auto_refinance_account) {
+            return false;
+        }
+
+        let auto_refinance: ProgramAccount<Self> =
+            ProgramAccount::try_from(auto_refinance_account)?;
+        &auto_refinance.borrower == borrower
+    }
+
+    pub fn get_address(program_id: &Pubkey, deposit: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, deposit).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, deposit: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &deposit.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, deposit: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr = Pubkey::create_program_address(
+            &[Self::SEED, &deposit.to_bytes(), &[bump]],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(TakerError::AutoRefinanceAddressNotCorrect);
+        }
+    }
+}
+
+impl RefinancePayoff {
+    pub(crate) fn account_size() -> usize {
+        let instance = RefinancePayoff {
+            lender: Pubkey::default(),
+            tai_required_to_unlock: 0,
+            lender_withdrawable: 0,
+        };
+        8 + instance.try_to_vec().unwrap().len()
+    }
+
+    // Adds the paid off loan to the payoff of the lender on the deposit, the lender can be refinanced
+    // out of the same deposit again before withdrawing
+    #[throws(ProgramError)]
+    pub fn credit<'info>(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        deposit: &Pubkey,
+        lender: &Pubkey,
+        tai_required_to_unlock: u64,
+        lender_withdrawable: u64,
+        payer: &AccountInfo<'info>,
+        payoff_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system_program: &AccountInfo<'info>,
+    ) {
+        let (_, bump) = Self::get_address_with_bump(program_id, pool, deposit, lender);
+        Self::verify_address(program_id, pool, deposit, lender, bump, payoff_account.key)?;
+
+        let mut instance = if utils::is_account_allocated(payoff_account) {
+            let payoff: ProgramAccount<Self> = ProgramAccount::try_from(payoff_account)?;
+            payoff.into_inner()
+        } else {
+            utils::create_derived_account_with_seed(
+                program_id,
+                payer,
+                &[
+                    Self::SEED,
+                    &pool.to_bytes(),
+                    &deposit.to_bytes(),
+                    &lender.to_bytes(),
+                    &[bump],
+                ],
+                payoff_account,
+                Self::account_size() as u64,
+                rent,
+                system_program,
+            )?;
+
+            RefinancePayoff {
+                lender: *lender,
+                tai_required_to_unlock: 0,
+                lender_withdrawable: 0,
+            }
+        };
+
+        instance.tai_required_to_unlock = instance
+            .tai_required_to_unlock
+            .checked_add(tai_required_to_unlock)
+            .ok_or(TakerError::MathOverflow)?;
+        instance.lender_withdrawable = instance
+            .lender_withdrawable
+            .checked_add(lender_withdrawable)
+            .ok_or(TakerError::MathOverflow)?;
+
+        let mut data = payoff_account.try_borrow_mut_data()?;
+        let mut cursor = std::io::Cursor::new(&mut **data);
+        instance.try_serialize(&mut cursor)?;
+    }
+
+    // The payoff at `payoff_account`, whose address follows the lender it records
+    #[throws(ProgramError)]
+    pub fn load<'info>(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        deposit: &Pubkey,
+        payoff_account: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        if !utils::is_account_allocated(payoff_account) {
+            throw!(TakerError::LoanNotRepayed);
+        }
+
+        let payoff: ProgramAccount<Self> = ProgramAccount::try_from(payoff_account)?;
+        let (_, bump) = Self::get_address_with_bump(program_id, pool, deposit, &payoff.lender);
+        Self::verify_address(
+            program_id,
+            pool,
+            deposit,
+            &payoff.lender,
+            bump,
+            payoff_account.key,
+        )?;
+
+        payoff
+    }
+
+    pub fn get_address(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        deposit: &Pubkey,
+        lender: &Pubkey,
+    ) -> Pubkey {
+        Self::get_address_with_bump(program_id, pool, deposit, lender).0
+    }
+
+    pub(crate) fn get_address_with_bump(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        deposit: &Pubkey,
+        lender: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                Self::SEED,
+                &pool.to_bytes(),
+                &deposit.to_bytes(),
+                &lender.to_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        deposit: &Pubkey,
+        lender: &Pubkey,
+        bump: u8,
+        address: &Pubkey,
+    ) {
+        let addr = Pubkey::create_program_address(
+            &[
+                Self::SEED,
+                &pool.to_bytes(),
+                &deposit.to_bytes(),
+                &lender.to_bytes(),
+                &[bump],
+            ],
+            program_id,
+        )?;
+
+        if &addr != address {
+            throw!(TakerError::RefinancePayoffAddressNotCorrect);
+        }
+    }
+}
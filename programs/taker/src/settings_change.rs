@@ -0,0 +1,158 @@
+use crate::{
+    utils, DerivedAccountIdentifier, InterestCurve, KinkedInterest, LoanSettings, SettingsCaps,
+    SettingsChange, TakerError,
+};
+use anchor_lang::prelude::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+use fehler::{throw, throws};
+use solana_program::clock::UnixTimestamp;
+
+// What a `SettingsChange` applies once the delay is over
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum QueuedChange {
+    Settings(LoanSettings), // proposed by the settings admin
+    Caps {
+        // proposed by the owner, loosening the caps or shortening the delay
+        settings_caps: SettingsCaps,
+        settings_delay: i64,
+    },
+}
+
+impl DerivedAccountIdentifier for SettingsChange {
+    const SEED: &'static [u8] = b"TakerSettingsChange";
+}
+
+impl SettingsChange {
+    pub fn proposer(&self) -> Pubkey {
+        self.proposer
+    }
+
+    pub fn change(&self) -> QueuedChange {
+        self.change
+    }
+
+    pub fn effective_at(&self) -> UnixTimestamp {
+        self.effective_at
+    }
+
+    pub(crate) fn account_size() -> usize {
+        // room for the largest interest model
+        let settings = QueuedChange::Settings(LoanSettings {
+            interest_model: InterestCurve::Kinked(KinkedInterest {
+                kink: 0,
+                jump_rate: 0,
+            }),
+            ..LoanSettings::default()
+        });
+        let caps = QueuedChange::Caps {
+            settings_caps: SettingsCaps::UNBOUNDED,
+            settings_delay: 0,
+        };
+
+        [settings, caps]
+            .iter()
+            .map(|change| {
+                let instance = SettingsChange {
+                    proposer: Pubkey::default(),
+                    change: *change,
+                    effective_at: 0,
+                };
+                8 + instance.try_to_vec().unwrap().len()
+            })
+            .max()
+            .unwrap()
+    }
+
+    // Queues the change, a pool has at most one change queued at a time
+    #[throws(ProgramError)]
+    pub fn propose<'info>(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        change: QueuedChange,
+        effective_at: UnixTimestamp,
+        proposer: &AccountInfo<'info>,
+        settings_change_account: &AccountInfo<'info>,
+        rent: &Sysvar<'info, Rent>,
+        system_program: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, pool);
+        Self::verify_address(program_id, pool, bump, settings_change_account.key)?;
+
+        // cancel or execute the queued change first
+        if utils::is_account_allocated(settings_change_account) {
+            throw!(TakerError::SettingsChangeAlreadyQueued);
+        }
+
+        let instance = SettingsChange {
+            proposer: *proposer.key,
+            change,
+            effective_at,
+        };
+
+        utils::create_derived_account_with_seed(
+            program_id,
+            proposer,
+            &[Self::SEED, &pool.to_bytes(), &[bump]],
+            settings_change_account,
+            Self::account_size() as u64,
+            rent,
+            system_program,
+        )?;
+
+        {
+            let mut data = settings_change_account.try_borrow_mut_data()?;
+            let mut cursor = std::io::Cursor::new(&mut **data);
+            instance.try_serialize(&mut cursor)?;
+        }
+
+        ProgramAccount::try_from(settings_change_account)?
+    }
+
+    // The change queued for `pool`
+    #[throws(ProgramError)]
+    pub fn load<'info>(
+        program_id: &Pubkey,
+        pool: &Pubkey,
+        settings_change_account: &AccountInfo<'info>,
+    ) -> ProgramAccount<'info, Self> {
+        let (_, bump) = Self::get_address_with_bump(program_id, pool);
+        Self::verify_address(program_id, pool, bump, settings_change_account.key)?;
+
+        if !utils::is_account_allocated(settings_change_account) {
+            throw!(TakerError::NoSettingsChangeQueued);
+        }
+
+        ProgramAccount::try_from(settings_change_account)?
+    }
+
+    // Deallocates the executed or cancelled change and gives the rent back to the proposer
+    #[throws(ProgramError)]
+    pub fn close<'info>(
+        settings_change: &ProgramAccount<'info, Self>,
+        proposer: &AccountInfo<'info>,
+    ) {
+        if &settings_change.proposer != proposer.key {
+            throw!(TakerError::ProposerMismatch);
+        }
+
+        anchor_lang::AccountsClose::close(settings_change, proposer.clone())?;
+    }
+
+    pub fn get_address(program_id: &Pubkey, pool: &Pubkey) -> Pubkey {
+        Self::get_address_with_bump(program_id, pool).0
+    }
+
+    pub(crate) fn get_address_with_bump(program_id: &Pubkey, pool: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, &pool.to_bytes()], program_id)
+    }
+
+    #[throws(ProgramError)]
+    pub fn verify_address(program_id: &Pubkey, pool: &Pubkey, bump: u8, address: &Pubkey) {
+        let addr =
+            Pubkey::create_program_address(&[Self::SEED, &pool.to_bytes(), &[bump]], program_id)?;
+
+        if &addr != address {
+            throw!(TakerError::SettingsChangeAddressNotCorrect);
+        }
+    }
+}
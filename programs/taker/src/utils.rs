@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::Mint;
 use fehler::throws;
-use solana_program::{instruction::Instruction, program::invoke_signed, system_program};
+use solana_program::{
+    instruction::Instruction, program::invoke_signed, program_pack::Pack, system_program,
+};
 use solana_program::{program::invoke, system_instruction};
 
 #[throws(ProgramError)]
@@ -29,6 +31,74 @@ pub fn create_derived_account_with_seed<'info>(
     )?;
 }
 
+// A token account at a program derived address, owned by the token program
+#[throws(ProgramError)]
+pub fn create_derived_token_account<'info>(
+    funder: &AccountInfo<'info>,
+    seeds_with_bump: &[&[u8]],
+    token_account: &AccountInfo<'info>,
+    mint: &CpiAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    spl_program: &AccountInfo<'info>,
+    system: &AccountInfo<'info>,
+    rent: &Sysvar<'info, Rent>,
+) {
+    // signed with the seeds of the taker program, but owned by the token program
+    create_derived_account_with_seed(
+        &spl_token::id(),
+        funder,
+        seeds_with_bump,
+        token_account,
+        spl_token::state::Account::LEN as u64,
+        rent,
+        system,
+    )?;
+
+    anchor_spl::token::initialize_account(CpiContext::new(
+        spl_program.clone(),
+        anchor_spl::token::InitializeAccount {
+            account: token_account.clone(),
+            mint: mint.to_account_info(),
+            authority: authority.clone(),
+            rent: rent.to_account_info(),
+        },
+    ))?;
+}
+
+// A mint at a program derived address without freeze authority
+#[throws(ProgramError)]
+pub fn create_derived_mint<'info>(
+    funder: &AccountInfo<'info>,
+    seeds_with_bump: &[&[u8]],
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    decimals: u8,
+    spl_program: &AccountInfo<'info>,
+    system: &AccountInfo<'info>,
+    rent: &Sysvar<'info, Rent>,
+) {
+    create_derived_account_with_seed(
+        &spl_token::id(),
+        funder,
+        seeds_with_bump,
+        mint,
+        spl_token::state::Mint::LEN as u64,
+        rent,
+        system,
+    )?;
+
+    invoke(
+        &spl_token::instruction::initialize_mint(
+            spl_program.key,
+            mint.key,
+            authority.key,
+            None,
+            decimals,
+        )?,
+        &[mint.clone(), rent.to_account_info(), spl_program.clone()],
+    )?;
+}
+
 #[throws(ProgramError)]
 pub fn create_associated_token_account<'info>(
     wallet: &AccountInfo<'info>,
@@ -80,5 +150,5 @@ pub fn create_associated_token_account<'info>(
 
 pub fn is_account_allocated(acc: &AccountInfo) -> bool {
     //    if the account has non zero lamports or has data stored or has the owner != system_program, then this account is already allocated
-    acc.lamports() != 0 || !acc.data_is_empty() || !system_program::check_id(&acc.owner)
+    acc.lamports() != 0 || !acc.data_is_empty() || !system_program::check_id(acc.owner)
 }
@@ -0,0 +1,280 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, Borrower, TestEnv, UNIT};
+use solana_sdk::signature::{Keypair, Signer};
+use taker::{DepositState, LoanRepayedState, LoanTerms, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+// A one hour loan of BID_PRICE
+async fn start_loan(env: &mut TestEnv) -> (Borrower, Keypair) {
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_loan_settings(None, None, None, Some(SECONDS_PER_HOUR), None)
+        .await
+        .unwrap();
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    (borrower, lender)
+}
+
+#[tokio::test]
+async fn repay_during_the_grace_period() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    env.change_liquidation_settings(Some(2 * SECONDS_PER_HOUR), Some(500), None, None)
+        .await
+        .unwrap();
+    let (borrower, lender) = start_loan(&mut env).await;
+    let wallet = borrower.wallet.pubkey();
+    let loan = env.active_loan(&borrower).await;
+    assert_eq!(loan.grace_period, 2 * SECONDS_PER_HOUR);
+    assert_eq!(loan.grace_penalty_rate, 500);
+
+    // the loan keeps the grace settings it started with
+    env.change_liquidation_settings(Some(0), Some(10000), None, None)
+        .await
+        .unwrap();
+
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+
+    // expired, but still in the grace period
+    assert_eq!(
+        env.liquidate(&borrower, &lender, false).await.unwrap_err(),
+        taker_error(TakerError::LoanNotExpired)
+    );
+
+    let (interest, fee) = loan
+        .calculate_interest_and_fee(env.unix_timestamp().await - loan.started_at)
+        .unwrap();
+    let penalty = loan.borrowed_amount * 500 / 10000;
+    env.mint_to(&dai_mint, &wallet, interest + penalty).await;
+
+    env.repay(&borrower).await.unwrap();
+
+    assert_eq!(env.balance(&wallet, &borrower.nft_mint).await, 1);
+    assert_eq!(env.balance(&wallet, &dai_mint).await, 0);
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanRepayed(LoanRepayedState {
+            tai_required_to_unlock: loan.borrowed_amount,
+            lender_withdrawable: BID_PRICE + interest - fee + penalty,
+            lender: lender.pubkey(),
+        })
+    );
+}
+
+#[tokio::test]
+async fn sell_the_nft_at_auction() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    env.change_liquidation_settings(None, None, Some(10 * SECONDS_PER_HOUR), Some(20000))
+        .await
+        .unwrap();
+    let (borrower, lender) = start_loan(&mut env).await;
+    let wallet = borrower.wallet.pubkey();
+    let loan = env.active_loan(&borrower).await;
+    assert_eq!(
+        (loan.auction_duration, loan.auction_start_rate),
+        (10 * SECONDS_PER_HOUR, 20000)
+    );
+
+    // the loan keeps the auction settings it started with
+    env.change_liquidation_settings(None, None, Some(0), Some(10000))
+        .await
+        .unwrap();
+
+    let buyer = env.new_wallet().await;
+    env.mint_to(&dai_mint, &buyer.pubkey(), 2 * BID_PRICE).await;
+
+    // the auction cannot start before the loan expires
+    assert_eq!(
+        env.start_auction(&borrower).await.unwrap_err(),
+        taker_error(TakerError::LoanNotExpired)
+    );
+
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+
+    assert_eq!(
+        env.liquidate(&borrower, &lender, false).await.unwrap_err(),
+        taker_error(TakerError::AuctionRequired)
+    );
+
+    env.start_auction(&borrower).await.unwrap();
+
+    let (claim, fee) = loan.claim().unwrap();
+    let auction = match env.deposit_state(&borrower).await {
+        DepositState::LoanAuctioned(auction) => auction,
+        state => panic!("unexpected state {:?}", state),
+    };
+    assert_eq!(auction.claim, claim);
+    assert_eq!(auction.start_price, claim * 2);
+    assert_eq!(auction.ended_at, auction.started_at + 10 * SECONDS_PER_HOUR);
+
+    // the price falls towards the claim of the lender
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    let price = auction.price_at(env.unix_timestamp().await);
+    assert!(price < auction.start_price && price > claim);
+
+    assert_eq!(
+        env.buy_collateral(&borrower, &buyer, price - 1)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::AuctionPriceTooHigh)
+    );
+    env.skip_slots().await;
+    env.buy_collateral(&borrower, &buyer, price).await.unwrap();
+
+    // skipping slots moved the clock, so the price kept falling
+    let paid = 2 * BID_PRICE - env.balance(&buyer.pubkey(), &dai_mint).await;
+    assert!(paid <= price && paid > claim);
+
+    assert_eq!(env.balance(&buyer.pubkey(), &borrower.nft_mint).await, 1);
+    assert_eq!(
+        env.balance(&wallet, &dai_mint).await,
+        loan.borrowed_amount + paid - claim
+    );
+    assert_eq!(env.fee_vault_balance().await, fee);
+
+    // the lender is paid like for a repayed loan
+    env.withdraw_locked_asset(&borrower, &lender, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - BID_PRICE + loan.pool_holdings() + claim - fee
+    );
+}
+
+#[tokio::test]
+async fn unsold_nft_goes_to_the_lender() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    env.change_liquidation_settings(None, None, Some(SECONDS_PER_HOUR), None)
+        .await
+        .unwrap();
+    let (borrower, lender) = start_loan(&mut env).await;
+    let loan = env.active_loan(&borrower).await;
+
+    let buyer = env.new_wallet().await;
+    env.mint_to(&dai_mint, &buyer.pubkey(), 2 * BID_PRICE).await;
+
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+    env.start_auction(&borrower).await.unwrap();
+
+    assert_eq!(
+        env.liquidate(&borrower, &lender, false).await.unwrap_err(),
+        taker_error(TakerError::AuctionNotFinished)
+    );
+
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+
+    assert_eq!(
+        env.buy_collateral(&borrower, &buyer, 2 * BID_PRICE)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::AuctionNotRunning)
+    );
+
+    env.liquidate(&borrower, &lender, false).await.unwrap();
+
+    let (_, fee) = loan.claim().unwrap();
+    assert_eq!(env.balance(&lender.pubkey(), &borrower.nft_mint).await, 1);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - BID_PRICE + loan.pool_holdings() - fee
+    );
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanLiquidated
+    );
+}
+
+#[tokio::test]
+async fn invalid_liquidation_settings_are_rejected() {
+    let mut env = TestEnv::new().await;
+
+    for (grace_period, grace_penalty_rate, auction_duration, auction_start_rate) in &[
+        (Some(-1), None, None, None),
+        (None, Some(10001), None, None),
+        (None, None, Some(-1), None),
+        (None, None, None, Some(9999)),
+    ] {
+        env.skip_slots().await;
+        assert_eq!(
+            env.change_liquidation_settings(
+                *grace_period,
+                *grace_penalty_rate,
+                *auction_duration,
+                *auction_start_rate
+            )
+            .await
+            .unwrap_err(),
+            taker_error(TakerError::InvalidLiquidationSettings)
+        );
+    }
+}
+
+#[tokio::test]
+async fn liquidation_fee_is_capped_at_the_pool_holdings() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    // the whole bid is lent, the pool holds nothing for the lender to take the fee from
+    let terms = LoanTerms {
+        interest_rate: 2000,
+        max_loan_duration: SECONDS_PER_HOUR,
+        mortgage_rate: 10000,
+        max_extension: 0,
+    };
+    let mut loans = vec![];
+    for auction_duration in &[0, SECONDS_PER_HOUR] {
+        env.change_liquidation_settings(None, None, Some(*auction_duration), None)
+            .await
+            .unwrap();
+        let borrower = env.new_borrower().await;
+        let lender = env.new_lender(LENDER_DAI).await;
+        env.deposit_nft(&borrower).await.unwrap();
+        env.place_bid_with_terms(&lender, &borrower.nft_mint, BID_PRICE, 1, Some(terms))
+            .await
+            .unwrap();
+        env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+            .await
+            .unwrap();
+
+        let loan = env.active_loan(&borrower).await;
+        let (_, fee) = loan.claim().unwrap();
+        assert!(fee > loan.pool_holdings());
+        loans.push((borrower, lender));
+    }
+
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+    env.start_auction(&loans[1].0).await.unwrap();
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+
+    // the lender takes the NFT of the loan straight away and the unsold one after its auction
+    for (borrower, lender) in &loans {
+        env.liquidate(borrower, lender, false).await.unwrap();
+
+        assert_eq!(env.balance(&lender.pubkey(), &borrower.nft_mint).await, 1);
+        assert_eq!(
+            env.balance(&lender.pubkey(), &dai_mint).await,
+            LENDER_DAI - BID_PRICE
+        );
+    }
+    assert_eq!(env.fee_vault_balance().await, 0);
+}
@@ -0,0 +1,108 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+use taker::{DepositState, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn loans_stay_within_the_bid_limits() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    assert_eq!(
+        env.place_bid_with_limits(
+            &lender,
+            &borrower.nft_mint,
+            BID_PRICE,
+            1,
+            None,
+            2 * BID_PRICE
+        )
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::InvalidBidLimits)
+    );
+    env.place_bid_with_limits(
+        &lender,
+        &borrower.nft_mint,
+        BID_PRICE,
+        1,
+        None,
+        BID_PRICE / 2,
+    )
+    .await
+    .unwrap();
+
+    let bid = env.bid_account(&borrower.nft_mint, &lender.pubkey()).await;
+    assert_eq!((bid.expires_at, bid.min_amount), (0, BID_PRICE / 2));
+
+    // no dust loans against the bid
+    assert_eq!(
+        env.borrow(&borrower, &lender.pubkey(), BID_PRICE / 4)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::BorrowBelowBidMinimum)
+    );
+    assert_eq!(
+        env.borrow(&borrower, &lender.pubkey(), 2 * BID_PRICE)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::NFTBorrowExceedBidAmount)
+    );
+
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE / 2)
+        .await
+        .unwrap();
+    assert_eq!(env.active_loan(&borrower).await.total_amount, BID_PRICE / 2);
+}
+
+#[tokio::test]
+async fn expired_bid_is_rejected() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    let expires_at = env.unix_timestamp().await + SECONDS_PER_HOUR;
+    env.place_bid_with_limits(
+        &lender,
+        &borrower.nft_mint,
+        BID_PRICE,
+        1,
+        Some(expires_at),
+        0,
+    )
+    .await
+    .unwrap();
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    assert_eq!(
+        env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::BidExpired)
+    );
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::PendingLoan
+    );
+
+    // placing the bid again renews it
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.skip_slots().await;
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{clone_keypair, replace_account, taker_error, Borrower, TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::get_associated_token_address;
+use taker::{DepositState, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+
+#[tokio::test]
+async fn bid_of_another_lender_is_rejected() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let other_lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.place_bid(&other_lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+
+    // the bid of one lender is paired with the DAI approval of the other
+    let bid = env.bid_address(&borrower.nft_mint, &lender.pubkey());
+    assert_eq!(
+        env.borrow_against(&borrower, &other_lender.pubkey(), &bid, BID_PRICE, vec![])
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::BidLenderMismatch)
+    );
+
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::PendingLoan
+    );
+    assert_eq!(env.balance(&lender.pubkey(), &dai_mint).await, LENDER_DAI);
+    assert_eq!(
+        env.balance(&other_lender.pubkey(), &dai_mint).await,
+        LENDER_DAI
+    );
+}
+
+#[tokio::test]
+async fn deposit_of_another_nft_is_rejected() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    let other_nft = env.new_borrower().await.nft_mint;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &other_nft, BID_PRICE, 1)
+        .await
+        .unwrap();
+
+    // the deposit of the borrower's NFT is used to borrow against the bid on another NFT
+    let mismatched = Borrower {
+        wallet: clone_keypair(&borrower.wallet),
+        nft_mint: other_nft,
+        deposit_id: borrower.deposit_id,
+        deposit: borrower.deposit,
+    };
+    assert_eq!(
+        env.borrow(&mismatched, &lender.pubkey(), BID_PRICE)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::DepositMintMismatch)
+    );
+
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::PendingLoan
+    );
+    assert_eq!(env.bid_account(&other_nft, &lender.pubkey()).await.qty, 1);
+}
+
+#[tokio::test]
+async fn dai_account_of_another_lender_is_not_the_pool_account() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let victim = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.place_bid(&victim, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+
+    // the pool is the delegate of the victim's DAI, which would pay the borrower
+    let bid = env.bid_address(&borrower.nft_mint, &lender.pubkey());
+    let mut ix = env.borrow_instruction(&borrower, &lender.pubkey(), &bid, BID_PRICE, vec![]);
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&env.pool, &dai_mint),
+        &get_associated_token_address(&victim.pubkey(), &dai_mint),
+    );
+    assert_eq!(
+        env.process(&[ix], &[&borrower.wallet]).await.unwrap_err(),
+        taker_error(TakerError::PoolAccountMismatch)
+    );
+
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::PendingLoan
+    );
+    assert_eq!(env.balance(&victim.pubkey(), &dai_mint).await, LENDER_DAI);
+}
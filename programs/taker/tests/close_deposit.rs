@@ -0,0 +1,102 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, TestEnv, UNIT};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use taker::TakerError;
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+
+#[tokio::test]
+async fn close_a_withdrawn_deposit() {
+    let mut env = TestEnv::new().await;
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+
+    env.deposit_nft(&borrower).await.unwrap();
+    let rent = env.account(&borrower.deposit).await.unwrap().lamports;
+
+    // the NFT is still in the pool
+    assert_eq!(
+        env.close_deposit(&borrower.deposit, &wallet)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::DepositNotClosable)
+    );
+
+    env.withdraw_nft(&borrower, false).await.unwrap();
+    let lamports = env.account(&wallet).await.unwrap().lamports;
+
+    // the rent can only go back to the borrower
+    assert_eq!(
+        env.close_deposit(&borrower.deposit, &Pubkey::new_unique())
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::BorrowerMismatch)
+    );
+
+    env.skip_slots().await;
+    env.close_deposit(&borrower.deposit, &wallet).await.unwrap();
+
+    assert!(env.account(&borrower.deposit).await.is_none());
+    assert_eq!(
+        env.account(&wallet).await.unwrap().lamports,
+        lamports + rent
+    );
+}
+
+#[tokio::test]
+async fn withdraw_nft_and_close() {
+    let mut env = TestEnv::new().await;
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+
+    env.deposit_nft(&borrower).await.unwrap();
+    let rent = env.account(&borrower.deposit).await.unwrap().lamports;
+    let lamports = env.account(&wallet).await.unwrap().lamports;
+
+    env.withdraw_nft(&borrower, true).await.unwrap();
+
+    assert!(env.account(&borrower.deposit).await.is_none());
+    assert_eq!(
+        env.account(&wallet).await.unwrap().lamports,
+        lamports + rent
+    );
+    assert_eq!(env.balance(&wallet, &borrower.nft_mint).await, 1);
+
+    // the deposit id can be used again
+    env.deposit_nft(&borrower).await.unwrap();
+}
+
+#[tokio::test]
+async fn withdraw_locked_asset_and_close() {
+    let mut env = TestEnv::new().await;
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    env.repay(&borrower).await.unwrap();
+
+    let rent = env.account(&borrower.deposit).await.unwrap().lamports;
+    let lamports = env.account(&wallet).await.unwrap().lamports;
+
+    // the lender settles the loan and the borrower gets the rent back
+    env.withdraw_locked_asset(&borrower, &lender, true)
+        .await
+        .unwrap();
+
+    assert!(env.account(&borrower.deposit).await.is_none());
+    assert_eq!(
+        env.account(&wallet).await.unwrap().lamports,
+        lamports + rent
+    );
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, TestEnv, UNIT};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use taker::{allowlist, BidTarget, DepositState, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+
+#[tokio::test]
+async fn borrow_against_a_collection_bid() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let mut borrowers = vec![];
+    for _ in 0..3 {
+        let borrower = env.new_borrower().await;
+        env.deposit_nft(&borrower).await.unwrap();
+        borrowers.push(borrower);
+    }
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    // the collection also has NFTs that are not deposited
+    let mut collection: Vec<_> = borrowers.iter().map(|b| b.nft_mint).collect();
+    collection.extend((0..4).map(|_| Pubkey::new_unique()));
+    let root = allowlist::root(&collection);
+
+    env.place_collection_bid(&lender, root, BID_PRICE, 2)
+        .await
+        .unwrap();
+    let bid = env.collection_bid_address(&root, &lender.pubkey());
+    let content: taker::NFTBid = env.anchor_account(&bid).await;
+    assert_eq!(content.target, BidTarget::Collection(root));
+    assert_eq!(content.qty, 2);
+
+    // two different NFTs are borrowed against the same bid
+    for borrower in &borrowers[..2] {
+        let proof = allowlist::proof(&collection, &borrower.nft_mint).unwrap();
+        env.borrow_against(borrower, &lender.pubkey(), &bid, BID_PRICE, proof)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            env.deposit_state(borrower).await,
+            DepositState::LoanActive(loan) if loan.lender == lender.pubkey()
+        ));
+    }
+
+    let content: taker::NFTBid = env.anchor_account(&bid).await;
+    assert_eq!(content.qty, 0);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - 2 * BID_PRICE
+    );
+
+    // the bid is filled, which also resets its price
+    let proof = allowlist::proof(&collection, &borrowers[2].nft_mint).unwrap();
+    assert_eq!(
+        env.borrow_against(&borrowers[2], &lender.pubkey(), &bid, BID_PRICE, proof)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::NFTBorrowExceedBidAmount)
+    );
+}
+
+#[tokio::test]
+async fn nft_outside_the_collection_is_rejected() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    env.deposit_nft(&borrower).await.unwrap();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    let collection: Vec<_> = (0..5).map(|_| Pubkey::new_unique()).collect();
+    let root = allowlist::root(&collection);
+    env.place_collection_bid(&lender, root, BID_PRICE, 1)
+        .await
+        .unwrap();
+    let bid = env.collection_bid_address(&root, &lender.pubkey());
+
+    // neither without a proof nor with the proof of another NFT
+    let other_proof = allowlist::proof(&collection, &collection[0]).unwrap();
+    for proof in vec![vec![], other_proof] {
+        assert_eq!(
+            env.borrow_against(&borrower, &lender.pubkey(), &bid, BID_PRICE, proof)
+                .await
+                .unwrap_err(),
+            taker_error(TakerError::NFTNotCoveredByBid)
+        );
+    }
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::PendingLoan
+    );
+
+    // a mint bid only covers its own NFT
+    let other = env.new_borrower().await;
+    env.place_bid(&lender, &other.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    let bid = env.bid_address(&other.nft_mint, &lender.pubkey());
+    assert_eq!(
+        env.borrow_against(&borrower, &lender.pubkey(), &bid, BID_PRICE, vec![])
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::NFTNotCoveredByBid)
+    );
+}
+
+#[tokio::test]
+async fn cancel_a_collection_bid() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    env.deposit_nft(&borrower).await.unwrap();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    let collection = vec![borrower.nft_mint, Pubkey::new_unique()];
+    let root = allowlist::root(&collection);
+    env.place_collection_bid(&lender, root, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.cancel_collection_bid(&lender, root).await.unwrap();
+
+    let bid = env.collection_bid_address(&root, &lender.pubkey());
+    let content: taker::NFTBid = env.anchor_account(&bid).await;
+    assert_eq!((content.price, content.qty), (0, 0));
+
+    let proof = allowlist::proof(&collection, &borrower.nft_mint).unwrap();
+    assert!(env
+        .borrow_against(&borrower, &lender.pubkey(), &bid, BID_PRICE, proof)
+        .await
+        .is_err());
+}
@@ -0,0 +1,1486 @@
+#![allow(dead_code)]
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use solana_program::{
+    clock::Clock, program_pack::Pack, system_instruction, system_program, sysvar,
+};
+use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
+use taker::{
+    AutoRefinance, DepositState, InterestCurve, LoanActiveState, LoanTerms, NFTBid, NFTDeposit,
+    NFTListing, NFTPool, RefinancePayoff, SettingsCaps, SettingsChange, TakerError,
+    ERROR_CODE_OFFSET,
+};
+
+pub const DECIMALS: u8 = 9;
+pub const UNIT: u64 = 1_000_000_000;
+
+// An offline Taker deployment: the program, the TKR/TAI/DAI mints and an initialized pool
+pub struct TestEnv {
+    pub ctx: ProgramTestContext,
+    pub program_id: Pubkey,
+    pub mint_authority: Keypair,
+
+    pub owner: Keypair,
+    pub settings_admin: Keypair, // the owner until the roles change
+    pub pool_id: Pubkey,
+    pub pool: Pubkey,
+
+    pub tkr_mint: Pubkey,
+    pub tai_mint: Pubkey,
+    pub dai_mint: Pubkey,
+
+    warped: bool, // the bank was warped, see `process`
+}
+
+// Passes `to` in place of the account `from` of the instruction
+pub fn replace_account(ix: &mut Instruction, from: &Pubkey, to: &Pubkey) {
+    for meta in ix.accounts.iter_mut().filter(|m| &m.pubkey == from) {
+        meta.pubkey = *to;
+    }
+}
+
+// A user holding an NFT that is going to be deposited in the pool
+pub struct Borrower {
+    pub wallet: Keypair,
+    pub nft_mint: Pubkey,
+    pub deposit_id: Pubkey,
+    pub deposit: Pubkey,
+}
+
+impl TestEnv {
+    pub async fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let mut program_test =
+            ProgramTest::new("taker", program_id, processor!(taker::process_instruction));
+        // without BPF_OUT_DIR the taker program runs natively, run the SPL programs natively
+        // too instead of the bundled BPF builds so that the whole suite can be debugged
+        if std::env::var("BPF_OUT_DIR").is_err() {
+            program_test.add_program(
+                "spl_token",
+                spl_token::id(),
+                processor!(spl_token::processor::Processor::process),
+            );
+            program_test.add_program(
+                "spl_associated_token_account",
+                spl_associated_token_account::id(),
+                processor!(spl_associated_token_account::processor::process_instruction),
+            );
+        }
+        let ctx = program_test.start_with_context().await;
+
+        let mut env = TestEnv {
+            ctx,
+            program_id,
+            mint_authority: Keypair::new(),
+            owner: Keypair::new(),
+            settings_admin: Keypair::new(),
+            pool_id: Pubkey::new_unique(),
+            pool: Pubkey::default(),
+            tkr_mint: Pubkey::default(),
+            tai_mint: Pubkey::default(),
+            dai_mint: Pubkey::default(),
+            warped: false,
+        };
+        env.pool = NFTPool::get_address(&env.program_id, &env.pool_id);
+        env.settings_admin = clone_keypair(&env.owner);
+
+        env.airdrop(&env.owner.pubkey(), 10 * UNIT).await;
+        env.tkr_mint = env.create_mint(DECIMALS).await;
+        env.tai_mint = env.create_mint(DECIMALS).await;
+        env.dai_mint = env.create_mint(DECIMALS).await;
+
+        env.initialize().await.unwrap();
+
+        // the pool pays TKR incentives and TAI receipts out of its own reserve
+        let (pool, owner) = (env.pool, env.owner.pubkey());
+        let (tkr_mint, tai_mint, dai_mint) = (env.tkr_mint, env.tai_mint, env.dai_mint);
+        env.mint_to(&tkr_mint, &pool, 1000 * UNIT).await;
+        env.mint_to(&tai_mint, &pool, 1000 * UNIT).await;
+        // the owner collects the service fee
+        env.create_ata(&owner, &dai_mint).await;
+
+        env
+    }
+
+    pub async fn initialize(&mut self) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsInitialize {
+            pool_owner: self.owner.pubkey(),
+            pool: self.pool,
+
+            tkr_mint: self.tkr_mint,
+            pool_tkr_account: get_associated_token_address(&self.pool, &self.tkr_mint),
+            tai_mint: self.tai_mint,
+            pool_tai_account: get_associated_token_address(&self.pool, &self.tai_mint),
+            dai_mint: self.dai_mint,
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+            pool_fee_vault: self.fee_vault_address(),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::Initialize {
+                pool_id: self.pool_id,
+            },
+        );
+        let owner = clone_keypair(&self.owner);
+        self.process(&[ix], &[&owner]).await
+    }
+
+    pub async fn change_loan_settings(
+        &mut self,
+        incentive: Option<u64>,
+        interest_rate: Option<u64>,
+        service_fee_rate: Option<u64>,
+        max_loan_duration: Option<i64>,
+        mortgage_rate: Option<u64>,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsChangeLoanSetting {
+            settings_admin: self.settings_admin.pubkey(),
+            pool: self.pool,
+            clock: sysvar::clock::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::ChangeLoanSettings {
+                incentive,
+                interest_rate,
+                service_fee_rate,
+                max_loan_duration,
+                mortgage_rate,
+            },
+        );
+        let settings_admin = clone_keypair(&self.settings_admin);
+        self.process(&[ix], &[&settings_admin]).await
+    }
+
+    pub async fn change_liquidation_settings(
+        &mut self,
+        grace_period: Option<i64>,
+        grace_penalty_rate: Option<u64>,
+        auction_duration: Option<i64>,
+        auction_start_rate: Option<u64>,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsChangeLoanSetting {
+            settings_admin: self.settings_admin.pubkey(),
+            pool: self.pool,
+            clock: sysvar::clock::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::ChangeLiquidationSettings {
+                grace_period,
+                grace_penalty_rate,
+                auction_duration,
+                auction_start_rate,
+            },
+        );
+        let settings_admin = clone_keypair(&self.settings_admin);
+        self.process(&[ix], &[&settings_admin]).await
+    }
+
+    pub async fn change_interest_model(
+        &mut self,
+        interest_model: InterestCurve,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsChangeLoanSetting {
+            settings_admin: self.settings_admin.pubkey(),
+            pool: self.pool,
+            clock: sysvar::clock::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::ChangeInterestModel { interest_model },
+        );
+        let settings_admin = clone_keypair(&self.settings_admin);
+        self.process(&[ix], &[&settings_admin]).await
+    }
+
+    // Proposed by the settings admin, who funds the queued change
+    pub async fn propose_settings_change(
+        &mut self,
+        incentive: Option<u64>,
+        interest_rate: Option<u64>,
+        service_fee_rate: Option<u64>,
+        max_loan_duration: Option<i64>,
+        mortgage_rate: Option<u64>,
+    ) -> Result<(), TransactionError> {
+        self.propose_settings(taker::instruction::ProposeSettingsChange {
+            incentive,
+            interest_rate,
+            service_fee_rate,
+            max_loan_duration,
+            mortgage_rate,
+            grace_period: None,
+            grace_penalty_rate: None,
+            auction_duration: None,
+            auction_start_rate: None,
+            interest_model: None,
+        })
+        .await
+    }
+
+    pub async fn propose_settings(
+        &mut self,
+        args: taker::instruction::ProposeSettingsChange,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsProposeSettingsChange {
+            settings_admin: self.settings_admin.pubkey(),
+            pool: self.pool,
+            settings_change: self.settings_change_address(),
+            clock: sysvar::clock::id(),
+            rent: sysvar::rent::id(),
+            system_program: system_program::id(),
+        };
+        let ix = self.instruction(accounts, args);
+        let settings_admin = clone_keypair(&self.settings_admin);
+        self.process(&[ix], &[&settings_admin]).await
+    }
+
+    pub async fn cancel_settings_change(
+        &mut self,
+        authority: &Keypair,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsCancelSettingsChange {
+            authority: authority.pubkey(),
+            pool: self.pool,
+            settings_change: self.settings_change_address(),
+            proposer: self.settings_change_proposer().await,
+        };
+        let ix = self.instruction(accounts, taker::instruction::CancelSettingsChange {});
+        self.process(&[ix], &[authority]).await
+    }
+
+    // Sent by the payer, executing the queued change needs no signature
+    pub async fn execute_settings_change(&mut self) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsExecuteSettingsChange {
+            pool: self.pool,
+            settings_change: self.settings_change_address(),
+            proposer: self.settings_change_proposer().await,
+            clock: sysvar::clock::id(),
+        };
+        let ix = self.instruction(accounts, taker::instruction::ExecuteSettingsChange {});
+        self.process(&[ix], &[]).await
+    }
+
+    pub async fn change_settings_caps(
+        &mut self,
+        settings_caps: SettingsCaps,
+        settings_delay: i64,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsPoolOwner {
+            owner: self.owner.pubkey(),
+            pool: self.pool,
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::ChangeSettingsCaps {
+                settings_caps,
+                settings_delay,
+            },
+        );
+        let owner = clone_keypair(&self.owner);
+        self.process(&[ix], &[&owner]).await
+    }
+
+    // Proposed by the owner, who funds the queued change
+    pub async fn propose_caps_change(
+        &mut self,
+        settings_caps: SettingsCaps,
+        settings_delay: i64,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsProposeCapsChange {
+            owner: self.owner.pubkey(),
+            pool: self.pool,
+            settings_change: self.settings_change_address(),
+            clock: sysvar::clock::id(),
+            rent: sysvar::rent::id(),
+            system_program: system_program::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::ProposeCapsChange {
+                settings_caps,
+                settings_delay,
+            },
+        );
+        let owner = clone_keypair(&self.owner);
+        self.process(&[ix], &[&owner]).await
+    }
+
+    pub async fn propose_owner(
+        &mut self,
+        owner: &Keypair,
+        pending_owner: &Pubkey,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsPoolOwner {
+            owner: owner.pubkey(),
+            pool: self.pool,
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::ProposeOwner {
+                pending_owner: *pending_owner,
+            },
+        );
+        self.process(&[ix], &[owner]).await
+    }
+
+    pub async fn accept_owner(&mut self, pending_owner: &Keypair) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsAcceptOwner {
+            pending_owner: pending_owner.pubkey(),
+            pool: self.pool,
+        };
+        let ix = self.instruction(accounts, taker::instruction::AcceptOwner {});
+        self.process(&[ix], &[pending_owner]).await
+    }
+
+    pub async fn change_roles(
+        &mut self,
+        owner: &Keypair,
+        fee_recipient: Option<Pubkey>,
+        settings_admin: Option<Pubkey>,
+        pauser: Option<Pubkey>,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsPoolOwner {
+            owner: owner.pubkey(),
+            pool: self.pool,
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::ChangeRoles {
+                fee_recipient,
+                settings_admin,
+                pauser,
+            },
+        );
+        self.process(&[ix], &[owner]).await
+    }
+
+    // Sweeps the fees to the DAI account of the fee recipient
+    pub async fn collect_fees(&mut self, fee_recipient: &Keypair) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsCollectFees {
+            fee_recipient: fee_recipient.pubkey(),
+            pool: self.pool,
+            pool_fee_vault: self.fee_vault_address(),
+            fee_recipient_dai_account: get_associated_token_address(
+                &fee_recipient.pubkey(),
+                &self.dai_mint,
+            ),
+            spl_program: spl_token::id(),
+        };
+        let ix = self.instruction(accounts, taker::instruction::CollectFees {});
+        self.process(&[ix], &[fee_recipient]).await
+    }
+
+    pub async fn set_pause(
+        &mut self,
+        authority: &Keypair,
+        paused: u32,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsSetPause {
+            authority: authority.pubkey(),
+            pool: self.pool,
+        };
+        let ix = self.instruction(accounts, taker::instruction::SetPause { paused });
+        self.process(&[ix], &[authority]).await
+    }
+
+    // Creates a funded wallet that owns a freshly minted NFT
+    pub async fn new_borrower(&mut self) -> Borrower {
+        let wallet = self.new_wallet().await;
+        let nft_mint = self.create_mint(0).await;
+        self.mint_to(&nft_mint, &wallet.pubkey(), 1).await;
+        let dai_mint = self.dai_mint;
+        self.create_ata(&wallet.pubkey(), &dai_mint).await;
+
+        let deposit_id = Pubkey::new_unique();
+        let deposit = NFTDeposit::get_address(
+            &self.program_id,
+            &self.pool,
+            &nft_mint,
+            &wallet.pubkey(),
+            &deposit_id,
+        );
+
+        Borrower {
+            wallet,
+            nft_mint,
+            deposit_id,
+            deposit,
+        }
+    }
+
+    // Creates a funded wallet holding `dai` DAI and an empty TAI account
+    pub async fn new_lender(&mut self, dai: u64) -> Keypair {
+        let wallet = self.new_wallet().await;
+        let (dai_mint, tai_mint) = (self.dai_mint, self.tai_mint);
+        self.mint_to(&dai_mint, &wallet.pubkey(), dai).await;
+        self.create_ata(&wallet.pubkey(), &tai_mint).await;
+        wallet
+    }
+
+    pub async fn new_wallet(&mut self) -> Keypair {
+        let wallet = Keypair::new();
+        self.airdrop(&wallet.pubkey(), 10 * UNIT).await;
+        wallet
+    }
+
+    pub async fn deposit_nft(&mut self, borrower: &Borrower) -> Result<(), TransactionError> {
+        let wallet = borrower.wallet.pubkey();
+        let accounts = taker::accounts::AccountsDepositNFT {
+            pool: self.pool,
+            borrower_wallet_account: wallet,
+
+            nft_mint: borrower.nft_mint,
+            tkr_mint: self.tkr_mint,
+
+            borrower_nft_account: get_associated_token_address(&wallet, &borrower.nft_mint),
+            pool_nft_account: get_associated_token_address(&self.pool, &borrower.nft_mint),
+
+            borrower_tkr_account: get_associated_token_address(&wallet, &self.tkr_mint),
+            pool_tkr_account: get_associated_token_address(&self.pool, &self.tkr_mint),
+
+            deposit_account: borrower.deposit,
+
+            rent: sysvar::rent::id(),
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::DepositNft {
+                deposit_id: borrower.deposit_id,
+            },
+        );
+        self.process(&[ix], &[&borrower.wallet]).await
+    }
+
+    pub async fn withdraw_nft(
+        &mut self,
+        borrower: &Borrower,
+        close: bool,
+    ) -> Result<(), TransactionError> {
+        let wallet = borrower.wallet.pubkey();
+        let accounts = taker::accounts::AccountsWithdrawNFT {
+            pool: self.pool,
+            borrower_wallet_account: wallet,
+
+            nft_mint: borrower.nft_mint,
+            pool_nft_account: get_associated_token_address(&self.pool, &borrower.nft_mint),
+            borrower_nft_account: get_associated_token_address(&wallet, &borrower.nft_mint),
+
+            deposit_account: borrower.deposit,
+
+            spl_program: spl_token::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::WithdrawNft {
+                deposit_id: borrower.deposit_id,
+                close,
+            },
+        );
+        self.process(&[ix], &[&borrower.wallet]).await
+    }
+
+    // Places a bid with the default terms of the pool
+    pub async fn place_bid(
+        &mut self,
+        lender: &Keypair,
+        nft_mint: &Pubkey,
+        price: u64,
+        qty: u64,
+    ) -> Result<(), TransactionError> {
+        self.place_bid_with_terms(lender, nft_mint, price, qty, None)
+            .await
+    }
+
+    pub async fn place_bid_with_terms(
+        &mut self,
+        lender: &Keypair,
+        nft_mint: &Pubkey,
+        price: u64,
+        qty: u64,
+        terms: Option<LoanTerms>,
+    ) -> Result<(), TransactionError> {
+        self.place_mint_bid(lender, nft_mint, price, qty, terms, false, None, 0)
+            .await
+    }
+
+    // The DAI of the bid moves into its escrow
+    pub async fn place_escrowed_bid(
+        &mut self,
+        lender: &Keypair,
+        nft_mint: &Pubkey,
+        price: u64,
+        qty: u64,
+    ) -> Result<(), TransactionError> {
+        self.place_mint_bid(lender, nft_mint, price, qty, None, true, None, 0)
+            .await
+    }
+
+    // Loans take at least `min_amount` from the bid, until `expires_at`
+    pub async fn place_bid_with_limits(
+        &mut self,
+        lender: &Keypair,
+        nft_mint: &Pubkey,
+        price: u64,
+        qty: u64,
+        expires_at: Option<i64>,
+        min_amount: u64,
+    ) -> Result<(), TransactionError> {
+        self.place_mint_bid(
+            lender, nft_mint, price, qty, None, false, expires_at, min_amount,
+        )
+        .await
+    }
+
+    pub async fn place_mint_bid(
+        &mut self,
+        lender: &Keypair,
+        nft_mint: &Pubkey,
+        price: u64,
+        qty: u64,
+        terms: Option<LoanTerms>,
+        escrow: bool,
+        expires_at: Option<i64>,
+        min_amount: u64,
+    ) -> Result<(), TransactionError> {
+        let bid = self.bid_address(nft_mint, &lender.pubkey());
+        let accounts = taker::accounts::AccountsPlaceBid {
+            pool: self.pool,
+            lender_wallet_account: lender.pubkey(),
+
+            nft_mint: *nft_mint,
+            dai_mint: self.dai_mint,
+            lender_dai_account: get_associated_token_address(&lender.pubkey(), &self.dai_mint),
+
+            bid_account: bid,
+            bid_escrow_account: self.bid_escrow_address(&bid),
+
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::PlaceBid {
+                price,
+                qty,
+                terms,
+                escrow,
+                expires_at,
+                min_amount,
+            },
+        );
+        self.process(&[ix], &[lender]).await
+    }
+
+    pub async fn cancel_bid(
+        &mut self,
+        lender: &Keypair,
+        nft_mint: &Pubkey,
+    ) -> Result<(), TransactionError> {
+        let bid = self.bid_address(nft_mint, &lender.pubkey());
+        let accounts = taker::accounts::AccountsCancelBid {
+            pool: self.pool,
+            lender_wallet_account: lender.pubkey(),
+
+            nft_mint: *nft_mint,
+            lender_dai_account: get_associated_token_address(&lender.pubkey(), &self.dai_mint),
+
+            bid_account: bid,
+            bid_escrow_account: self.bid_escrow_address(&bid),
+
+            spl_program: spl_token::id(),
+        };
+        let ix = self.instruction(accounts, taker::instruction::CancelBid { revoke: true });
+        self.process(&[ix], &[lender]).await
+    }
+
+    pub async fn place_collection_bid(
+        &mut self,
+        lender: &Keypair,
+        allowlist_root: [u8; 32],
+        price: u64,
+        qty: u64,
+    ) -> Result<(), TransactionError> {
+        self.place_collection_bid_with_terms(lender, allowlist_root, price, qty, None, None)
+            .await
+    }
+
+    pub async fn place_collection_bid_with_terms(
+        &mut self,
+        lender: &Keypair,
+        allowlist_root: [u8; 32],
+        price: u64,
+        qty: u64,
+        terms: Option<LoanTerms>,
+        expires_at: Option<i64>,
+    ) -> Result<(), TransactionError> {
+        let bid = self.collection_bid_address(&allowlist_root, &lender.pubkey());
+        let accounts = taker::accounts::AccountsPlaceCollectionBid {
+            pool: self.pool,
+            lender_wallet_account: lender.pubkey(),
+
+            dai_mint: self.dai_mint,
+            lender_dai_account: get_associated_token_address(&lender.pubkey(), &self.dai_mint),
+
+            bid_account: bid,
+            bid_escrow_account: self.bid_escrow_address(&bid),
+
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::PlaceCollectionBid {
+                allowlist_root,
+                price,
+                qty,
+                terms,
+                escrow: false,
+                expires_at,
+                min_amount: 0,
+            },
+        );
+        self.process(&[ix], &[lender]).await
+    }
+
+    pub async fn cancel_collection_bid(
+        &mut self,
+        lender: &Keypair,
+        allowlist_root: [u8; 32],
+    ) -> Result<(), TransactionError> {
+        let bid = self.collection_bid_address(&allowlist_root, &lender.pubkey());
+        let accounts = taker::accounts::AccountsCancelCollectionBid {
+            pool: self.pool,
+            lender_wallet_account: lender.pubkey(),
+
+            lender_dai_account: get_associated_token_address(&lender.pubkey(), &self.dai_mint),
+
+            bid_account: bid,
+            bid_escrow_account: self.bid_escrow_address(&bid),
+
+            spl_program: spl_token::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::CancelCollectionBid {
+                allowlist_root,
+                revoke: true,
+            },
+        );
+        self.process(&[ix], &[lender]).await
+    }
+
+    // Borrows against the mint bid of `lender`
+    pub async fn borrow(
+        &mut self,
+        borrower: &Borrower,
+        lender: &Pubkey,
+        amount: u64,
+    ) -> Result<(), TransactionError> {
+        let bid = self.bid_address(&borrower.nft_mint, lender);
+        self.borrow_against(borrower, lender, &bid, amount, vec![])
+            .await
+    }
+
+    pub async fn borrow_against(
+        &mut self,
+        borrower: &Borrower,
+        lender: &Pubkey,
+        bid: &Pubkey,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<(), TransactionError> {
+        let ix = self.borrow_instruction(borrower, lender, bid, amount, proof);
+        self.process(&[ix], &[&borrower.wallet]).await
+    }
+
+    pub fn borrow_instruction(
+        &self,
+        borrower: &Borrower,
+        lender: &Pubkey,
+        bid: &Pubkey,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Instruction {
+        let wallet = borrower.wallet.pubkey();
+        let accounts = taker::accounts::AccountsBorrow {
+            pool: self.pool,
+            borrower_wallet_account: wallet,
+            lender_wallet_account: *lender,
+
+            nft_mint: borrower.nft_mint,
+
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+            borrower_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+            lender_dai_account: get_associated_token_address(lender, &self.dai_mint),
+
+            lender_tai_account: get_associated_token_address(lender, &self.tai_mint),
+            pool_tai_account: get_associated_token_address(&self.pool, &self.tai_mint),
+
+            deposit_account: borrower.deposit,
+            bid_account: *bid,
+            bid_escrow_account: self.bid_escrow_address(bid),
+
+            loan_note_mint: self.loan_note_address(borrower, lender),
+            lender_note_account: self.loan_note_account(borrower, lender, lender),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        };
+        self.instruction(accounts, taker::instruction::Borrow { amount, proof })
+    }
+
+    pub async fn place_listing(
+        &mut self,
+        borrower: &Borrower,
+        amount: u64,
+        max_interest_rate: u64,
+        loan_duration: i64,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsPlaceListing {
+            pool: self.pool,
+            borrower_wallet_account: borrower.wallet.pubkey(),
+
+            deposit_account: borrower.deposit,
+            listing_account: self.listing_address(borrower),
+
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let ix = self.instruction(
+            accounts,
+            taker::instruction::PlaceListing {
+                amount,
+                max_interest_rate,
+                loan_duration,
+            },
+        );
+        self.process(&[ix], &[&borrower.wallet]).await
+    }
+
+    pub async fn cancel_listing(&mut self, borrower: &Borrower) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsCancelListing {
+            borrower_wallet_account: borrower.wallet.pubkey(),
+            listing_account: self.listing_address(borrower),
+        };
+        let ix = self.instruction(accounts, taker::instruction::CancelListing {});
+        self.process(&[ix], &[&borrower.wallet]).await
+    }
+
+    pub async fn fund_listing(
+        &mut self,
+        lender: &Keypair,
+        borrower: &Borrower,
+        interest_rate: u64,
+    ) -> Result<(), TransactionError> {
+        let ix = self.fund_listing_instruction(&lender.pubkey(), borrower, interest_rate);
+        self.process(&[ix], &[lender]).await
+    }
+
+    pub fn fund_listing_instruction(
+        &self,
+        lender: &Pubkey,
+        borrower: &Borrower,
+        interest_rate: u64,
+    ) -> Instruction {
+        let wallet = borrower.wallet.pubkey();
+        let accounts = taker::accounts::AccountsFundListing {
+            pool: self.pool,
+            lender_wallet_account: *lender,
+            borrower_wallet_account: wallet,
+
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+            borrower_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+            lender_dai_account: get_associated_token_address(lender, &self.dai_mint),
+
+            lender_tai_account: get_associated_token_address(lender, &self.tai_mint),
+            pool_tai_account: get_associated_token_address(&self.pool, &self.tai_mint),
+
+            deposit_account: borrower.deposit,
+            listing_account: self.listing_address(borrower),
+
+            loan_note_mint: self.loan_note_address(borrower, lender),
+            lender_note_account: self.loan_note_account(borrower, lender, lender),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        };
+        self.instruction(accounts, taker::instruction::FundListing { interest_rate })
+    }
+
+    pub async fn repay(&mut self, borrower: &Borrower) -> Result<(), TransactionError> {
+        let pool_dai_account = get_associated_token_address(&self.pool, &self.dai_mint);
+        self.repay_into(borrower, &pool_dai_account).await
+    }
+
+    pub async fn repay_into(
+        &mut self,
+        borrower: &Borrower,
+        pool_dai_account: &Pubkey,
+    ) -> Result<(), TransactionError> {
+        let wallet = borrower.wallet.pubkey();
+        let accounts = taker::accounts::AccountsRepay {
+            pool: self.pool,
+            borrower_wallet_account: wallet,
+
+            pool_fee_vault: self.fee_vault_address(),
+            borrower_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+            pool_dai_account: *pool_dai_account,
+
+            borrower_nft_account: get_associated_token_address(&wallet, &borrower.nft_mint),
+            pool_nft_account: get_associated_token_address(&self.pool, &borrower.nft_mint),
+
+            deposit_account: borrower.deposit,
+
+            spl_program: spl_token::id(),
+            clock: sysvar::clock::id(),
+        };
+        let ix = self.instruction(accounts, taker::instruction::Repay {});
+        self.process(&[ix], &[&borrower.wallet]).await
+    }
+
+    pub async fn repay_partial(
+        &mut self,
+        borrower: &Borrower,
+        amount: u64,
+    ) -> Result<(), TransactionError> {
+        let wallet = borrower.wallet.pubkey();
+        let accounts = taker::accounts::AccountsRepayPartial {
+            pool: self.pool,
+            borrower_wallet_account: wallet,
+
+            pool_fee_vault: self.fee_vault_address(),
+            borrower_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+
+            deposit_account: borrower.deposit,
+
+            spl_program: spl_token::id(),
+            clock: sysvar::clock::id(),
+        };
+        let ix = self.instruction(accounts, taker::instruction::RepayPartial { amount });
+        self.process(&[ix], &[&borrower.wallet]).await
+    }
+
+    // The lender signs when `lender` is given, otherwise the bid on the NFT has to allow the extension
+    pub async fn extend_loan(
+        &mut self,
+        borrower: &Borrower,
+        lender: &Pubkey,
+        extension: i64,
+        lender_signer: Option<&Keypair>,
+    ) -> Result<(), TransactionError> {
+        let bid = self.bid_address(&borrower.nft_mint, lender);
+        self.extend_loan_against(borrower, lender, &bid, vec![], extension, lender_signer)
+            .await
+    }
+
+    // Extends the loan as allowed by `bid`, a collection bid needs the `proof` of the NFT
+    pub async fn extend_loan_against(
+        &mut self,
+        borrower: &Borrower,
+        lender: &Pubkey,
+        bid: &Pubkey,
+        proof: Vec<[u8; 32]>,
+        extension: i64,
+        lender_signer: Option<&Keypair>,
+    ) -> Result<(), TransactionError> {
+        let wallet = borrower.wallet.pubkey();
+        let recorded = self.recorded_lender(borrower).await;
+        let accounts = taker::accounts::AccountsExtendLoan {
+            pool: self.pool,
+            borrower_wallet_account: wallet,
+            lender_wallet_account: *lender,
+
+            pool_fee_vault: self.fee_vault_address(),
+            borrower_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+            lender_dai_account: get_associated_token_address(lender, &self.dai_mint),
+
+            bid_account: *bid,
+            deposit_account: borrower.deposit,
+
+            loan_note_mint: self.loan_note_address(borrower, &recorded),
+            lender_note_account: self.loan_note_account(borrower, &recorded, lender),
+
+            spl_program: spl_token::id(),
+            clock: sysvar::clock::id(),
+        };
+        let mut ix = self.instruction(
+            accounts,
+            taker::instruction::ExtendLoan { extension, proof },
+        );
+
+        let mut signers = vec![&borrower.wallet];
+        if let Some(lender) = lender_signer {
+            for meta in ix
+                .accounts
+                .iter_mut()
+                .filter(|m| m.pubkey == lender.pubkey())
+            {
+                meta.is_signer = true;
+            }
+            signers.push(lender);
+        }
+        self.process(&[ix], &signers).await
+    }
+
+    pub async fn set_auto_refinance(
+        &mut self,
+        borrower: &Borrower,
+        enabled: bool,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsSetAutoRefinance {
+            pool: self.pool,
+            borrower_wallet_account: borrower.wallet.pubkey(),
+
+            deposit_account: borrower.deposit,
+            auto_refinance_account: AutoRefinance::get_address(&self.program_id, &borrower.deposit),
+
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+        };
+        let ix = self.instruction(accounts, taker::instruction::SetAutoRefinance { enabled });
+        self.process(&[ix], &[&borrower.wallet]).await
+    }
+
+    // Refinances the loan with the mint bid of `lender`, the borrower signs when it is the caller
+    pub async fn refinance(
+        &mut self,
+        caller: &Keypair,
+        borrower: &Borrower,
+        lender: &Pubkey,
+    ) -> Result<(), TransactionError> {
+        let wallet = borrower.wallet.pubkey();
+        let current_lender = self.active_loan(borrower).await.lender;
+        let bid = self.bid_address(&borrower.nft_mint, lender);
+        let accounts = taker::accounts::AccountsRefinance {
+            pool: self.pool,
+            payer: caller.pubkey(),
+            borrower_wallet_account: wallet,
+            lender_wallet_account: *lender,
+
+            pool_fee_vault: self.fee_vault_address(),
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+            lender_dai_account: get_associated_token_address(lender, &self.dai_mint),
+
+            pool_tai_account: get_associated_token_address(&self.pool, &self.tai_mint),
+            lender_tai_account: get_associated_token_address(lender, &self.tai_mint),
+
+            deposit_account: borrower.deposit,
+            bid_account: bid,
+            bid_escrow_account: self.bid_escrow_address(&bid),
+            auto_refinance_account: AutoRefinance::get_address(&self.program_id, &borrower.deposit),
+            payoff_account: self.refinance_payoff_address(borrower, &current_lender),
+
+            loan_note_mint: self.loan_note_address(borrower, lender),
+            lender_note_account: self.loan_note_account(borrower, lender, lender),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        };
+        let mut ix = self.instruction(accounts, taker::instruction::Refinance { proof: vec![] });
+        for meta in ix
+            .accounts
+            .iter_mut()
+            .filter(|m| m.pubkey == caller.pubkey())
+        {
+            meta.is_signer = true;
+        }
+        self.process(&[ix], &[caller]).await
+    }
+
+    // Sent by the holder of the loan note of the refinanced `lender`
+    pub async fn withdraw_refinance_payoff(
+        &mut self,
+        borrower: &Borrower,
+        holder: &Keypair,
+        lender: &Pubkey,
+    ) -> Result<(), TransactionError> {
+        let ix = self.withdraw_refinance_payoff_instruction(borrower, &holder.pubkey(), lender);
+        self.process(&[ix], &[holder]).await
+    }
+
+    pub fn withdraw_refinance_payoff_instruction(
+        &self,
+        borrower: &Borrower,
+        holder: &Pubkey,
+        lender: &Pubkey,
+    ) -> Instruction {
+        let wallet = *holder;
+        let accounts = taker::accounts::AccountsWithdrawRefinancePayoff {
+            pool: self.pool,
+            lender_wallet_account: wallet,
+
+            lender_tai_account: get_associated_token_address(&wallet, &self.tai_mint),
+            pool_tai_account: get_associated_token_address(&self.pool, &self.tai_mint),
+
+            lender_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+
+            deposit_account: borrower.deposit,
+            payoff_account: self.refinance_payoff_address(borrower, lender),
+
+            loan_note_mint: self.loan_note_address(borrower, lender),
+            lender_note_account: self.loan_note_account(borrower, lender, &wallet),
+
+            spl_program: spl_token::id(),
+        };
+        self.instruction(accounts, taker::instruction::WithdrawRefinancePayoff {})
+    }
+
+    pub async fn liquidate(
+        &mut self,
+        borrower: &Borrower,
+        lender: &Keypair,
+        close: bool,
+    ) -> Result<(), TransactionError> {
+        let ix = self.liquidate_instruction(borrower, lender, close).await;
+        self.process(&[ix], &[lender]).await
+    }
+
+    pub async fn liquidate_instruction(
+        &mut self,
+        borrower: &Borrower,
+        lender: &Keypair,
+        close: bool,
+    ) -> Instruction {
+        let wallet = lender.pubkey();
+        let recorded_lender = self.recorded_lender(borrower).await;
+        let accounts = taker::accounts::AccountsLiquidate {
+            pool: self.pool,
+            lender_wallet_account: wallet,
+            borrower_wallet_account: borrower.wallet.pubkey(),
+
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+            pool_fee_vault: self.fee_vault_address(),
+            lender_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+
+            nft_mint: borrower.nft_mint,
+            pool_nft_account: get_associated_token_address(&self.pool, &borrower.nft_mint),
+            lender_nft_account: get_associated_token_address(&wallet, &borrower.nft_mint),
+
+            lender_tai_account: get_associated_token_address(&wallet, &self.tai_mint),
+            pool_tai_account: get_associated_token_address(&self.pool, &self.tai_mint),
+
+            deposit_account: borrower.deposit,
+
+            loan_note_mint: self.loan_note_address(borrower, &recorded_lender),
+            lender_note_account: self.loan_note_account(borrower, &recorded_lender, &wallet),
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        };
+        self.instruction(accounts, taker::instruction::Liquidate { close })
+    }
+
+    pub async fn withdraw_locked_asset(
+        &mut self,
+        borrower: &Borrower,
+        lender: &Keypair,
+        close: bool,
+    ) -> Result<(), TransactionError> {
+        let ix = self
+            .withdraw_locked_asset_instruction(borrower, lender, close)
+            .await;
+        self.process(&[ix], &[lender]).await
+    }
+
+    pub async fn withdraw_locked_asset_instruction(
+        &mut self,
+        borrower: &Borrower,
+        lender: &Keypair,
+        close: bool,
+    ) -> Instruction {
+        let wallet = lender.pubkey();
+        let recorded_lender = self.recorded_lender(borrower).await;
+        let accounts = taker::accounts::AccountsWithdrawLockedAsset {
+            pool: self.pool,
+            lender_wallet_account: wallet,
+            borrower_wallet_account: borrower.wallet.pubkey(),
+
+            lender_tai_account: get_associated_token_address(&wallet, &self.tai_mint),
+            pool_tai_account: get_associated_token_address(&self.pool, &self.tai_mint),
+
+            lender_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+
+            deposit_account: borrower.deposit,
+
+            loan_note_mint: self.loan_note_address(borrower, &recorded_lender),
+            lender_note_account: self.loan_note_account(borrower, &recorded_lender, &wallet),
+
+            spl_program: spl_token::id(),
+        };
+        self.instruction(accounts, taker::instruction::WithdrawLockedAsset { close })
+    }
+
+    // Sent by the payer, starting an auction needs no signature
+    pub async fn start_auction(&mut self, borrower: &Borrower) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsStartAuction {
+            pool: self.pool,
+            deposit_account: borrower.deposit,
+            clock: sysvar::clock::id(),
+        };
+        let ix = self.instruction(accounts, taker::instruction::StartAuction {});
+        self.process(&[ix], &[]).await
+    }
+
+    pub async fn buy_collateral(
+        &mut self,
+        borrower: &Borrower,
+        buyer: &Keypair,
+        max_price: u64,
+    ) -> Result<(), TransactionError> {
+        let wallet = buyer.pubkey();
+        let accounts = taker::accounts::AccountsBuyCollateral {
+            pool: self.pool,
+            buyer_wallet_account: wallet,
+
+            pool_fee_vault: self.fee_vault_address(),
+            buyer_dai_account: get_associated_token_address(&wallet, &self.dai_mint),
+            borrower_dai_account: get_associated_token_address(
+                &borrower.wallet.pubkey(),
+                &self.dai_mint,
+            ),
+            pool_dai_account: get_associated_token_address(&self.pool, &self.dai_mint),
+
+            nft_mint: borrower.nft_mint,
+            buyer_nft_account: get_associated_token_address(&wallet, &borrower.nft_mint),
+            pool_nft_account: get_associated_token_address(&self.pool, &borrower.nft_mint),
+
+            deposit_account: borrower.deposit,
+
+            ata_program: spl_associated_token_account::id(),
+            spl_program: spl_token::id(),
+            system_program: system_program::id(),
+            rent: sysvar::rent::id(),
+            clock: sysvar::clock::id(),
+        };
+        let ix = self.instruction(accounts, taker::instruction::BuyCollateral { max_price });
+        self.process(&[ix], &[buyer]).await
+    }
+
+    // Sent by the payer, closing a deposit needs no signature
+    pub async fn close_deposit(
+        &mut self,
+        deposit: &Pubkey,
+        borrower_wallet: &Pubkey,
+    ) -> Result<(), TransactionError> {
+        let accounts = taker::accounts::AccountsCloseDeposit {
+            pool: self.pool,
+            borrower_wallet_account: *borrower_wallet,
+            deposit_account: *deposit,
+        };
+        let ix = self.instruction(accounts, taker::instruction::CloseDeposit {});
+        self.process(&[ix], &[]).await
+    }
+
+    pub fn bid_address(&self, nft_mint: &Pubkey, lender: &Pubkey) -> Pubkey {
+        NFTBid::get_address(&self.program_id, &self.pool, nft_mint, lender)
+    }
+
+    pub fn collection_bid_address(&self, allowlist_root: &[u8; 32], lender: &Pubkey) -> Pubkey {
+        NFTBid::get_collection_address(&self.program_id, &self.pool, allowlist_root, lender)
+    }
+
+    pub fn bid_escrow_address(&self, bid: &Pubkey) -> Pubkey {
+        NFTBid::get_escrow_address(&self.program_id, bid)
+    }
+
+    pub fn listing_address(&self, borrower: &Borrower) -> Pubkey {
+        NFTListing::get_address(&self.program_id, &borrower.deposit)
+    }
+
+    pub fn refinance_payoff_address(&self, borrower: &Borrower, lender: &Pubkey) -> Pubkey {
+        RefinancePayoff::get_address(&self.program_id, &self.pool, &borrower.deposit, lender)
+    }
+
+    pub fn loan_note_address(&self, borrower: &Borrower, lender: &Pubkey) -> Pubkey {
+        NFTDeposit::get_loan_note_address(&self.program_id, &borrower.deposit, lender)
+    }
+
+    // The account of `holder` for the loan note of `lender`
+    pub fn loan_note_account(
+        &self,
+        borrower: &Borrower,
+        lender: &Pubkey,
+        holder: &Pubkey,
+    ) -> Pubkey {
+        get_associated_token_address(holder, &self.loan_note_address(borrower, lender))
+    }
+
+    pub fn fee_vault_address(&self) -> Pubkey {
+        NFTPool::get_fee_vault_address(&self.program_id, &self.pool)
+    }
+
+    pub async fn fee_vault_balance(&mut self) -> u64 {
+        let fee_vault = self.fee_vault_address();
+        self.token_balance(&fee_vault).await
+    }
+
+    pub fn settings_change_address(&self) -> Pubkey {
+        SettingsChange::get_address(&self.program_id, &self.pool)
+    }
+
+    pub async fn settings_change_account(&mut self) -> SettingsChange {
+        let settings_change = self.settings_change_address();
+        self.anchor_account(&settings_change).await
+    }
+
+    // The settings admin when no change is queued
+    pub async fn settings_change_proposer(&mut self) -> Pubkey {
+        let settings_change = self.settings_change_address();
+        match self.account(&settings_change).await {
+            Some(_) => self.settings_change_account().await.proposer(),
+            None => self.settings_admin.pubkey(),
+        }
+    }
+
+    pub async fn pool_account(&mut self) -> NFTPool {
+        let pool = self.pool;
+        self.anchor_account(&pool).await
+    }
+
+    pub async fn bid_account(&mut self, nft_mint: &Pubkey, lender: &Pubkey) -> NFTBid {
+        let bid = self.bid_address(nft_mint, lender);
+        self.anchor_account(&bid).await
+    }
+
+    pub async fn deposit_state(&mut self, borrower: &Borrower) -> DepositState {
+        let deposit: NFTDeposit = self.anchor_account(&borrower.deposit).await;
+        deposit.state()
+    }
+
+    // The lender recorded by the deposit, the loan note is derived from it
+    pub async fn recorded_lender(&mut self, borrower: &Borrower) -> Pubkey {
+        match self.deposit_state(borrower).await {
+            DepositState::LoanActive(loan) => loan.lender,
+            DepositState::LoanAuctioned(auction) => auction.lender,
+            DepositState::LoanRepayed(repay) => repay.lender,
+            _ => Pubkey::default(),
+        }
+    }
+
+    pub async fn active_loan(&mut self, borrower: &Borrower) -> LoanActiveState {
+        let deposit: NFTDeposit = self.anchor_account(&borrower.deposit).await;
+        deposit
+            .get_active_state()
+            .unwrap_or_else(|e| panic!("the loan is not active: {:?} {:?}", e, deposit.state()))
+    }
+
+    pub async fn anchor_account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self.account(address).await.expect("account not found");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.ctx.banks_client.get_account(*address).await.unwrap()
+    }
+
+    // Token balance of the associated token account of `wallet`, 0 if the account does not exist
+    pub async fn balance(&mut self, wallet: &Pubkey, mint: &Pubkey) -> u64 {
+        self.token_balance(&get_associated_token_address(wallet, mint))
+            .await
+    }
+
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        match self.account(address).await {
+            Some(account) => {
+                spl_token::state::Account::unpack(&account.data)
+                    .unwrap()
+                    .amount
+            }
+            None => 0,
+        }
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.ctx.banks_client.get_sysvar().await.unwrap()
+    }
+
+    pub async fn unix_timestamp(&mut self) -> i64 {
+        self.clock().await.unix_timestamp
+    }
+
+    // Moves to a later slot so that a retried transaction gets a new blockhash, identical
+    // transactions are otherwise deduplicated by the bank
+    pub async fn skip_slots(&mut self) {
+        let slot = self.clock().await.slot;
+        self.ctx.warp_to_slot(slot + 2).unwrap();
+        self.warped = true;
+    }
+
+    // Moves the clock forward by at least `seconds`. The bank only moves the timestamp by
+    // estimation when warping, so keep warping until the target is reached.
+    pub async fn advance_clock(&mut self, seconds: i64) {
+        let target = self.unix_timestamp().await + seconds;
+        let mut slot = self.clock().await.slot;
+        for _ in 0..32 {
+            if self.unix_timestamp().await >= target {
+                return;
+            }
+            // a slot is ~400ms
+            slot += (seconds as u64 * 5 / 2).max(1000);
+            self.ctx.warp_to_slot(slot).unwrap();
+            self.warped = true;
+        }
+        panic!("the clock did not advance by {} seconds", seconds);
+    }
+
+    pub async fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let payer = self.ctx.payer.pubkey();
+
+        let ixs = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &self.mint_authority.pubkey(),
+                None,
+                decimals,
+            )
+            .unwrap(),
+        ];
+        self.process(&ixs, &[&mint]).await.unwrap();
+
+        mint.pubkey()
+    }
+
+    pub async fn create_ata(&mut self, wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let address = get_associated_token_address(wallet, mint);
+        if self.account(&address).await.is_none() {
+            let ix = create_associated_token_account(&self.ctx.payer.pubkey(), wallet, mint);
+            self.process(&[ix], &[]).await.unwrap();
+        }
+        address
+    }
+
+    pub async fn mint_to(&mut self, mint: &Pubkey, wallet: &Pubkey, amount: u64) {
+        let address = self.create_ata(wallet, mint).await;
+        let ix = spl_token::instruction::mint_to(
+            &spl_token::id(),
+            mint,
+            &address,
+            &self.mint_authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        let authority = clone_keypair(&self.mint_authority);
+        self.process(&[ix], &[&authority]).await.unwrap();
+    }
+
+    // Moves tokens between the associated accounts of two wallets
+    pub async fn transfer(&mut self, mint: &Pubkey, from: &Keypair, to: &Pubkey, amount: u64) {
+        let destination = self.create_ata(to, mint).await;
+        let ix = spl_token::instruction::transfer(
+            &spl_token::id(),
+            &get_associated_token_address(&from.pubkey(), mint),
+            &destination,
+            &from.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.process(&[ix], &[from]).await.unwrap();
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        let ix = system_instruction::transfer(&self.ctx.payer.pubkey(), to, lamports);
+        self.process(&[ix], &[]).await.unwrap();
+    }
+
+    pub fn instruction(
+        &self,
+        accounts: impl ToAccountMetas,
+        args: impl InstructionData,
+    ) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: args.data(),
+        }
+    }
+
+    // Sends the instructions signed by the payer and `signers`
+    pub async fn process(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransactionError> {
+        // Once warped, the client reads the blockhashes of the bank past the root while the server
+        // checks them against the root, which only knows the blockhash of the last warp
+        let blockhash = if self.warped {
+            self.ctx.last_blockhash
+        } else {
+            self.ctx.banks_client.get_recent_blockhash().await.unwrap()
+        };
+
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        self.ctx
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .map_err(|e| e.unwrap())
+    }
+}
+
+// The error of a transaction whose first instruction failed with `err`
+pub fn taker_error(err: TakerError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(err as u32 + ERROR_CODE_OFFSET))
+}
+
+// Errors raised by the anchor account constraints
+pub fn anchor_error(err: anchor_lang::__private::ErrorCode) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(err as u32))
+}
+
+pub fn clone_keypair(keypair: &Keypair) -> Keypair {
+    Keypair::from_bytes(&keypair.to_bytes()).unwrap()
+}
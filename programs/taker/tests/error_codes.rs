@@ -0,0 +1,39 @@
+use std::convert::TryFrom;
+use taker::{CustomError, TakerError, ERROR_CODE_OFFSET};
+
+#[test]
+fn decode_taker_errors() {
+    for err in &[
+        TakerError::NotAuhorized,
+        TakerError::NFTBidAddressNotCorrect,
+        TakerError::BorrowedAmountExceedTotal,
+    ] {
+        let code = *err as u32 + ERROR_CODE_OFFSET;
+        assert_eq!(TakerError::try_from(code), Ok(*err));
+        assert!(matches!(CustomError::from(code), CustomError::Taker(e) if e == *err));
+    }
+
+    let unknown = ERROR_CODE_OFFSET + 1000;
+    assert_eq!(TakerError::try_from(unknown), Err(unknown));
+    assert!(matches!(CustomError::from(unknown), CustomError::Unknown(c) if c == unknown));
+}
+
+#[test]
+fn decode_framework_errors() {
+    // codes below the offset never underflow into a Taker error
+    for code in &[0, 1, 99, 100, 299] {
+        assert!(TakerError::try_from(*code).is_err());
+    }
+
+    assert!(matches!(
+        CustomError::from(1),
+        CustomError::Token(spl_token::error::TokenError::InsufficientFunds)
+    ));
+    assert!(matches!(CustomError::from(142), CustomError::Anchor(_)));
+    assert_eq!(
+        CustomError::from(142).to_string(),
+        "Anchor error ConstraintSigner: A signer constraint as violated"
+    );
+    assert!(matches!(CustomError::from(99), CustomError::Unknown(99)));
+    assert!(matches!(CustomError::from(110), CustomError::Unknown(110)));
+}
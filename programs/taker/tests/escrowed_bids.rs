@@ -0,0 +1,113 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+
+#[tokio::test]
+async fn escrowed_bids_stay_funded() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let first = env.new_borrower().await;
+    let second = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&first).await.unwrap();
+    env.deposit_nft(&second).await.unwrap();
+
+    // an approval would only cover the last bid, each escrow holds its own DAI
+    env.place_escrowed_bid(&lender, &first.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.place_escrowed_bid(&lender, &second.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+
+    let first_escrow = env.bid_escrow_address(&env.bid_address(&first.nft_mint, &lender.pubkey()));
+    let second_escrow =
+        env.bid_escrow_address(&env.bid_address(&second.nft_mint, &lender.pubkey()));
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - 2 * BID_PRICE
+    );
+    assert_eq!(env.token_balance(&first_escrow).await, BID_PRICE);
+    assert_eq!(env.token_balance(&second_escrow).await, BID_PRICE);
+    assert!(
+        env.bid_account(&first.nft_mint, &lender.pubkey())
+            .await
+            .escrowed
+    );
+
+    env.borrow(&first, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    env.borrow(&second, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    assert_eq!(env.token_balance(&first_escrow).await, 0);
+    assert_eq!(env.token_balance(&second_escrow).await, 0);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - 2 * BID_PRICE
+    );
+    assert_eq!(env.active_loan(&first).await.lender, lender.pubkey());
+    assert_eq!(env.active_loan(&second).await.lender, lender.pubkey());
+}
+
+#[tokio::test]
+async fn escrow_follows_the_bid() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let escrow = env.bid_escrow_address(&env.bid_address(&borrower.nft_mint, &lender.pubkey()));
+
+    env.place_escrowed_bid(&lender, &borrower.nft_mint, 2 * BID_PRICE, 1)
+        .await
+        .unwrap();
+    assert_eq!(env.token_balance(&escrow).await, 2 * BID_PRICE);
+
+    // lowering the bid refunds the difference
+    env.place_escrowed_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    assert_eq!(env.token_balance(&escrow).await, BID_PRICE);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - BID_PRICE
+    );
+
+    // back to an approval, the escrow is refunded
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    assert_eq!(env.token_balance(&escrow).await, 0);
+    assert_eq!(env.balance(&lender.pubkey(), &dai_mint).await, LENDER_DAI);
+    assert!(
+        !env.bid_account(&borrower.nft_mint, &lender.pubkey())
+            .await
+            .escrowed
+    );
+
+    // cancelling refunds the escrow and closes it
+    env.place_escrowed_bid(&lender, &borrower.nft_mint, 2 * BID_PRICE, 1)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - 2 * BID_PRICE
+    );
+    env.cancel_bid(&lender, &borrower.nft_mint).await.unwrap();
+
+    assert_eq!(env.balance(&lender.pubkey(), &dai_mint).await, LENDER_DAI);
+    assert!(env.account(&escrow).await.is_none());
+    let bid = env.bid_account(&borrower.nft_mint, &lender.pubkey()).await;
+    assert_eq!((bid.price, bid.qty, bid.escrowed), (0, 0, false));
+}
@@ -0,0 +1,278 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, Borrower, TestEnv, UNIT};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use taker::{allowlist, LoanTerms, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+// A loan against a collection bid that stays open and allows extensions of up to an hour,
+// returns the root of the allowlist and the proof of the NFT
+async fn start_loan(
+    env: &mut TestEnv,
+    expires_at: Option<i64>,
+) -> (Borrower, Keypair, [u8; 32], Vec<[u8; 32]>) {
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    let terms = LoanTerms {
+        interest_rate: 100,
+        max_loan_duration: 3 * SECONDS_PER_HOUR,
+        mortgage_rate: 5000,
+        max_extension: SECONDS_PER_HOUR,
+    };
+
+    let collection = vec![borrower.nft_mint, Pubkey::new_unique()];
+    let root = allowlist::root(&collection);
+    let proof = allowlist::proof(&collection, &borrower.nft_mint).unwrap();
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_collection_bid_with_terms(&lender, root, BID_PRICE, 2, Some(terms), expires_at)
+        .await
+        .unwrap();
+    let bid = env.collection_bid_address(&root, &lender.pubkey());
+    env.borrow_against(&borrower, &lender.pubkey(), &bid, BID_PRICE, proof.clone())
+        .await
+        .unwrap();
+
+    let wallet = borrower.wallet.pubkey();
+    let dai_mint = env.dai_mint;
+    env.mint_to(&dai_mint, &wallet, UNIT).await;
+
+    (borrower, lender, root, proof)
+}
+
+#[tokio::test]
+async fn lender_signs_an_extension() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let loan = env.active_loan(&borrower).await;
+    let borrowed = env.balance(&wallet, &dai_mint).await;
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+
+    // the pool terms leave extensions to the lender
+    assert_eq!(
+        env.extend_loan(&borrower, &lender.pubkey(), SECONDS_PER_HOUR, None)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::ExtensionNotAllowed)
+    );
+
+    let extended_at = env.unix_timestamp().await;
+    let (interest, fee) = loan
+        .calculate_interest_and_fee(extended_at - loan.started_at)
+        .unwrap();
+    assert!(interest > 0 && fee > 0);
+    env.mint_to(&dai_mint, &wallet, interest).await;
+
+    env.extend_loan(
+        &borrower,
+        &lender.pubkey(),
+        24 * SECONDS_PER_HOUR,
+        Some(&lender),
+    )
+    .await
+    .unwrap();
+
+    let extended = env.active_loan(&borrower).await;
+    assert_eq!(extended.started_at, extended_at);
+    assert_eq!(
+        extended.expired_at(),
+        loan.expired_at() + 24 * SECONDS_PER_HOUR
+    );
+    assert_eq!(extended.borrowed_amount, loan.borrowed_amount);
+
+    assert_eq!(env.balance(&wallet, &dai_mint).await, borrowed);
+    assert_eq!(env.fee_vault_balance().await, fee);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - BID_PRICE + interest - fee
+    );
+}
+
+#[tokio::test]
+async fn bid_policy_allows_extensions() {
+    let mut env = TestEnv::new().await;
+    let (borrower, lender, root, proof) = start_loan(&mut env, None).await;
+    let bid = env.collection_bid_address(&root, &lender.pubkey());
+    let loan = env.active_loan(&borrower).await;
+
+    // longer than the bid allows
+    assert_eq!(
+        env.extend_loan_against(
+            &borrower,
+            &lender.pubkey(),
+            &bid,
+            proof.clone(),
+            2 * SECONDS_PER_HOUR,
+            None
+        )
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::ExtensionNotAllowed)
+    );
+
+    // the loan would run longer than `max_loan_duration` from now
+    assert_eq!(
+        env.extend_loan_against(
+            &borrower,
+            &lender.pubkey(),
+            &bid,
+            proof.clone(),
+            SECONDS_PER_HOUR,
+            None
+        )
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::ExtensionNotAllowed)
+    );
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    env.extend_loan_against(
+        &borrower,
+        &lender.pubkey(),
+        &bid,
+        proof,
+        SECONDS_PER_HOUR / 2,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.active_loan(&borrower).await.expired_at(),
+        loan.expired_at() + SECONDS_PER_HOUR / 2
+    );
+}
+
+#[tokio::test]
+async fn filled_bid_allows_no_extension() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    let terms = LoanTerms {
+        interest_rate: 100,
+        max_loan_duration: 3 * SECONDS_PER_HOUR,
+        mortgage_rate: 5000,
+        max_extension: SECONDS_PER_HOUR,
+    };
+
+    // the loan takes the only NFT of a mint bid
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid_with_terms(&lender, &borrower.nft_mint, BID_PRICE, 1, Some(terms))
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    env.mint_to(&dai_mint, &wallet, UNIT).await;
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    assert_eq!(
+        env.extend_loan(&borrower, &lender.pubkey(), SECONDS_PER_HOUR / 2, None)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::ExtensionNotAllowed)
+    );
+}
+
+#[tokio::test]
+async fn cancelled_bid_allows_no_extension() {
+    let mut env = TestEnv::new().await;
+    let (borrower, lender, root, proof) = start_loan(&mut env, None).await;
+    let bid = env.collection_bid_address(&root, &lender.pubkey());
+    env.cancel_collection_bid(&lender, root).await.unwrap();
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    assert_eq!(
+        env.extend_loan_against(
+            &borrower,
+            &lender.pubkey(),
+            &bid,
+            proof,
+            SECONDS_PER_HOUR / 2,
+            None
+        )
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::ExtensionNotAllowed)
+    );
+}
+
+#[tokio::test]
+async fn expired_bid_allows_no_extension() {
+    let mut env = TestEnv::new().await;
+    let expires_at = env.unix_timestamp().await + SECONDS_PER_HOUR;
+    let (borrower, lender, root, proof) = start_loan(&mut env, Some(expires_at)).await;
+    let bid = env.collection_bid_address(&root, &lender.pubkey());
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    assert_eq!(
+        env.extend_loan_against(
+            &borrower,
+            &lender.pubkey(),
+            &bid,
+            proof,
+            SECONDS_PER_HOUR / 2,
+            None
+        )
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::BidExpired)
+    );
+}
+
+#[tokio::test]
+async fn expired_loan_cannot_be_extended() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    let terms = LoanTerms {
+        interest_rate: 100,
+        max_loan_duration: SECONDS_PER_HOUR,
+        mortgage_rate: 5000,
+        max_extension: SECONDS_PER_HOUR,
+    };
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid_with_terms(&lender, &borrower.nft_mint, BID_PRICE, 1, Some(terms))
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+
+    assert_eq!(
+        env.extend_loan(&borrower, &lender.pubkey(), SECONDS_PER_HOUR, Some(&lender))
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::LoanLiquidated)
+    );
+}
@@ -0,0 +1,154 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+use taker::{
+    DepositState, InterestCurve, KinkedInterest, LoanActiveState, LoanRepayedState, TakerError,
+};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+const KINKED: InterestCurve = InterestCurve::Kinked(KinkedInterest {
+    kink: 8000,
+    jump_rate: 10000,
+});
+
+#[tokio::test]
+async fn loan_keeps_the_interest_model_it_started_with() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_interest_model(KINKED).await.unwrap();
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    let loan = env.active_loan(&borrower).await;
+    assert_eq!(loan.interest_model, KINKED);
+    assert!(matches!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanActive(LoanActiveState {
+            interest_model: KINKED,
+            ..
+        })
+    ));
+
+    // changing the model of the pool does not touch the running loan
+    env.change_interest_model(InterestCurve::Simple)
+        .await
+        .unwrap();
+    env.advance_clock(SECONDS_PER_HOUR).await;
+
+    let duration = env.unix_timestamp().await - loan.started_at;
+    let (interest, fee) = loan.calculate_interest_and_fee(duration).unwrap();
+    // the only bid of the pool is filled, above the kink the rate rises
+    let mut simple = loan;
+    simple.interest_model = InterestCurve::Simple;
+    assert!(interest > simple.calculate_interest_and_fee(duration).unwrap().0);
+    env.mint_to(&dai_mint, &wallet, interest).await;
+
+    env.repay(&borrower).await.unwrap();
+
+    assert_eq!(env.balance(&wallet, &dai_mint).await, 0);
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanRepayed(LoanRepayedState {
+            tai_required_to_unlock: loan.borrowed_amount,
+            lender_withdrawable: BID_PRICE + interest - fee,
+            lender: lender.pubkey(),
+        })
+    );
+}
+
+#[tokio::test]
+async fn kinked_loans_are_priced_on_the_utilization_of_the_pool() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let first = env.new_borrower().await;
+    let second = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let other_lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_interest_model(KINKED).await.unwrap();
+    env.deposit_nft(&first).await.unwrap();
+    env.deposit_nft(&second).await.unwrap();
+    env.place_bid(&lender, &first.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.place_bid(&other_lender, &second.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    assert_eq!(env.pool_account().await.offered_amount, 2 * BID_PRICE);
+
+    // the bid of the other lender still offers its DAI, the pool is below the kink
+    env.borrow(&first, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let low = env.active_loan(&first).await;
+    let pool = env.pool_account().await;
+    assert_eq!(
+        (pool.outstanding_amount, pool.offered_amount),
+        (low.borrowed_amount, BID_PRICE)
+    );
+    assert_eq!(
+        low.utilization,
+        low.borrowed_amount * 10000 / (low.borrowed_amount + BID_PRICE)
+    );
+
+    env.borrow(&second, &other_lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let high = env.active_loan(&second).await;
+    assert_eq!(high.utilization, 10000);
+
+    // same principal and rate, the loan started in the busier pool accrues more
+    let duration = SECONDS_PER_HOUR;
+    assert!(
+        low.calculate_interest_and_fee(duration).unwrap().0
+            < high.calculate_interest_and_fee(duration).unwrap().0
+    );
+
+    // the principal goes back to the pool when the loan is repayed
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    let (interest, _) = low
+        .calculate_interest_and_fee(env.unix_timestamp().await - low.started_at)
+        .unwrap();
+    env.mint_to(&dai_mint, &first.wallet.pubkey(), interest)
+        .await;
+    env.repay(&first).await.unwrap();
+    let pool = env.pool_account().await;
+    assert_eq!(
+        (pool.outstanding_amount, pool.offered_amount),
+        (high.borrowed_amount, 0)
+    );
+}
+
+#[tokio::test]
+async fn invalid_interest_model_is_rejected() {
+    let mut env = TestEnv::new().await;
+
+    assert_eq!(
+        env.change_interest_model(InterestCurve::Kinked(KinkedInterest {
+            kink: 10000,
+            jump_rate: 5000,
+        }))
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::InvalidInterestModel)
+    );
+
+    env.change_interest_model(KINKED).await.unwrap();
+    assert_eq!(env.pool_account().await.interest_model, KINKED);
+}
@@ -0,0 +1,233 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use taker::{
+    DailyCompoundInterest, InterestCurve, InterestModel, KinkedInterest, SimpleInterest, TakerError,
+};
+
+const CASES: usize = 2000;
+const DAY: u64 = 24 * 60 * 60;
+const UNIT: u64 = 1_000_000_000;
+
+// principal, rate (bp per day) and duration drawn within what the pools lend
+fn loans(seed: u64, max_rate: u64, max_days: u64) -> impl Iterator<Item = (u64, u64, u64)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..CASES).map(move |_| {
+        (
+            rng.gen_range(1, 1_000_000 * UNIT),
+            rng.gen_range(0, max_rate + 1),
+            rng.gen_range(0, max_days * DAY + 1),
+        )
+    })
+}
+
+// The models round up, the floating point reference is only a few ulps away
+fn assert_close(actual: u64, reference: f64, case: impl std::fmt::Debug) {
+    let tolerance = (reference * 1e-9).max(2.);
+    assert!(
+        (actual as f64 - reference).abs() <= tolerance,
+        "{} too far from {} for {:?}",
+        actual,
+        reference,
+        case
+    );
+}
+
+fn reference_simple(principal: u64, rate: u64, duration: u64) -> u128 {
+    let numerator = principal as u128 * rate as u128 * duration as u128;
+    let denominator = DAY as u128 * 10000;
+    (numerator + denominator - 1) / denominator
+}
+
+fn reference_compound(principal: u64, rate: u64, duration: u64) -> f64 {
+    let rate = rate as f64 / 10000.;
+    let days = (duration / DAY) as i32;
+    let rest = (duration % DAY) as f64 / DAY as f64;
+    principal as f64 * ((1. + rate).powi(days) * (1. + rate * rest) - 1.)
+}
+
+fn reference_kinked(model: &KinkedInterest, rate: u64, utilization: u64) -> f64 {
+    let (rate, utilization) = (rate as f64 / 10000., utilization as f64);
+    let (kink, jump) = (model.kink as f64, model.jump_rate as f64 / 10000.);
+    if utilization <= kink {
+        rate * utilization / kink
+    } else {
+        rate * (1. + jump * (utilization - kink) / (10000. - kink))
+    }
+}
+
+#[test]
+fn simple_interest_matches_the_reference() {
+    for (principal, rate, duration) in loans(1, u16::MAX as u64, 365) {
+        let interest = SimpleInterest
+            .interest(principal, rate, duration, 0)
+            .unwrap();
+        assert_eq!(
+            interest as u128,
+            reference_simple(principal, rate, duration),
+            "{:?}",
+            (principal, rate, duration)
+        );
+    }
+}
+
+#[test]
+fn short_loans_on_small_principals_accrue_interest() {
+    // a minute on 0.000001 DAI at 1% per day used to truncate to zero
+    for model in &[
+        InterestCurve::Simple,
+        InterestCurve::DailyCompound,
+        InterestCurve::Kinked(KinkedInterest {
+            kink: 8000,
+            jump_rate: 10000,
+        }),
+    ] {
+        assert_eq!(model.interest(1000, 100, 60, 9000).unwrap(), 1);
+        assert_eq!(model.interest(1000, 100, 0, 9000).unwrap(), 0);
+        assert_eq!(model.interest(1000, 0, 60, 9000).unwrap(), 0);
+    }
+}
+
+#[test]
+fn simple_interest_does_not_depend_on_how_the_loan_is_settled() {
+    for (principal, rate, duration) in loans(2, 1000, 30) {
+        let split = duration / 3;
+        let whole = SimpleInterest
+            .interest(principal, rate, duration, 0)
+            .unwrap();
+        let settled = SimpleInterest.interest(principal, rate, split, 0).unwrap()
+            + SimpleInterest
+                .interest(principal, rate, duration - split, 0)
+                .unwrap();
+
+        // each settlement rounds up once
+        assert!(settled >= whole && settled <= whole + 1);
+    }
+}
+
+#[test]
+fn daily_compound_interest_matches_the_reference() {
+    for (principal, rate, duration) in loans(3, 300, 90) {
+        let interest = DailyCompoundInterest
+            .interest(principal, rate, duration, 0)
+            .unwrap();
+        assert_close(
+            interest,
+            reference_compound(principal, rate, duration),
+            (principal, rate, duration),
+        );
+
+        // compounding never earns less, and makes no difference within the first day
+        let simple = SimpleInterest
+            .interest(principal, rate, duration, 0)
+            .unwrap();
+        assert!(interest + 1 >= simple);
+        if duration <= DAY {
+            assert!(interest <= simple + 1);
+        }
+    }
+}
+
+#[test]
+fn long_compounding_loans_do_not_overflow() {
+    // the growth of these loans is past 340x, where the plain WAD product overflows
+    for (rate, days) in &[(100, 730), (500, 120), (1000, 100)] {
+        for principal in &[1, UNIT, 1_000_000 * UNIT] {
+            let duration = days * DAY + DAY / 2;
+            let interest = DailyCompoundInterest
+                .interest(*principal, *rate, duration, 0)
+                .unwrap();
+            assert_close(
+                interest,
+                reference_compound(*principal, *rate, duration),
+                (principal, rate, duration),
+            );
+        }
+    }
+}
+
+#[test]
+fn kinked_interest_matches_the_reference() {
+    let mut rng = StdRng::seed_from_u64(4);
+
+    for (principal, rate, duration) in loans(5, 1000, 90) {
+        let model = KinkedInterest {
+            kink: rng.gen_range(1, 10000),
+            jump_rate: rng.gen(),
+        };
+        let utilization = rng.gen_range(0, 10001);
+
+        let interest = model
+            .interest(principal, rate, duration, utilization)
+            .unwrap();
+        let reference = principal as f64
+            * reference_kinked(&model, rate, utilization)
+            * (duration as f64 / DAY as f64);
+        assert_close(
+            interest,
+            reference,
+            (principal, rate, duration, utilization, model),
+        );
+
+        // the rate of the loan applies at the kink
+        let at_kink = model
+            .interest(principal, rate, duration, model.kink as u64)
+            .unwrap();
+        let simple = SimpleInterest
+            .interest(principal, rate, duration, 0)
+            .unwrap();
+        assert!(at_kink <= simple && at_kink + 1 >= simple);
+    }
+}
+
+#[test]
+fn kinked_rate_rises_with_the_utilization() {
+    let mut rng = StdRng::seed_from_u64(6);
+
+    for _ in 0..CASES {
+        let model = KinkedInterest {
+            kink: rng.gen_range(1, 10000),
+            jump_rate: rng.gen(),
+        };
+        let rate = rng.gen_range(0, u16::MAX as u64 + 1);
+        let low = rng.gen_range(0, 10001);
+        let high = rng.gen_range(low, 10001);
+
+        assert!(model.rate_at(rate, low).unwrap() <= model.rate_at(rate, high).unwrap());
+    }
+}
+
+#[test]
+fn overflows_are_reported() {
+    assert_eq!(
+        DailyCompoundInterest
+            .interest(u64::MAX, u16::MAX as u64, 365 * DAY, 0)
+            .unwrap_err(),
+        TakerError::MathOverflow
+    );
+    assert_eq!(
+        SimpleInterest
+            .interest(u64::MAX, u16::MAX as u64, u32::MAX as u64, 0)
+            .unwrap_err(),
+        TakerError::MathOverflow
+    );
+}
+
+#[test]
+fn kink_must_be_inside_the_utilization_range() {
+    for kink in &[0, 10000] {
+        assert_eq!(
+            InterestCurve::Kinked(KinkedInterest {
+                kink: *kink,
+                jump_rate: 0
+            })
+            .validate()
+            .unwrap_err(),
+            TakerError::InvalidInterestModel
+        );
+    }
+    InterestCurve::Kinked(KinkedInterest {
+        kink: 8000,
+        jump_rate: 0,
+    })
+    .validate()
+    .unwrap();
+}
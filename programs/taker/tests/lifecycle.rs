@@ -0,0 +1,474 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{replace_account, taker_error, TestEnv, DECIMALS, UNIT};
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::get_associated_token_address;
+use taker::{
+    DepositState, InterestCurve, LoanActiveState, LoanRepayedState, NFTDeposit, TakerError,
+};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn deposit_and_withdraw_nft() {
+    let mut env = TestEnv::new().await;
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let incentive = env.pool_account().await.incentive;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::PendingLoan
+    );
+    assert_eq!(env.balance(&wallet, &borrower.nft_mint).await, 0);
+    assert_eq!(env.balance(&env.pool.clone(), &borrower.nft_mint).await, 1);
+    assert_eq!(env.balance(&wallet, &env.tkr_mint.clone()).await, incentive);
+
+    env.withdraw_nft(&borrower, false).await.unwrap();
+    assert_eq!(env.deposit_state(&borrower).await, DepositState::Withdrawn);
+    assert_eq!(env.balance(&wallet, &borrower.nft_mint).await, 1);
+    assert_eq!(env.balance(&env.pool.clone(), &borrower.nft_mint).await, 0);
+}
+
+#[tokio::test]
+async fn deposit_of_a_fungible_token_is_rejected() {
+    let mut env = TestEnv::new().await;
+    let mut borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+
+    let mint = env.create_mint(DECIMALS).await;
+    env.mint_to(&mint, &wallet, UNIT).await;
+    borrower.nft_mint = mint;
+    borrower.deposit = NFTDeposit::get_address(
+        &env.program_id,
+        &env.pool,
+        &mint,
+        &wallet,
+        &borrower.deposit_id,
+    );
+
+    assert_eq!(
+        env.deposit_nft(&borrower).await.unwrap_err(),
+        taker_error(TakerError::NotAnNFT)
+    );
+    assert_eq!(env.balance(&wallet, &mint).await, UNIT);
+}
+
+#[tokio::test]
+async fn borrow_repay_and_withdraw_locked_asset() {
+    let mut env = TestEnv::new().await;
+    let pool = env.pool;
+    let (tai_mint, dai_mint) = (env.tai_mint, env.dai_mint);
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+
+    let settings = env.pool_account().await;
+    let pool_tai = env.balance(&pool, &tai_mint).await;
+
+    // borrow
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    let borrowed = BID_PRICE * settings.mortgage_rate / 10000;
+    let started_at = env.unix_timestamp().await;
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanActive(LoanActiveState {
+            total_amount: BID_PRICE,
+            borrowed_amount: borrowed,
+            repayed_amount: 0,
+            started_at,
+            duration: settings.max_loan_duration,
+            lender: lender.pubkey(),
+            interest_rate: settings.interest_rate,
+            service_fee_rate: settings.service_fee_rate,
+            interest_model: InterestCurve::Simple,
+            grace_period: 0,
+            grace_penalty_rate: 0,
+            auction_duration: 0,
+            auction_start_rate: settings.auction_start_rate,
+            // the only bid of the pool is filled
+            utilization: 10000,
+        })
+    );
+    let loan = env.active_loan(&borrower).await;
+    assert_eq!(
+        env.bid_account(&borrower.nft_mint, &lender.pubkey())
+            .await
+            .qty,
+        0
+    );
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - BID_PRICE
+    );
+    assert_eq!(env.balance(&wallet, &dai_mint).await, borrowed);
+    assert_eq!(env.balance(&pool, &dai_mint).await, BID_PRICE - borrowed);
+    assert_eq!(env.balance(&lender.pubkey(), &tai_mint).await, borrowed);
+    assert_eq!(env.balance(&pool, &tai_mint).await, pool_tai - borrowed);
+
+    // repay an hour later, the borrower needs some extra DAI for the interest
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    let repayed_at = env.unix_timestamp().await;
+    let (interest, fee) = loan
+        .calculate_interest_and_fee(repayed_at - started_at)
+        .unwrap();
+    assert!(interest > 0 && fee > 0);
+    env.mint_to(&dai_mint, &wallet, interest).await;
+
+    env.repay(&borrower).await.unwrap();
+
+    let lender_income = interest - fee;
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanRepayed(LoanRepayedState {
+            tai_required_to_unlock: borrowed,
+            lender_withdrawable: BID_PRICE + lender_income,
+            lender: lender.pubkey(),
+        })
+    );
+    assert_eq!(env.balance(&wallet, &borrower.nft_mint).await, 1);
+    assert_eq!(env.balance(&pool, &borrower.nft_mint).await, 0);
+    assert_eq!(env.balance(&wallet, &dai_mint).await, 0);
+    assert_eq!(env.fee_vault_balance().await, fee);
+    assert_eq!(
+        env.balance(&pool, &dai_mint).await,
+        BID_PRICE + lender_income
+    );
+
+    // the lender returns the TAI and takes back the DAI plus the interest
+    env.withdraw_locked_asset(&borrower, &lender, false)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanCleared
+    );
+    assert_eq!(env.balance(&lender.pubkey(), &tai_mint).await, 0);
+    assert_eq!(env.balance(&pool, &tai_mint).await, pool_tai);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI + lender_income
+    );
+    assert_eq!(env.balance(&pool, &dai_mint).await, 0);
+
+    // the loan cannot be settled twice
+    env.skip_slots().await;
+    assert!(env
+        .withdraw_locked_asset(&borrower, &lender, false)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn repay_after_expiry_is_rejected() {
+    let mut env = TestEnv::new().await;
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_loan_settings(None, None, None, Some(SECONDS_PER_HOUR), None)
+        .await
+        .unwrap();
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    env.advance_clock(SECONDS_PER_HOUR + 1).await;
+
+    assert!(env.repay(&borrower).await.is_err());
+    assert!(matches!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanActive(_)
+    ));
+}
+
+#[tokio::test]
+async fn only_the_borrower_repays_into_the_pool() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+    let borrower = env.new_borrower().await;
+    let other = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.deposit_nft(&other).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    // another wallet repays the loan of the deposit to take the NFT
+    let mut impostor = other;
+    impostor.deposit = borrower.deposit;
+    assert_eq!(
+        env.repay(&impostor).await.unwrap_err(),
+        taker_error(TakerError::BorrowerMismatch)
+    );
+
+    // the repayment cannot be kept in the borrower's own account
+    let borrower_dai = env.create_ata(&borrower.wallet.pubkey(), &dai_mint).await;
+    assert_eq!(
+        env.repay_into(&borrower, &borrower_dai).await.unwrap_err(),
+        taker_error(TakerError::PoolAccountMismatch)
+    );
+    assert!(matches!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanActive(_)
+    ));
+
+    env.repay(&borrower).await.unwrap();
+    assert_eq!(
+        env.balance(&borrower.wallet.pubkey(), &borrower.nft_mint)
+            .await,
+        1
+    );
+}
+
+#[tokio::test]
+async fn liquidate_expired_loan() {
+    let mut env = TestEnv::new().await;
+    let pool = env.pool;
+    let (tai_mint, dai_mint) = (env.tai_mint, env.dai_mint);
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_loan_settings(None, None, None, Some(SECONDS_PER_HOUR), None)
+        .await
+        .unwrap();
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    let settings = env.pool_account().await;
+    let pool_tai = env.balance(&pool, &tai_mint).await;
+    let borrowed = BID_PRICE * settings.mortgage_rate / 10000;
+    let loan = env.active_loan(&borrower).await;
+
+    // the loan is still running
+    assert!(env.liquidate(&borrower, &lender, false).await.is_err());
+
+    env.advance_clock(SECONDS_PER_HOUR + 1).await;
+    env.liquidate(&borrower, &lender, false).await.unwrap();
+
+    // the fee is charged over the whole loan duration
+    let (_, fee) = loan
+        .calculate_interest_and_fee(settings.max_loan_duration)
+        .unwrap();
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanLiquidated
+    );
+    assert_eq!(env.balance(&lender.pubkey(), &borrower.nft_mint).await, 1);
+    assert_eq!(env.balance(&pool, &borrower.nft_mint).await, 0);
+    assert_eq!(env.balance(&lender.pubkey(), &tai_mint).await, 0);
+    assert_eq!(env.balance(&pool, &tai_mint).await, pool_tai + borrowed);
+    assert_eq!(env.fee_vault_balance().await, fee);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - borrowed - fee
+    );
+    assert_eq!(env.balance(&pool, &dai_mint).await, 0);
+}
+
+#[tokio::test]
+async fn only_the_lender_can_liquidate() {
+    let mut env = TestEnv::new().await;
+    let tai_mint = env.tai_mint;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_loan_settings(None, None, None, Some(SECONDS_PER_HOUR), None)
+        .await
+        .unwrap();
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    // another wallet holding enough TAI but not the loan note tries to seize the NFT
+    let borrowed = env.balance(&lender.pubkey(), &tai_mint).await;
+    let intruder = env.new_lender(LENDER_DAI).await;
+    env.mint_to(&tai_mint, &intruder.pubkey(), borrowed).await;
+
+    env.advance_clock(SECONDS_PER_HOUR + 1).await;
+
+    assert_eq!(
+        env.liquidate(&borrower, &intruder, false)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::NotLoanNoteHolder)
+    );
+    assert!(matches!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanActive(_)
+    ));
+    assert_eq!(env.balance(&intruder.pubkey(), &tai_mint).await, borrowed);
+
+    env.liquidate(&borrower, &lender, false).await.unwrap();
+    assert_eq!(env.balance(&lender.pubkey(), &borrower.nft_mint).await, 1);
+}
+
+#[tokio::test]
+async fn liquidate_takes_only_the_nft_of_the_deposit() {
+    let mut env = TestEnv::new().await;
+    let pool = env.pool;
+
+    let borrower = env.new_borrower().await;
+    let other = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_loan_settings(None, None, None, Some(SECONDS_PER_HOUR), None)
+        .await
+        .unwrap();
+    env.deposit_nft(&borrower).await.unwrap();
+    env.deposit_nft(&other).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let lender_other_nft = env.create_ata(&lender.pubkey(), &other.nft_mint).await;
+
+    env.advance_clock(SECONDS_PER_HOUR + 1).await;
+
+    // the expired loan is used to take the NFT of another deposit held by the pool
+    let mut ix = env.liquidate_instruction(&borrower, &lender, false).await;
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&pool, &borrower.nft_mint),
+        &get_associated_token_address(&pool, &other.nft_mint),
+    );
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&lender.pubkey(), &borrower.nft_mint),
+        &lender_other_nft,
+    );
+    assert_eq!(
+        env.process(&[ix], &[&lender]).await.unwrap_err(),
+        taker_error(TakerError::DepositMintMismatch)
+    );
+    assert_eq!(env.balance(&pool, &other.nft_mint).await, 1);
+
+    env.liquidate(&borrower, &lender, false).await.unwrap();
+    assert_eq!(env.balance(&lender.pubkey(), &borrower.nft_mint).await, 1);
+}
+
+#[tokio::test]
+async fn only_the_lender_can_withdraw_locked_asset() {
+    let mut env = TestEnv::new().await;
+    let (tai_mint, dai_mint) = (env.tai_mint, env.dai_mint);
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    env.repay(&borrower).await.unwrap();
+
+    // another wallet holding enough TAI but not the loan note tries to take the DAI of the lender
+    let borrowed = env.balance(&lender.pubkey(), &tai_mint).await;
+    let intruder = env.new_lender(0).await;
+    env.mint_to(&tai_mint, &intruder.pubkey(), borrowed).await;
+
+    assert_eq!(
+        env.withdraw_locked_asset(&borrower, &intruder, false)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::NotLoanNoteHolder)
+    );
+    assert_eq!(env.balance(&intruder.pubkey(), &dai_mint).await, 0);
+    assert!(matches!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanRepayed(_)
+    ));
+
+    env.withdraw_locked_asset(&borrower, &lender, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanCleared
+    );
+}
+
+#[tokio::test]
+async fn locked_asset_is_paid_from_the_pool_account() {
+    let mut env = TestEnv::new().await;
+    let (pool, dai_mint) = (env.pool, env.dai_mint);
+
+    let borrower = env.new_borrower().await;
+    let other = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let victim = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.deposit_nft(&other).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.place_bid(&victim, &other.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    env.repay(&borrower).await.unwrap();
+
+    // the pool is the delegate of the victim's DAI, which would pay the lender
+    let mut ix = env
+        .withdraw_locked_asset_instruction(&borrower, &lender, false)
+        .await;
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&pool, &dai_mint),
+        &get_associated_token_address(&victim.pubkey(), &dai_mint),
+    );
+    assert_eq!(
+        env.process(&[ix], &[&lender]).await.unwrap_err(),
+        taker_error(TakerError::PoolAccountMismatch)
+    );
+    assert_eq!(env.balance(&victim.pubkey(), &dai_mint).await, LENDER_DAI);
+
+    env.withdraw_locked_asset(&borrower, &lender, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanCleared
+    );
+}
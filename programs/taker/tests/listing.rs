@@ -0,0 +1,156 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{replace_account, taker_error, TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::get_associated_token_address;
+use taker::{DepositState, TakerError};
+
+const LISTING_AMOUNT: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[tokio::test]
+async fn lender_funds_the_listing() {
+    let mut env = TestEnv::new().await;
+    let (tai_mint, dai_mint) = (env.tai_mint, env.dai_mint);
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_listing(&borrower, LISTING_AMOUNT, 200, SECONDS_PER_DAY)
+        .await
+        .unwrap();
+
+    let listing = env.listing_address(&borrower);
+    let content: taker::NFTListing = env.anchor_account(&listing).await;
+    assert_eq!(content.deposit, borrower.deposit);
+    assert_eq!(content.borrower, wallet);
+    assert_eq!(
+        (
+            content.amount,
+            content.max_interest_rate,
+            content.loan_duration
+        ),
+        (LISTING_AMOUNT, 200, SECONDS_PER_DAY)
+    );
+
+    assert_eq!(
+        env.fund_listing(&lender, &borrower, 300).await.unwrap_err(),
+        taker_error(TakerError::ListingRateTooHigh)
+    );
+
+    // the lender signs, no approval is needed
+    env.fund_listing(&lender, &borrower, 150).await.unwrap();
+
+    let settings = env.pool_account().await;
+    let borrowed_amount = LISTING_AMOUNT * settings.mortgage_rate / 10000;
+    let loan = env.active_loan(&borrower).await;
+    assert_eq!(loan.lender, lender.pubkey());
+    assert_eq!(loan.total_amount, LISTING_AMOUNT);
+    assert_eq!(loan.borrowed_amount, borrowed_amount);
+    assert_eq!(loan.interest_rate, 150);
+    assert_eq!(loan.duration, SECONDS_PER_DAY);
+
+    assert_eq!(env.balance(&wallet, &dai_mint).await, borrowed_amount);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - LISTING_AMOUNT
+    );
+    assert_eq!(
+        env.balance(&lender.pubkey(), &tai_mint).await,
+        borrowed_amount
+    );
+    assert!(env.account(&listing).await.is_none());
+}
+
+#[tokio::test]
+async fn only_the_borrower_manages_the_listing() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    let other = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    assert_eq!(
+        env.place_listing(&borrower, LISTING_AMOUNT, 200, 0)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::InvalidLoanTerms)
+    );
+    env.place_listing(&borrower, LISTING_AMOUNT, 200, SECONDS_PER_DAY)
+        .await
+        .unwrap();
+
+    // another wallet cancels with the listing of the deposit
+    let mut impostor = other;
+    impostor.deposit = borrower.deposit;
+    assert_eq!(
+        env.cancel_listing(&impostor).await.unwrap_err(),
+        taker_error(TakerError::BorrowerMismatch)
+    );
+
+    env.cancel_listing(&borrower).await.unwrap();
+    let listing = env.listing_address(&borrower);
+    assert!(env.account(&listing).await.is_none());
+    assert!(env.fund_listing(&lender, &borrower, 150).await.is_err());
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::PendingLoan
+    );
+
+    // a deposit already lent against cannot be listed
+    env.place_bid(&lender, &borrower.nft_mint, LISTING_AMOUNT, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), LISTING_AMOUNT)
+        .await
+        .unwrap();
+    env.skip_slots().await;
+    assert_eq!(
+        env.place_listing(&borrower, LISTING_AMOUNT, 200, SECONDS_PER_DAY)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::BorrowAlreadyStarted)
+    );
+}
+
+#[tokio::test]
+async fn dai_account_of_another_lender_does_not_fund_the_listing() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let victim = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_listing(&borrower, LISTING_AMOUNT, 200, SECONDS_PER_DAY)
+        .await
+        .unwrap();
+    env.place_bid(&victim, &borrower.nft_mint, LISTING_AMOUNT, 1)
+        .await
+        .unwrap();
+
+    // the pool is the delegate of the victim's DAI, which would pay the borrower
+    let mut ix = env.fund_listing_instruction(&lender.pubkey(), &borrower, 150);
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&env.pool, &dai_mint),
+        &get_associated_token_address(&victim.pubkey(), &dai_mint),
+    );
+    assert_eq!(
+        env.process(&[ix], &[&lender]).await.unwrap_err(),
+        taker_error(TakerError::PoolAccountMismatch)
+    );
+
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::PendingLoan
+    );
+    assert_eq!(env.balance(&victim.pubkey(), &dai_mint).await, LENDER_DAI);
+}
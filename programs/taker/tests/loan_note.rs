@@ -0,0 +1,155 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+use taker::{DepositState, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn note_holder_withdraws_the_repayed_loan() {
+    let mut env = TestEnv::new().await;
+    let (tai_mint, dai_mint) = (env.tai_mint, env.dai_mint);
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let buyer = env.new_lender(0).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    let note = env.loan_note_address(&borrower, &lender.pubkey());
+    assert_eq!(env.balance(&lender.pubkey(), &note).await, 1);
+
+    // the lender sells the loan with the TAI it unlocks
+    let borrowed = env.balance(&lender.pubkey(), &tai_mint).await;
+    env.transfer(&note, &lender, &buyer.pubkey(), 1).await;
+    env.transfer(&tai_mint, &lender, &buyer.pubkey(), borrowed)
+        .await;
+
+    env.repay(&borrower).await.unwrap();
+    let withdrawable = match env.deposit_state(&borrower).await {
+        DepositState::LoanRepayed(repay) => repay.lender_withdrawable,
+        state => panic!("the loan is not repayed: {:?}", state),
+    };
+
+    assert_eq!(
+        env.withdraw_locked_asset(&borrower, &lender, false)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::NotLoanNoteHolder)
+    );
+
+    env.withdraw_locked_asset(&borrower, &buyer, false)
+        .await
+        .unwrap();
+    assert_eq!(env.balance(&buyer.pubkey(), &dai_mint).await, withdrawable);
+    assert_eq!(env.balance(&buyer.pubkey(), &tai_mint).await, 0);
+    assert_eq!(env.balance(&buyer.pubkey(), &note).await, 0);
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanCleared
+    );
+}
+
+#[tokio::test]
+async fn note_holder_liquidates_the_expired_loan() {
+    let mut env = TestEnv::new().await;
+    let tai_mint = env.tai_mint;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let buyer = env.new_lender(0).await;
+
+    env.change_loan_settings(None, None, None, Some(SECONDS_PER_HOUR), None)
+        .await
+        .unwrap();
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    let note = env.loan_note_address(&borrower, &lender.pubkey());
+    let borrowed = env.balance(&lender.pubkey(), &tai_mint).await;
+    env.transfer(&note, &lender, &buyer.pubkey(), 1).await;
+    env.transfer(&tai_mint, &lender, &buyer.pubkey(), borrowed)
+        .await;
+
+    env.advance_clock(SECONDS_PER_HOUR + 1).await;
+    env.liquidate(&borrower, &buyer, false).await.unwrap();
+
+    assert_eq!(env.balance(&buyer.pubkey(), &borrower.nft_mint).await, 1);
+    assert_eq!(env.balance(&buyer.pubkey(), &note).await, 0);
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanLiquidated
+    );
+}
+
+#[tokio::test]
+async fn note_holder_extends_the_loan() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+    let buyer = env.new_lender(0).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let loan = env.active_loan(&borrower).await;
+
+    let note = env.loan_note_address(&borrower, &lender.pubkey());
+    env.transfer(&note, &lender, &buyer.pubkey(), 1).await;
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+
+    // the lender sold the loan, it no longer agrees to extensions
+    assert_eq!(
+        env.extend_loan(&borrower, &lender.pubkey(), SECONDS_PER_HOUR, Some(&lender))
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::NotLoanNoteHolder)
+    );
+
+    let extended_at = env.unix_timestamp().await;
+    let (interest, fee) = loan
+        .calculate_interest_and_fee(extended_at - loan.started_at)
+        .unwrap();
+    env.mint_to(&dai_mint, &wallet, interest).await;
+
+    env.extend_loan(&borrower, &buyer.pubkey(), SECONDS_PER_HOUR, Some(&buyer))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.active_loan(&borrower).await.expired_at(),
+        loan.expired_at() + SECONDS_PER_HOUR
+    );
+    assert_eq!(
+        env.balance(&buyer.pubkey(), &dai_mint).await,
+        interest - fee
+    );
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - BID_PRICE
+    );
+}
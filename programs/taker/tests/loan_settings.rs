@@ -0,0 +1,120 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, TestEnv};
+use taker::{LoanSettings, SettingsCaps, TakerError};
+
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn settings_out_of_bounds_are_rejected() {
+    let mut env = TestEnv::new().await;
+
+    let cases: &[(_, TakerError)] = &[
+        (
+            (None, None, Some(10001), None),
+            TakerError::InvalidMortgageRate,
+        ),
+        ((None, None, Some(0), None), TakerError::InvalidMortgageRate),
+        (
+            (Some(10001), None, None, None),
+            TakerError::InvalidServiceFeeRate,
+        ),
+        (
+            (None, Some(-1), None, None),
+            TakerError::InvalidLoanDuration,
+        ),
+        ((None, Some(0), None, None), TakerError::InvalidLoanDuration),
+    ];
+    for ((service_fee_rate, max_loan_duration, mortgage_rate, interest_rate), err) in cases {
+        env.skip_slots().await;
+        assert_eq!(
+            env.change_loan_settings(
+                None,
+                *interest_rate,
+                *service_fee_rate,
+                *max_loan_duration,
+                *mortgage_rate
+            )
+            .await
+            .unwrap_err(),
+            taker_error(*err)
+        );
+    }
+
+    env.change_loan_settings(
+        None,
+        Some(200),
+        Some(1000),
+        Some(SECONDS_PER_HOUR),
+        Some(5000),
+    )
+    .await
+    .unwrap();
+    let pool = env.pool_account().await;
+    assert_eq!(
+        pool.loan_settings(),
+        LoanSettings {
+            incentive: pool.incentive,
+            interest_rate: 200,
+            service_fee_rate: 1000,
+            max_loan_duration: SECONDS_PER_HOUR,
+            mortgage_rate: 5000,
+            ..pool.loan_settings()
+        }
+    );
+}
+
+#[tokio::test]
+async fn owner_caps_the_settings() {
+    let mut env = TestEnv::new().await;
+    let caps = SettingsCaps {
+        max_interest_rate: 500,
+        ..SettingsCaps::UNBOUNDED
+    };
+
+    // caps above the bounds of the protocol are rejected
+    assert_eq!(
+        env.change_settings_caps(
+            SettingsCaps {
+                max_mortgage_rate: 10001,
+                ..caps
+            },
+            0
+        )
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::InvalidMortgageRate)
+    );
+    assert_eq!(
+        env.change_settings_caps(caps, -1).await.unwrap_err(),
+        taker_error(TakerError::InvalidSettingsDelay)
+    );
+
+    env.change_settings_caps(caps, 0).await.unwrap();
+    assert_eq!(
+        env.change_loan_settings(None, Some(600), None, None, None)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::SettingAboveCap)
+    );
+    env.change_loan_settings(None, Some(400), None, None, None)
+        .await
+        .unwrap();
+
+    // the current settings must stay within the caps
+    env.skip_slots().await;
+    assert_eq!(
+        env.change_settings_caps(
+            SettingsCaps {
+                max_interest_rate: 300,
+                ..caps
+            },
+            0
+        )
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::SettingAboveCap)
+    );
+}
@@ -0,0 +1,125 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+use taker::{LoanTerms, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn loan_follows_the_terms_of_the_bid() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    let terms = LoanTerms {
+        interest_rate: 300,
+        max_loan_duration: 2 * SECONDS_PER_HOUR,
+        mortgage_rate: 5000,
+        max_extension: 0,
+    };
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid_with_terms(&lender, &borrower.nft_mint, BID_PRICE, 1, Some(terms))
+        .await
+        .unwrap();
+    assert_eq!(
+        env.bid_account(&borrower.nft_mint, &lender.pubkey())
+            .await
+            .terms,
+        terms
+    );
+
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    let loan = env.active_loan(&borrower).await;
+    assert_eq!(loan.borrowed_amount, BID_PRICE / 2);
+    assert_eq!(loan.duration, terms.max_loan_duration);
+    assert_eq!(loan.interest_rate, terms.interest_rate);
+    assert_eq!(
+        loan.service_fee_rate,
+        env.pool_account().await.service_fee_rate
+    );
+    assert_eq!(env.balance(&wallet, &dai_mint).await, BID_PRICE / 2);
+
+    // the pool settings change after the loan started, the pool duration alone would expire it
+    env.change_loan_settings(
+        None,
+        Some(10000),
+        Some(9000),
+        Some(SECONDS_PER_HOUR),
+        Some(9000),
+    )
+    .await
+    .unwrap();
+    env.advance_clock(SECONDS_PER_HOUR + 1).await;
+
+    // the loan settles with the rates it started with
+    let (interest, fee) = loan
+        .calculate_interest_and_fee(env.unix_timestamp().await - loan.started_at)
+        .unwrap();
+    assert!(fee > 0);
+    env.mint_to(&dai_mint, &wallet, interest).await;
+
+    env.repay(&borrower).await.unwrap();
+
+    assert_eq!(env.balance(&wallet, &dai_mint).await, 0);
+    assert_eq!(env.fee_vault_balance().await, fee);
+}
+
+#[tokio::test]
+async fn invalid_terms_are_rejected() {
+    let mut env = TestEnv::new().await;
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    for terms in &[
+        LoanTerms {
+            interest_rate: 100,
+            max_loan_duration: 0,
+            mortgage_rate: 5000,
+            max_extension: 0,
+        },
+        LoanTerms {
+            interest_rate: 100,
+            max_loan_duration: SECONDS_PER_HOUR,
+            mortgage_rate: 0,
+            max_extension: 0,
+        },
+        LoanTerms {
+            interest_rate: 100,
+            max_loan_duration: SECONDS_PER_HOUR,
+            mortgage_rate: 10001,
+            max_extension: 0,
+        },
+        LoanTerms {
+            interest_rate: 100,
+            max_loan_duration: SECONDS_PER_HOUR,
+            mortgage_rate: 5000,
+            max_extension: -1,
+        },
+        // 10% per day over 101 days is above LoanTerms::MAX_LOAN_RATE
+        LoanTerms {
+            interest_rate: 1000,
+            max_loan_duration: 101 * 24 * SECONDS_PER_HOUR,
+            mortgage_rate: 5000,
+            max_extension: 0,
+        },
+    ] {
+        assert_eq!(
+            env.place_bid_with_terms(&lender, &borrower.nft_mint, BID_PRICE, 1, Some(*terms))
+                .await
+                .unwrap_err(),
+            taker_error(TakerError::InvalidLoanTerms)
+        );
+    }
+}
@@ -0,0 +1,215 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{clone_keypair, taker_error, TestEnv, UNIT};
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use taker::{allowlist, DepositState, NFTPool, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn users_can_exit_a_paused_pool() {
+    let mut env = TestEnv::new().await;
+    let owner = clone_keypair(&env.owner);
+    let dai_mint = env.dai_mint;
+
+    let pauser = env.new_wallet().await;
+    let stranger = env.new_wallet().await;
+    let borrower = env.new_borrower().await;
+    let pending = env.new_borrower().await;
+    let late = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_roles(&owner, None, None, Some(pauser.pubkey()))
+        .await
+        .unwrap();
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.deposit_nft(&pending).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.place_bid(&lender, &pending.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.set_pause(&stranger, NFTPool::PAUSE_ALL)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::NotAuhorized)
+    );
+    assert_eq!(
+        env.set_pause(&pauser, NFTPool::PAUSE_ALL + 1)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::InvalidPauseFlags)
+    );
+    env.set_pause(&pauser, NFTPool::PAUSE_ALL).await.unwrap();
+    assert_eq!(env.pool_account().await.paused, NFTPool::PAUSE_ALL);
+
+    assert_eq!(
+        env.deposit_nft(&late).await.unwrap_err(),
+        taker_error(TakerError::ProtocolPaused)
+    );
+    assert_eq!(
+        env.place_bid(&lender, &late.nft_mint, BID_PRICE, 1)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::ProtocolPaused)
+    );
+    assert_eq!(
+        env.borrow(&pending, &lender.pubkey(), BID_PRICE)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::ProtocolPaused)
+    );
+
+    // repaying and withdrawing stay open
+    let loan = env.active_loan(&borrower).await;
+    let (interest, _) = loan
+        .calculate_interest_and_fee(env.unix_timestamp().await - loan.started_at)
+        .unwrap();
+    env.mint_to(&dai_mint, &borrower.wallet.pubkey(), interest)
+        .await;
+    env.repay(&borrower).await.unwrap();
+    env.withdraw_nft(&pending, false).await.unwrap();
+    assert!(matches!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanRepayed(_)
+    ));
+    assert_eq!(env.deposit_state(&pending).await, DepositState::Withdrawn);
+
+    // the lender gets the DAI back once the owner resumes the pool
+    assert_eq!(
+        env.withdraw_locked_asset(&borrower, &lender, false)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::ProtocolPaused)
+    );
+    env.set_pause(&owner, 0).await.unwrap();
+    env.skip_slots().await;
+    env.withdraw_locked_asset(&borrower, &lender, false)
+        .await
+        .unwrap();
+    env.deposit_nft(&late).await.unwrap();
+}
+
+#[tokio::test]
+async fn lenders_get_their_dai_back_from_a_paused_pool() {
+    let mut env = TestEnv::new().await;
+    let owner = clone_keypair(&env.owner);
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let other = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let new_lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    env.place_bid(&new_lender, &borrower.nft_mint, 2 * BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    env.refinance(&borrower.wallet, &borrower, &new_lender.pubkey())
+        .await
+        .unwrap();
+
+    env.place_bid(&lender, &other.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    let root = allowlist::root(&[other.nft_mint, Pubkey::new_unique()]);
+    env.place_collection_bid(&lender, root, BID_PRICE, 1)
+        .await
+        .unwrap();
+
+    env.set_pause(&owner, NFTPool::PAUSE_ALL).await.unwrap();
+
+    env.cancel_bid(&lender, &other.nft_mint).await.unwrap();
+    env.cancel_collection_bid(&lender, root).await.unwrap();
+    assert_eq!(
+        env.bid_account(&other.nft_mint, &lender.pubkey()).await.qty,
+        0
+    );
+
+    let before = env.balance(&lender.pubkey(), &dai_mint).await;
+    env.withdraw_refinance_payoff(&borrower, &lender, &lender.pubkey())
+        .await
+        .unwrap();
+    assert!(env.balance(&lender.pubkey(), &dai_mint).await > before);
+}
+
+#[tokio::test]
+async fn pause_flags_are_granular() {
+    let mut env = TestEnv::new().await;
+    let owner = clone_keypair(&env.owner);
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.set_pause(&owner, NFTPool::PAUSE_BORROW).await.unwrap();
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::ProtocolPaused)
+    );
+
+    env.set_pause(&owner, 0).await.unwrap();
+    env.skip_slots().await;
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn fees_and_auto_refinance_can_be_paused() {
+    let mut env = TestEnv::new().await;
+    let owner = clone_keypair(&env.owner);
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    env.create_ata(&owner.pubkey(), &dai_mint).await;
+    env.deposit_nft(&borrower).await.unwrap();
+
+    env.set_pause(
+        &owner,
+        NFTPool::PAUSE_COLLECT_FEES | NFTPool::PAUSE_SET_AUTO_REFINANCE,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.collect_fees(&owner).await.unwrap_err(),
+        taker_error(TakerError::ProtocolPaused)
+    );
+    assert_eq!(
+        env.set_auto_refinance(&borrower, true).await.unwrap_err(),
+        taker_error(TakerError::ProtocolPaused)
+    );
+
+    env.set_pause(&owner, 0).await.unwrap();
+    env.skip_slots().await;
+    env.collect_fees(&owner).await.unwrap();
+    env.set_auto_refinance(&borrower, true).await.unwrap();
+
+    // the borrower can still opt out of a paused pool
+    env.set_pause(&owner, NFTPool::PAUSE_ALL).await.unwrap();
+    env.set_auto_refinance(&borrower, false).await.unwrap();
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::__private::ErrorCode;
+use common::{anchor_error, clone_keypair, TestEnv, UNIT};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn ownership_moves_once_accepted() {
+    let mut env = TestEnv::new().await;
+    let owner = clone_keypair(&env.owner);
+    let new_owner = Keypair::new();
+    let stranger = Keypair::new();
+    env.airdrop(&new_owner.pubkey(), UNIT).await;
+    env.airdrop(&stranger.pubkey(), UNIT).await;
+
+    assert_eq!(
+        env.propose_owner(&stranger, &stranger.pubkey())
+            .await
+            .unwrap_err(),
+        anchor_error(ErrorCode::ConstraintHasOne)
+    );
+
+    // nothing is pending yet
+    assert_eq!(
+        env.accept_owner(&new_owner).await.unwrap_err(),
+        anchor_error(ErrorCode::ConstraintHasOne)
+    );
+
+    env.propose_owner(&owner, &new_owner.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(env.pool_account().await.owner, owner.pubkey());
+    assert_eq!(
+        env.accept_owner(&stranger).await.unwrap_err(),
+        anchor_error(ErrorCode::ConstraintHasOne)
+    );
+
+    // the same transaction as the rejected one above, it must not be deduplicated
+    env.skip_slots().await;
+    env.accept_owner(&new_owner).await.unwrap();
+
+    let pool = env.pool_account().await;
+    assert_eq!(pool.owner, new_owner.pubkey());
+    assert_eq!(pool.pending_owner, Pubkey::default());
+
+    // the previous owner lost its rights, the other roles did not move
+    assert_eq!(
+        env.change_roles(&owner, None, Some(owner.pubkey()), None)
+            .await
+            .unwrap_err(),
+        anchor_error(ErrorCode::ConstraintHasOne)
+    );
+    assert_eq!(pool.settings_admin, owner.pubkey());
+    assert_eq!(pool.fee_recipient, owner.pubkey());
+}
+
+#[tokio::test]
+async fn fees_go_to_the_fee_recipient() {
+    let mut env = TestEnv::new().await;
+    let owner = clone_keypair(&env.owner);
+    let dai_mint = env.dai_mint;
+
+    let admin = env.new_wallet().await;
+    let treasury = env.new_wallet().await;
+    env.create_ata(&treasury.pubkey(), &dai_mint).await;
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_roles(
+        &owner,
+        Some(treasury.pubkey()),
+        Some(admin.pubkey()),
+        Some(admin.pubkey()),
+    )
+    .await
+    .unwrap();
+
+    let pool = env.pool_account().await;
+    assert_eq!(
+        (pool.fee_recipient, pool.settings_admin, pool.pauser),
+        (treasury.pubkey(), admin.pubkey(), admin.pubkey())
+    );
+
+    // the settings are the admin's now
+    assert_eq!(
+        env.change_loan_settings(None, Some(200), None, None, None)
+            .await
+            .unwrap_err(),
+        anchor_error(ErrorCode::ConstraintHasOne)
+    );
+    env.settings_admin = clone_keypair(&admin);
+    env.change_loan_settings(None, Some(200), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(env.pool_account().await.interest_rate, 200);
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let loan = env.active_loan(&borrower).await;
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    let (interest, fee) = loan
+        .calculate_interest_and_fee(env.unix_timestamp().await - loan.started_at)
+        .unwrap();
+    env.mint_to(&dai_mint, &wallet, interest).await;
+
+    // the fee waits in the fee vault of the pool
+    env.repay(&borrower).await.unwrap();
+    assert_eq!(env.fee_vault_balance().await, fee);
+
+    // the owner does not collect the fees anymore
+    assert_eq!(
+        env.collect_fees(&owner).await.unwrap_err(),
+        anchor_error(ErrorCode::ConstraintHasOne)
+    );
+    env.collect_fees(&treasury).await.unwrap();
+
+    assert_eq!(env.balance(&treasury.pubkey(), &dai_mint).await, fee);
+    assert_eq!(env.balance(&owner.pubkey(), &dai_mint).await, 0);
+    assert_eq!(env.fee_vault_balance().await, 0);
+    let pool = env.pool_account().await;
+    assert_eq!((pool.fees_accrued, pool.fees_collected), (fee, fee));
+
+    // nothing left to sweep
+    env.skip_slots().await;
+    env.collect_fees(&treasury).await.unwrap();
+    assert_eq!(env.balance(&treasury.pubkey(), &dai_mint).await, fee);
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{replace_account, taker_error, TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::get_associated_token_address;
+use taker::{DepositState, LoanTerms, RefinancePayoff, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn new_lender_pays_off_the_loan() {
+    let mut env = TestEnv::new().await;
+    let (tai_mint, dai_mint) = (env.tai_mint, env.dai_mint);
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let new_lender = env.new_lender(LENDER_DAI).await;
+    let victim = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let loan = env.active_loan(&borrower).await;
+
+    env.place_bid(&new_lender, &borrower.nft_mint, 2 * BID_PRICE, 1)
+        .await
+        .unwrap();
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    let refinanced_at = env.unix_timestamp().await;
+    let (interest, fee) = loan
+        .calculate_interest_and_fee(refinanced_at - loan.started_at)
+        .unwrap();
+    assert!(interest > 0 && fee > 0);
+
+    let wallet = &borrower.wallet;
+    env.refinance(wallet, &borrower, &new_lender.pubkey())
+        .await
+        .unwrap();
+
+    // the debt of the borrower moves to the new lender and the loan starts over
+    let payoff = loan.borrowed_amount + interest;
+    let refinanced = env.active_loan(&borrower).await;
+    assert_eq!(refinanced.lender, new_lender.pubkey());
+    assert_eq!(
+        (refinanced.total_amount, refinanced.borrowed_amount),
+        (payoff, payoff)
+    );
+    assert_eq!(refinanced.started_at, refinanced_at);
+    assert_eq!(
+        env.balance(&new_lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - payoff
+    );
+    assert_eq!(env.balance(&new_lender.pubkey(), &tai_mint).await, payoff);
+    let note = env.loan_note_address(&borrower, &new_lender.pubkey());
+    assert_eq!(env.balance(&new_lender.pubkey(), &note).await, 1);
+    assert_eq!(env.fee_vault_balance().await, fee);
+
+    // the previous lender gets the loan back with its interest by returning the TAI
+    let address = env.refinance_payoff_address(&borrower, &lender.pubkey());
+    let content: RefinancePayoff = env.anchor_account(&address).await;
+    assert_eq!(content.lender, lender.pubkey());
+    assert_eq!(content.tai_required_to_unlock, loan.borrowed_amount);
+    assert_eq!(content.lender_withdrawable, BID_PRICE + interest - fee);
+
+    // the pool is the delegate of the victim's DAI, which would pay the lender
+    env.place_bid(&victim, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    let mut ix =
+        env.withdraw_refinance_payoff_instruction(&borrower, &lender.pubkey(), &lender.pubkey());
+    replace_account(
+        &mut ix,
+        &get_associated_token_address(&env.pool, &dai_mint),
+        &get_associated_token_address(&victim.pubkey(), &dai_mint),
+    );
+    assert_eq!(
+        env.process(&[ix], &[&lender]).await.unwrap_err(),
+        taker_error(TakerError::PoolAccountMismatch)
+    );
+    assert_eq!(env.balance(&victim.pubkey(), &dai_mint).await, LENDER_DAI);
+
+    env.withdraw_refinance_payoff(&borrower, &lender, &lender.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI + interest - fee
+    );
+    assert_eq!(env.balance(&lender.pubkey(), &tai_mint).await, 0);
+    assert!(env.account(&address).await.is_none());
+}
+
+#[tokio::test]
+async fn auto_refinance_needs_the_opt_in_and_a_better_rate() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let new_lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.refinance(&lender, &borrower, &lender.pubkey())
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::RefinanceToSameLender)
+    );
+
+    env.place_bid(&new_lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.refinance(&new_lender, &borrower, &new_lender.pubkey())
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::RefinanceNotAllowed)
+    );
+
+    // the bid has the rate of the loan
+    env.set_auto_refinance(&borrower, true).await.unwrap();
+    env.skip_slots().await;
+    assert_eq!(
+        env.refinance(&new_lender, &borrower, &new_lender.pubkey())
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::RefinanceTermsNotBetter)
+    );
+
+    let settings = env.pool_account().await;
+    let terms = LoanTerms {
+        interest_rate: settings.interest_rate / 2,
+        max_loan_duration: settings.max_loan_duration,
+        mortgage_rate: settings.mortgage_rate,
+        max_extension: 0,
+    };
+    env.place_bid_with_terms(&new_lender, &borrower.nft_mint, BID_PRICE, 1, Some(terms))
+        .await
+        .unwrap();
+    env.skip_slots().await;
+    env.refinance(&new_lender, &borrower, &new_lender.pubkey())
+        .await
+        .unwrap();
+
+    let loan = env.active_loan(&borrower).await;
+    assert_eq!(loan.lender, new_lender.pubkey());
+    assert_eq!(loan.interest_rate, terms.interest_rate);
+}
+
+#[tokio::test]
+async fn defaulted_refinanced_loan_is_liquidated() {
+    let mut env = TestEnv::new().await;
+    let dai_mint = env.dai_mint;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+    let new_lender = env.new_lender(LENDER_DAI).await;
+
+    env.change_loan_settings(None, None, None, Some(SECONDS_PER_HOUR), None)
+        .await
+        .unwrap();
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    env.place_bid(&new_lender, &borrower.nft_mint, 2 * BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.refinance(&borrower.wallet, &borrower, &new_lender.pubkey())
+        .await
+        .unwrap();
+
+    // the refinanced loan has no margin in the pool to take the fee from
+    let loan = env.active_loan(&borrower).await;
+    assert_eq!(loan.pool_holdings(), 0);
+    let paid = LENDER_DAI - env.balance(&new_lender.pubkey(), &dai_mint).await;
+
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+    env.liquidate(&borrower, &new_lender, false).await.unwrap();
+
+    assert_eq!(
+        env.balance(&new_lender.pubkey(), &borrower.nft_mint).await,
+        1
+    );
+    assert_eq!(
+        env.balance(&new_lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - paid
+    );
+    assert_eq!(
+        env.deposit_state(&borrower).await,
+        DepositState::LoanLiquidated
+    );
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{taker_error, TestEnv, UNIT};
+use solana_sdk::signature::Signer;
+use taker::{LoanTerms, TakerError};
+
+const BID_PRICE: u64 = 100 * UNIT;
+const LENDER_DAI: u64 = 1000 * UNIT;
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn repay_in_two_steps() {
+    let mut env = TestEnv::new().await;
+    let pool = env.pool;
+    let (dai_mint, tai_mint) = (env.dai_mint, env.tai_mint);
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let loan = env.active_loan(&borrower).await;
+    let half = loan.borrowed_amount / 2;
+
+    // the interest of the first hour is charged on the whole principal
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    let first_at = env.unix_timestamp().await;
+    let (first_interest, first_fee) = loan
+        .calculate_interest_and_fee(first_at - loan.started_at)
+        .unwrap();
+    env.mint_to(&dai_mint, &wallet, first_interest).await;
+
+    env.repay_partial(&borrower, half).await.unwrap();
+
+    let first_income = first_interest - first_fee;
+    let partial = env.active_loan(&borrower).await;
+    assert_eq!(partial.outstanding_amount(), loan.borrowed_amount - half);
+    assert_eq!(partial.total_amount, loan.total_amount + first_income);
+    assert_eq!(partial.started_at, first_at);
+    assert_eq!(partial.expired_at(), loan.expired_at());
+    assert_eq!(env.balance(&pool, &borrower.nft_mint).await, 1);
+    assert_eq!(env.fee_vault_balance().await, first_fee);
+
+    // the interest of the second hour is charged on the rest only
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    let (second_interest, second_fee) = partial
+        .calculate_interest_and_fee(env.unix_timestamp().await - first_at)
+        .unwrap();
+    assert!(second_interest < first_interest);
+    env.mint_to(&dai_mint, &wallet, second_interest).await;
+
+    env.repay(&borrower).await.unwrap();
+
+    let income = first_income + second_interest - second_fee;
+    assert_eq!(env.balance(&wallet, &borrower.nft_mint).await, 1);
+    assert_eq!(env.balance(&wallet, &dai_mint).await, 0);
+    assert_eq!(env.fee_vault_balance().await, first_fee + second_fee);
+    assert_eq!(env.balance(&pool, &dai_mint).await, BID_PRICE + income);
+
+    // the lender returns the TAI of the whole loan
+    env.withdraw_locked_asset(&borrower, &lender, false)
+        .await
+        .unwrap();
+    assert_eq!(env.balance(&lender.pubkey(), &tai_mint).await, 0);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI + income
+    );
+}
+
+#[tokio::test]
+async fn partial_repayment_must_leave_some_principal() {
+    let mut env = TestEnv::new().await;
+
+    let borrower = env.new_borrower().await;
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid(&lender, &borrower.nft_mint, BID_PRICE, 1)
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let loan = env.active_loan(&borrower).await;
+
+    for amount in &[0, loan.borrowed_amount] {
+        assert_eq!(
+            env.repay_partial(&borrower, *amount).await.unwrap_err(),
+            taker_error(TakerError::InvalidRepayAmount)
+        );
+    }
+}
+
+#[tokio::test]
+async fn liquidate_a_partially_repayed_loan() {
+    let mut env = TestEnv::new().await;
+    let (pool, dai_mint) = (env.pool, env.dai_mint);
+
+    let borrower = env.new_borrower().await;
+    let wallet = borrower.wallet.pubkey();
+    let lender = env.new_lender(LENDER_DAI).await;
+
+    let terms = LoanTerms {
+        interest_rate: 100,
+        max_loan_duration: 2 * SECONDS_PER_HOUR,
+        mortgage_rate: 5000,
+        max_extension: 0,
+    };
+
+    env.deposit_nft(&borrower).await.unwrap();
+    env.place_bid_with_terms(&lender, &borrower.nft_mint, BID_PRICE, 1, Some(terms))
+        .await
+        .unwrap();
+    env.borrow(&borrower, &lender.pubkey(), BID_PRICE)
+        .await
+        .unwrap();
+    let loan = env.active_loan(&borrower).await;
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    let (interest, first_fee) = loan
+        .calculate_interest_and_fee(env.unix_timestamp().await - loan.started_at)
+        .unwrap();
+    env.mint_to(&dai_mint, &wallet, interest).await;
+    env.repay_partial(&borrower, loan.borrowed_amount / 2)
+        .await
+        .unwrap();
+    let partial = env.active_loan(&borrower).await;
+
+    env.advance_clock(2 * SECONDS_PER_HOUR).await;
+    env.liquidate(&borrower, &lender, false).await.unwrap();
+
+    // the lender keeps what the pool holds for the loan, less the fee over the rest of the loan
+    let (_, fee) = partial
+        .calculate_interest_and_fee(partial.duration)
+        .unwrap();
+    assert_eq!(env.balance(&lender.pubkey(), &borrower.nft_mint).await, 1);
+    assert_eq!(env.fee_vault_balance().await, first_fee + fee);
+    assert_eq!(
+        env.balance(&lender.pubkey(), &dai_mint).await,
+        LENDER_DAI - BID_PRICE + partial.total_amount - partial.outstanding_amount() - fee
+    );
+    assert_eq!(env.balance(&pool, &dai_mint).await, 0);
+}
@@ -0,0 +1,299 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{clone_keypair, taker_error, TestEnv};
+use solana_sdk::signature::Signer;
+use taker::{InterestCurve, KinkedInterest, LoanSettings, QueuedChange, SettingsCaps, TakerError};
+
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+#[tokio::test]
+async fn queued_change_applies_after_the_delay() {
+    let mut env = TestEnv::new().await;
+
+    env.change_settings_caps(SettingsCaps::UNBOUNDED, SECONDS_PER_HOUR)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.change_loan_settings(None, Some(300), None, None, None)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::SettingsChangeMustBeQueued)
+    );
+    assert_eq!(
+        env.execute_settings_change().await.unwrap_err(),
+        taker_error(TakerError::NoSettingsChangeQueued)
+    );
+
+    let before = env.pool_account().await.loan_settings();
+    env.propose_settings_change(None, Some(300), None, None, None)
+        .await
+        .unwrap();
+    let proposed_at = env.unix_timestamp().await;
+
+    // only queued so far
+    assert_eq!(env.pool_account().await.loan_settings(), before);
+    let settings_change = env.settings_change_account().await;
+    assert_eq!(settings_change.proposer(), env.settings_admin.pubkey());
+    assert_eq!(
+        settings_change.change(),
+        QueuedChange::Settings(LoanSettings {
+            interest_rate: 300,
+            ..before
+        })
+    );
+    assert_eq!(
+        settings_change.effective_at(),
+        proposed_at + SECONDS_PER_HOUR
+    );
+
+    env.skip_slots().await;
+    assert_eq!(
+        env.propose_settings_change(None, Some(400), None, None, None)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::SettingsChangeAlreadyQueued)
+    );
+    assert_eq!(
+        env.execute_settings_change().await.unwrap_err(),
+        taker_error(TakerError::SettingsTimelocked)
+    );
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    env.execute_settings_change().await.unwrap();
+
+    assert_eq!(env.pool_account().await.interest_rate, 300);
+    let settings_change = env.settings_change_address();
+    assert!(env.account(&settings_change).await.is_none());
+}
+
+#[tokio::test]
+async fn owner_cancels_the_queued_change() {
+    let mut env = TestEnv::new().await;
+    let owner = clone_keypair(&env.owner);
+    let stranger = env.new_wallet().await;
+
+    env.change_settings_caps(SettingsCaps::UNBOUNDED, SECONDS_PER_HOUR)
+        .await
+        .unwrap();
+    env.propose_settings_change(None, Some(300), None, None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        env.cancel_settings_change(&stranger).await.unwrap_err(),
+        taker_error(TakerError::NotAuhorized)
+    );
+    env.cancel_settings_change(&owner).await.unwrap();
+
+    let settings_change = env.settings_change_address();
+    assert!(env.account(&settings_change).await.is_none());
+    assert_eq!(env.pool_account().await.interest_rate, 100);
+
+    // a new change can be queued, the owner lowering the caps meanwhile blocks it
+    env.skip_slots().await;
+    env.propose_settings_change(None, Some(300), None, None, None)
+        .await
+        .unwrap();
+    env.change_settings_caps(
+        SettingsCaps {
+            max_interest_rate: 200,
+            ..SettingsCaps::UNBOUNDED
+        },
+        SECONDS_PER_HOUR,
+    )
+    .await
+    .unwrap();
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    assert_eq!(
+        env.execute_settings_change().await.unwrap_err(),
+        taker_error(TakerError::SettingAboveCap)
+    );
+}
+
+#[tokio::test]
+async fn liquidation_settings_and_interest_model_are_queued_too() {
+    let mut env = TestEnv::new().await;
+
+    env.change_settings_caps(
+        SettingsCaps {
+            max_grace_penalty_rate: 100,
+            max_jump_rate: 1000,
+            ..SettingsCaps::UNBOUNDED
+        },
+        SECONDS_PER_HOUR,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        env.change_liquidation_settings(Some(SECONDS_PER_HOUR), None, None, None)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::SettingsChangeMustBeQueued)
+    );
+    assert_eq!(
+        env.change_interest_model(InterestCurve::DailyCompound)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::SettingsChangeMustBeQueued)
+    );
+
+    let proposal = |grace_penalty_rate, interest_model| taker::instruction::ProposeSettingsChange {
+        incentive: None,
+        interest_rate: None,
+        service_fee_rate: None,
+        max_loan_duration: None,
+        mortgage_rate: None,
+        grace_period: Some(SECONDS_PER_HOUR),
+        grace_penalty_rate: Some(grace_penalty_rate),
+        auction_duration: None,
+        auction_start_rate: None,
+        interest_model: Some(interest_model),
+    };
+
+    // the caps of the owner bound them as the other settings
+    let kinked = |jump_rate| {
+        InterestCurve::Kinked(KinkedInterest {
+            kink: 8000,
+            jump_rate,
+        })
+    };
+    for (grace_penalty_rate, interest_model) in &[(200, kinked(500)), (100, kinked(2000))] {
+        assert_eq!(
+            env.propose_settings(proposal(*grace_penalty_rate, *interest_model))
+                .await
+                .unwrap_err(),
+            taker_error(TakerError::SettingAboveCap)
+        );
+    }
+
+    let before = env.pool_account().await.loan_settings();
+    env.propose_settings(proposal(100, kinked(1000)))
+        .await
+        .unwrap();
+    assert_eq!(env.pool_account().await.loan_settings(), before);
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    env.execute_settings_change().await.unwrap();
+
+    assert_eq!(
+        env.pool_account().await.loan_settings(),
+        LoanSettings {
+            grace_period: SECONDS_PER_HOUR,
+            grace_penalty_rate: 100,
+            interest_model: kinked(1000),
+            ..before
+        }
+    );
+}
+
+#[tokio::test]
+async fn lowering_the_delay_is_delayed() {
+    let mut env = TestEnv::new().await;
+
+    env.change_settings_caps(SettingsCaps::UNBOUNDED, SECONDS_PER_HOUR)
+        .await
+        .unwrap();
+    env.skip_slots().await;
+    assert_eq!(
+        env.change_settings_caps(SettingsCaps::UNBOUNDED, 0)
+            .await
+            .unwrap_err(),
+        taker_error(TakerError::SettingsChangeMustBeQueued)
+    );
+
+    // a longer delay applies right away
+    env.change_settings_caps(SettingsCaps::UNBOUNDED, 2 * SECONDS_PER_HOUR)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.pool_account().await.settings_delay,
+        2 * SECONDS_PER_HOUR
+    );
+
+    env.propose_caps_change(SettingsCaps::UNBOUNDED, 0)
+        .await
+        .unwrap();
+    let proposed_at = env.unix_timestamp().await;
+    let settings_change = env.settings_change_account().await;
+    assert_eq!(settings_change.proposer(), env.owner.pubkey());
+    assert_eq!(
+        settings_change.change(),
+        QueuedChange::Caps {
+            settings_caps: SettingsCaps::UNBOUNDED,
+            settings_delay: 0,
+        }
+    );
+    assert_eq!(
+        settings_change.effective_at(),
+        proposed_at + 2 * SECONDS_PER_HOUR
+    );
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    assert_eq!(
+        env.execute_settings_change().await.unwrap_err(),
+        taker_error(TakerError::SettingsTimelocked)
+    );
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    env.execute_settings_change().await.unwrap();
+    assert_eq!(env.pool_account().await.settings_delay, 0);
+
+    env.change_loan_settings(None, Some(300), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(env.pool_account().await.interest_rate, 300);
+}
+
+#[tokio::test]
+async fn raising_the_caps_is_delayed() {
+    let mut env = TestEnv::new().await;
+    let caps = SettingsCaps {
+        max_interest_rate: 200,
+        ..SettingsCaps::UNBOUNDED
+    };
+
+    env.change_settings_caps(caps, SECONDS_PER_HOUR)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.change_settings_caps(
+            SettingsCaps {
+                max_interest_rate: 300,
+                ..caps
+            },
+            SECONDS_PER_HOUR
+        )
+        .await
+        .unwrap_err(),
+        taker_error(TakerError::SettingsChangeMustBeQueued)
+    );
+
+    // lowering a cap applies right away
+    let lowered = SettingsCaps {
+        max_interest_rate: 150,
+        ..caps
+    };
+    env.change_settings_caps(lowered, SECONDS_PER_HOUR)
+        .await
+        .unwrap();
+    assert_eq!(env.pool_account().await.settings_caps, lowered);
+
+    let raised = SettingsCaps {
+        max_interest_rate: 300,
+        ..caps
+    };
+    env.propose_caps_change(raised, SECONDS_PER_HOUR)
+        .await
+        .unwrap();
+    assert_eq!(
+        env.execute_settings_change().await.unwrap_err(),
+        taker_error(TakerError::SettingsTimelocked)
+    );
+
+    env.advance_clock(SECONDS_PER_HOUR).await;
+    env.execute_settings_change().await.unwrap();
+    assert_eq!(env.pool_account().await.settings_caps, raised);
+}
@@ -1,14 +0,0 @@
-import * as anchor from '@project-serum/anchor';
-
-describe('aa', () => {
-
-  // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.Provider.env());
-
-  it('Is initialized!', async () => {
-    // Add your test here.
-    const program = anchor.workspace.Aa;
-    const tx = await program.rpc.initialize();
-    console.log("Your transaction signature", tx);
-  });
-});